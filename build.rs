@@ -0,0 +1,47 @@
+//! Generates the gRPC service stubs. Messages are hand-written `prost` types in `src/grpc/pb.rs`
+//! that mirror `proto/transactions.proto`, so the build does not need `protoc`.
+
+use tonic_build::manual::{Builder, Method, Service};
+
+fn unary(name: &str, route: &str, input: &str, output: &str) -> Method {
+    Method::builder()
+        .name(name)
+        .route_name(route)
+        .input_type(format!("crate::grpc::pb::{}", input))
+        .output_type(format!("crate::grpc::pb::{}", output))
+        .codec_path("tonic_prost::ProstCodec")
+        .build()
+}
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=proto/transactions.proto");
+    // Baked into `/health` when set.
+    println!("cargo:rerun-if-env-changed=GIT_COMMIT");
+
+    let service = Service::builder()
+        .name("TransactionService")
+        .package("transactions.v1")
+        .method(unary(
+            "create",
+            "Create",
+            "CreateTransactionRequest",
+            "CreateTransactionResponse",
+        ))
+        .method(unary("get", "Get", "GetTransactionRequest", "Transaction"))
+        .method(unary(
+            "list",
+            "List",
+            "ListTransactionsRequest",
+            "ListTransactionsResponse",
+        ))
+        .method(unary(
+            "update_status",
+            "UpdateStatus",
+            "UpdateStatusRequest",
+            "Transaction",
+        ))
+        .build();
+
+    Builder::new().compile(&[service]);
+}
@@ -0,0 +1,217 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use axum::extract::{MatchedPath, Request, State};
+use axum::middleware::Next;
+use axum::response::Response;
+
+use crate::api::jwt::Claims;
+use crate::error::ErrorDetail;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Maps request latency onto a fixed set of labels so log pipelines can aggregate without
+/// histogramming raw durations.
+#[derive(Debug, Clone)]
+pub struct LatencyBuckets {
+    bounds: Vec<(Duration, String)>,
+    overflow: String,
+}
+
+impl LatencyBuckets {
+    /// Buckets with explicit labels. `bounds` must be sorted ascending; a latency strictly below
+    /// a bound gets that bound's label, anything slower gets `overflow`.
+    pub fn new(bounds: Vec<(Duration, String)>, overflow: impl Into<String>) -> Self {
+        Self {
+            bounds,
+            overflow: overflow.into(),
+        }
+    }
+
+    /// Buckets labelled `<Nms` for each upper bound, with `>=Nms` for the overflow bucket.
+    pub fn from_millis(bounds: &[u64]) -> Self {
+        let overflow = match bounds.last() {
+            Some(last) => format!(">={}ms", last),
+            None => "all".into(),
+        };
+        let bounds = bounds
+            .iter()
+            .map(|ms| (Duration::from_millis(*ms), format!("<{}ms", ms)))
+            .collect();
+        Self::new(bounds, overflow)
+    }
+
+    pub fn label(&self, latency: Duration) -> &str {
+        self.bounds
+            .iter()
+            .find(|(bound, _)| latency < *bound)
+            .map_or(self.overflow.as_str(), |(_, label)| label.as_str())
+    }
+}
+
+impl Default for LatencyBuckets {
+    fn default() -> Self {
+        Self::from_millis(&[10, 50, 100, 500, 1000])
+    }
+}
+
+/// Emits one structured `access_log` event per request once the response is ready.
+pub async fn access_log(
+    State(buckets): State<Arc<LatencyBuckets>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let start = Instant::now();
+    let method = req.method().clone();
+    let path = req.uri().path().to_string();
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or_else(|| "unmatched".to_string(), |p| p.as_str().to_string());
+    let request_id = req
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("-")
+        .to_string();
+
+    let response = next.run(req).await;
+
+    let latency = start.elapsed();
+    let identity = response
+        .extensions()
+        .get::<Claims>()
+        .map_or("anonymous", |c| c.sub.as_str());
+    tracing::info!(
+        target: "access_log",
+        method = %method,
+        route = %route,
+        status = response.status().as_u16(),
+        latency_ms = latency.as_secs_f64() * 1000.0,
+        latency_bucket = buckets.label(latency),
+        request_id = %request_id,
+        identity = %identity,
+        "request completed"
+    );
+    if let Some(detail) = response.extensions().get::<ErrorDetail>() {
+        let status = response.status().as_u16();
+        if response.status().is_server_error() {
+            tracing::error!(
+                target: "api_error",
+                path = %path,
+                status,
+                error_type = detail.error_type,
+                reason = %detail.message,
+                reference = detail.reference.as_deref(),
+                "request failed"
+            );
+        } else {
+            tracing::warn!(
+                target: "api_error",
+                path = %path,
+                status,
+                error_type = detail.error_type,
+                reason = %detail.message,
+                "request rejected"
+            );
+        }
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::build_router;
+    use crate::domain::service::TransactionService;
+    use crate::storage::memory::InMemoryStorage;
+    use crate::test_support::Capture;
+    use axum::body::Body;
+    use tower::ServiceExt;
+
+    #[test]
+    fn labels_follow_bounds() {
+        let buckets = LatencyBuckets::from_millis(&[10, 100]);
+        assert_eq!(buckets.label(Duration::from_millis(3)), "<10ms");
+        assert_eq!(buckets.label(Duration::from_millis(10)), "<100ms");
+        assert_eq!(buckets.label(Duration::from_secs(2)), ">=100ms");
+    }
+
+    #[tokio::test]
+    async fn completed_request_is_logged() {
+        let capture = Capture::default();
+        let subscriber = tracing_subscriber::fmt()
+            .json()
+            .flatten_event(true)
+            .with_writer(capture.clone())
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let app = build_router(TransactionService::new(InMemoryStorage::new()));
+        let resp = app
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/api/v1/transactions/{}", uuid::Uuid::nil()))
+                    .header(REQUEST_ID_HEADER, "req-42")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 404);
+
+        let line = capture
+            .json_lines()
+            .into_iter()
+            .find(|v| v["target"] == "access_log")
+            .expect("access log line");
+        assert_eq!(line["method"], "GET");
+        assert_eq!(line["route"], "/api/v1/transactions/{id}");
+        assert_eq!(line["status"], 404);
+        assert_eq!(line["request_id"], "req-42");
+        assert_eq!(line["identity"], "anonymous");
+        assert_eq!(line["latency_bucket"], "<10ms");
+    }
+
+    #[tokio::test]
+    async fn validation_failure_is_logged_once_at_warn() {
+        let capture = Capture::default();
+        let subscriber = tracing_subscriber::fmt()
+            .json()
+            .flatten_event(true)
+            .with_writer(capture.clone())
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let app = build_router(TransactionService::new(InMemoryStorage::new()));
+        let payload = serde_json::json!({
+            "idempotency_key": "bad-amount",
+            "amount": -5.0,
+            "currency": "USD",
+            "description": "Negative"
+        });
+        let resp = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/transactions")
+                    .header("content-type", "application/json")
+                    .body(Body::from(payload.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 400);
+
+        let lines: Vec<serde_json::Value> = capture
+            .json_lines()
+            .into_iter()
+            .filter(|v| v["target"] == "api_error")
+            .collect();
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0]["level"], "WARN");
+        assert_eq!(lines[0]["path"], "/api/v1/transactions");
+        assert_eq!(lines[0]["error_type"], "VALIDATION_FAILED");
+        assert!(lines[0]["reason"].as_str().unwrap().contains("Validation error"));
+    }
+}
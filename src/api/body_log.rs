@@ -0,0 +1,154 @@
+use axum::body::{Body, Bytes};
+use axum::extract::{Request, State};
+use axum::http::{header, HeaderMap};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+
+use crate::api::handlers::NDJSON;
+use crate::error::AppError;
+
+/// Longest body prefix written to the log; the rest is replaced by a byte count.
+pub const MAX_LOGGED_BODY_BYTES: usize = 4 * 1024;
+
+/// Debug aid: logs request and response bodies at `debug`. Bodies are buffered and handed on
+/// unchanged. Streaming responses (SSE, NDJSON) pass through unlogged so they keep streaming.
+/// Request bodies over `max_body_bytes` are rejected here, since buffering reads them whole.
+pub async fn log_bodies(State(max_body_bytes): State<usize>, req: Request, next: Next) -> Response {
+    if !tracing::enabled!(target: "body_log", tracing::Level::DEBUG) {
+        return next.run(req).await;
+    }
+    let (parts, body) = req.into_parts();
+    let Ok(bytes) = axum::body::to_bytes(body, max_body_bytes).await else {
+        return AppError::PayloadTooLarge {
+            limit: max_body_bytes,
+        }
+        .into_response();
+    };
+    tracing::debug!(
+        target: "body_log",
+        method = %parts.method,
+        uri = %parts.uri,
+        headers = ?redacted(&parts.headers),
+        body = %preview(&bytes),
+        "request body"
+    );
+    let response = next.run(Request::from_parts(parts, Body::from(bytes))).await;
+
+    if is_streaming(response.headers()) {
+        return response;
+    }
+    let (parts, body) = response.into_parts();
+    let bytes = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            return AppError::Internal(format!("Failed to read response body: {}", e))
+                .into_response()
+        }
+    };
+    tracing::debug!(
+        target: "body_log",
+        status = parts.status.as_u16(),
+        body = %preview(&bytes),
+        "response body"
+    );
+    Response::from_parts(parts, Body::from(bytes))
+}
+
+fn redacted(headers: &HeaderMap) -> HeaderMap {
+    let mut headers = headers.clone();
+    if headers.contains_key(header::AUTHORIZATION) {
+        headers.insert(header::AUTHORIZATION, "[redacted]".parse().unwrap());
+    }
+    headers
+}
+
+fn preview(bytes: &Bytes) -> String {
+    let shown = &bytes[..bytes.len().min(MAX_LOGGED_BODY_BYTES)];
+    let mut text = String::from_utf8_lossy(shown).into_owned();
+    if bytes.len() > MAX_LOGGED_BODY_BYTES {
+        text.push_str(&format!("... ({} bytes total)", bytes.len()));
+    }
+    text
+}
+
+fn is_streaming(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("text/event-stream") || v.starts_with(NDJSON))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::{build_router_with_config, ApiConfig};
+    use crate::domain::service::TransactionService;
+    use crate::storage::memory::InMemoryStorage;
+    use crate::test_support::Capture;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn create_body_is_logged_with_authorization_redacted() {
+        let capture = Capture::default();
+        let subscriber = tracing_subscriber::fmt()
+            .json()
+            .flatten_event(true)
+            .with_max_level(tracing::Level::DEBUG)
+            .with_writer(capture.clone())
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let config = ApiConfig {
+            log_bodies: true,
+            ..ApiConfig::default()
+        };
+        let app = build_router_with_config(TransactionService::new(InMemoryStorage::new()), config);
+        let payload = serde_json::json!({
+            "idempotency_key": "logged-1",
+            "amount": 12.0,
+            "currency": "USD",
+            "description": "Log me"
+        })
+        .to_string();
+        let resp = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/transactions")
+                    .header("content-type", "application/json")
+                    .header("authorization", "Bearer secret-token")
+                    .body(Body::from(payload.clone()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 201);
+
+        let lines: Vec<serde_json::Value> = capture
+            .json_lines()
+            .into_iter()
+            .filter(|v| v["target"] == "body_log")
+            .collect();
+        let request = lines
+            .iter()
+            .find(|v| v["message"] == "request body")
+            .expect("request body line");
+        assert_eq!(request["body"], payload);
+        let headers = request["headers"].as_str().unwrap();
+        assert!(headers.contains("[redacted]"));
+        assert!(!headers.contains("secret-token"));
+        let response = lines
+            .iter()
+            .find(|v| v["message"] == "response body")
+            .expect("response body line");
+        assert_eq!(response["status"], 201);
+        assert!(response["body"].as_str().unwrap().contains("logged-1"));
+    }
+
+    #[test]
+    fn long_bodies_are_truncated() {
+        let body = Bytes::from(vec![b'a'; MAX_LOGGED_BODY_BYTES + 10]);
+        let text = preview(&body);
+        assert!(text.ends_with(&format!("... ({} bytes total)", MAX_LOGGED_BODY_BYTES + 10)));
+    }
+}
@@ -0,0 +1,135 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::domain::models::{CreateTransactionRequest, Currency};
+use crate::error::AppError;
+
+/// Columns accepted by `POST /transactions/import`, matched by header name. `tags` is a
+/// `;`-separated list.
+#[derive(Debug, Deserialize)]
+struct ImportRow {
+    idempotency_key: Option<String>,
+    amount: f64,
+    currency: Option<Currency>,
+    description: String,
+    expires_at: Option<DateTime<Utc>>,
+    tags: Option<String>,
+}
+
+impl From<ImportRow> for CreateTransactionRequest {
+    fn from(row: ImportRow) -> Self {
+        Self {
+            idempotency_key: row.idempotency_key,
+            tenant_id: None,
+            amount: row.amount,
+            currency: row.currency,
+            description: row.description,
+            expires_at: row.expires_at,
+            execute_at: None,
+            tags: row
+                .tags
+                .as_deref()
+                .unwrap_or_default()
+                .split(';')
+                .map(str::trim)
+                .filter(|tag| !tag.is_empty())
+                .map(String::from)
+                .collect(),
+        }
+    }
+}
+
+/// A data row's line number in the file (the header is line 1) and what it parsed into.
+pub type ParsedRow = (u64, Result<CreateTransactionRequest, AppError>);
+
+/// Parses every data row of `body`. A malformed row yields an error for that row only; only an
+/// unreadable header fails the whole import.
+pub fn parse(body: &str) -> Result<Vec<ParsedRow>, AppError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(body.as_bytes());
+    let headers = reader
+        .headers()
+        .map_err(|e| AppError::Validation(format!("Invalid CSV header: {}", e)))?
+        .clone();
+
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let row = match record {
+            Ok(record) => {
+                let line = record.position().map_or(0, |p| p.line());
+                let parsed = record
+                    .deserialize::<ImportRow>(Some(&headers))
+                    .map(CreateTransactionRequest::from)
+                    .map_err(|e| AppError::Validation(format!("Invalid row: {}", e)));
+                (line, parsed)
+            }
+            Err(e) => {
+                let line = e.position().map_or(0, |p| p.line());
+                (line, Err(AppError::Validation(format!("Invalid row: {}", e))))
+            }
+        };
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RowOutcome {
+    Created,
+    /// The idempotency key matched an existing transaction.
+    Skipped,
+    Error,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportRowResult {
+    pub row: u64,
+    pub outcome: RowOutcome,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<Uuid>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ImportSummary {
+    pub created: usize,
+    pub skipped: usize,
+    pub errored: usize,
+    pub rows: Vec<ImportRowResult>,
+}
+
+impl ImportSummary {
+    pub fn record(&mut self, row: u64, outcome: Result<(Uuid, bool), AppError>) {
+        let result = match outcome {
+            Ok((id, created)) => {
+                let outcome = if created {
+                    self.created += 1;
+                    RowOutcome::Created
+                } else {
+                    self.skipped += 1;
+                    RowOutcome::Skipped
+                };
+                ImportRowResult {
+                    row,
+                    outcome,
+                    id: Some(id),
+                    error: None,
+                }
+            }
+            Err(e) => {
+                self.errored += 1;
+                ImportRowResult {
+                    row,
+                    outcome: RowOutcome::Error,
+                    id: None,
+                    error: Some(e.to_body()["error"].take()),
+                }
+            }
+        };
+        self.rows.push(result);
+    }
+}
@@ -0,0 +1,105 @@
+use std::convert::Infallible;
+use std::sync::Arc;
+
+use axum::extract::rejection::{JsonRejection, QueryRejection};
+use axum::extract::{FromRef, FromRequest, FromRequestParts, OptionalFromRequest, Query, Request};
+use axum::http::request::Parts;
+use axum::http::StatusCode;
+use axum::Json;
+
+use crate::api::jwt::Claims;
+use crate::api::ApiConfig;
+use crate::error::AppError;
+
+/// `Json` request body whose rejections use the standard error body. Deserialization failures
+/// become a 400 naming the offending field and position; a body that is not declared as JSON
+/// (MessagePack is converted before this runs) is a 415.
+pub struct JsonBody<T>(pub T);
+
+fn rejection_to_error(rejection: JsonRejection, config: &ApiConfig) -> AppError {
+    match rejection {
+        JsonRejection::MissingJsonContentType(_) => AppError::UnsupportedMediaType(
+            "expected `Content-Type: application/json` or `application/msgpack`".into(),
+        ),
+        other if other.status() == StatusCode::PAYLOAD_TOO_LARGE => AppError::PayloadTooLarge {
+            limit: config.max_body_bytes,
+        },
+        other => AppError::Validation(other.body_text()),
+    }
+}
+
+impl<T, S> FromRequest<S> for JsonBody<T>
+where
+    Json<T>: FromRequest<S, Rejection = JsonRejection>,
+    Arc<ApiConfig>: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        Json::<T>::from_request(req, state)
+            .await
+            .map(|Json(value)| Self(value))
+            .map_err(|rejection| rejection_to_error(rejection, &Arc::from_ref(state)))
+    }
+}
+
+/// Absent when the request has no `Content-Type`, for endpoints whose body is optional.
+impl<T, S> OptionalFromRequest<S> for JsonBody<T>
+where
+    Json<T>: OptionalFromRequest<S, Rejection = JsonRejection>,
+    Arc<ApiConfig>: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Option<Self>, Self::Rejection> {
+        <Json<T> as OptionalFromRequest<S>>::from_request(req, state)
+            .await
+            .map(|body| body.map(|Json(value)| Self(value)))
+            .map_err(|rejection| rejection_to_error(rejection, &Arc::from_ref(state)))
+    }
+}
+
+/// `Query` whose rejections use the standard error body, so a bad parameter such as an unknown
+/// currency is a 400 naming the problem.
+pub struct QueryParams<T>(pub T);
+
+impl<T, S> FromRequestParts<S> for QueryParams<T>
+where
+    Query<T>: FromRequestParts<S, Rejection = QueryRejection>,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        Query::<T>::from_request_parts(parts, state)
+            .await
+            .map(|Query(value)| Self(value))
+            .map_err(|rejection| AppError::Validation(rejection.body_text()))
+    }
+}
+
+pub const TENANT_HEADER: &str = "x-tenant-id";
+
+/// Scope for idempotency keys: the token subject when authentication is configured, otherwise
+/// the `X-Tenant-Id` header. Authenticated callers cannot pick a tenant via the header.
+pub struct Tenant(pub Option<String>);
+
+impl<S: Send + Sync> FromRequestParts<S> for Tenant {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        if let Some(claims) = parts.extensions.get::<Claims>() {
+            return Ok(Self(Some(claims.sub.clone())));
+        }
+        let tenant = parts
+            .headers
+            .get(TENANT_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(str::to_owned);
+        Ok(Self(tenant))
+    }
+}
@@ -1,53 +1,639 @@
-use axum::extract::{Path, Query, State};
-use axum::http::StatusCode;
-use axum::response::IntoResponse;
+use std::convert::Infallible;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Instant;
+
+use axum::body::{Body, Bytes};
+use axum::extract::{OriginalUri, Path, State};
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
 use axum::Json;
+use futures_util::{stream, Stream, StreamExt};
+use serde::Deserialize;
+use tokio::sync::broadcast::error::RecvError;
 use uuid::Uuid;
 
-use crate::api::responses::ApiResponse;
-use crate::domain::models::{CreateTransactionRequest, ListFilters, UpdateStatusRequest};
+use crate::api::csv_import::{self, ImportSummary};
+use crate::api::extract::{JsonBody, QueryParams, Tenant};
+use crate::api::jwt::{Admin, Read, RequireScope, Write};
+use crate::api::load_shed::InFlight;
+use crate::api::msgpack;
+use crate::api::replay::ReplayCache;
+use crate::api::responses::{
+    ApiResponse, BatchItemResult, FieldSelection, PageLinks, ResponseMeta,
+};
+use crate::api::ApiConfig;
+use crate::domain::models::{
+    amount_format, timestamp_precision, AddNoteRequest, BalanceFilters, BatchCreateRequest,
+    BatchGetRequest, CreateTransactionRequest, Currency, CurrencyState, Facet, Facets, ListFilters,
+    SetCurrencyEnabledRequest, SetExchangeRateRequest, StatusBatchRequest, Transaction,
+    UpdateAmountRequest, UpdateStatusRequest, UpdateTransactionRequest, VoidRequest,
+};
 use crate::domain::service::TransactionService;
+use crate::domain::state_machine::{GraphFormat, TransitionGraph};
 use crate::error::AppError;
+use crate::storage::deadline::Deadline;
 use crate::storage::Storage;
 
-pub async fn health() -> impl IntoResponse {
-    Json(serde_json::json!({ "status": "ok" }))
+/// Liveness plus build info. `git_commit` comes from a `GIT_COMMIT` variable set at build time
+/// and is null when the build did not provide one.
+pub async fn health(State(started_at): State<Instant>) -> impl IntoResponse {
+    Json(serde_json::json!({
+        "status": "ok",
+        "version": env!("CARGO_PKG_VERSION"),
+        "git_commit": option_env!("GIT_COMMIT"),
+        "uptime_seconds": started_at.elapsed().as_secs(),
+    }))
+}
+
+/// Readiness for load balancers: 503 while the in-flight limit is reached, so traffic is routed
+/// elsewhere before requests start being shed, or while storage fails its health check. A
+/// check still running after `health_check_timeout` counts as failed.
+pub async fn ready<S: Storage>(
+    State(svc): State<TransactionService<S>>,
+    State(config): State<Arc<ApiConfig>>,
+    State(in_flight): State<Arc<InFlight>>,
+) -> impl IntoResponse {
+    let check = tokio::time::timeout(config.health_check_timeout, svc.health_check()).await;
+    let storage = match check {
+        Ok(Ok(())) => "ok",
+        Ok(Err(e)) => {
+            tracing::warn!(error = %e, "storage health check failed");
+            "unavailable"
+        }
+        Err(_) => {
+            tracing::warn!(timeout = ?config.health_check_timeout, "storage health check hung");
+            "timed_out"
+        }
+    };
+    let state = if storage != "ok" {
+        "storage_unavailable"
+    } else if in_flight.at_capacity() {
+        "at_capacity"
+    } else {
+        "ready"
+    };
+    let status = if state == "ready" {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    let body = Json(serde_json::json!({
+        "status": state,
+        "storage": storage,
+        "in_flight": in_flight.current(),
+        "max_in_flight": in_flight.limit(),
+    }));
+    (status, body)
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct CreateParams {
+    /// Validate and check idempotency without persisting.
+    #[serde(default)]
+    dry_run: bool,
 }
 
 pub async fn create_transaction<S: Storage>(
     State(svc): State<TransactionService<S>>,
-    Json(req): Json<CreateTransactionRequest>,
-) -> Result<impl IntoResponse, AppError> {
+    State(config): State<Arc<ApiConfig>>,
+    State(replays): State<Arc<ReplayCache>>,
+    _scope: RequireScope<Write>,
+    Tenant(tenant): Tenant,
+    QueryParams(params): QueryParams<CreateParams>,
+    JsonBody(mut req): JsonBody<CreateTransactionRequest>,
+) -> Result<Response, AppError> {
+    req.tenant_id = tenant.clone();
+    if params.dry_run {
+        let txn = svc.create_dry_run(req).await?;
+        return Ok(Json(ApiResponse::new(txn)).into_response());
+    }
+
+    // The fingerprint covers the amount format too, since it changes the serialized body.
+    let cacheable = match &req.idempotency_key {
+        Some(key) if replays.is_enabled() => {
+            let fingerprint = serde_json::to_vec(&(amount_format::as_strings(), &req))
+                .map_err(|e| AppError::Internal(format!("Failed to fingerprint request: {}", e)))?;
+            Some((key.clone(), fingerprint))
+        }
+        _ => None,
+    };
+    if let Some((key, fingerprint)) = &cacheable {
+        if let Some(hit) = replays.get(tenant.as_deref(), key, fingerprint) {
+            let base_path = config.base_path.as_deref();
+            return Ok(create_response(base_path, hit.transaction_id, false, hit.body));
+        }
+    }
+
     let (txn, created) = svc.create(req).await?;
+    let body = serde_json::to_vec(&ApiResponse::new(&txn))
+        .map_err(|e| AppError::Internal(format!("Failed to serialize response: {}", e)))?;
+    let body = Bytes::from(body);
+    if let Some((key, fingerprint)) = cacheable {
+        let key_lifetime = svc
+            .idempotency_remaining(&txn)
+            .map(|left| left.to_std().unwrap_or_default());
+        replays.put(tenant.as_deref(), &key, txn.id, fingerprint, body.clone(), key_lifetime);
+    }
+    Ok(create_response(config.base_path.as_deref(), txn.id, created, body))
+}
+
+/// `201` with the new transaction, or `200` marked as a replay when the key was seen before.
+/// `Location` starts with `base_path`, the prefix the router is mounted under.
+fn create_response(base_path: Option<&str>, id: Uuid, created: bool, body: Bytes) -> Response {
     let status = if created {
         StatusCode::CREATED
     } else {
         StatusCode::OK
     };
-    Ok((status, Json(ApiResponse::new(txn))))
+    let location = format!("{}/api/v1/transactions/{}", base_path.unwrap_or_default(), id);
+    let mut response = (
+        status,
+        [
+            (header::LOCATION, location),
+            (header::CONTENT_TYPE, "application/json".to_string()),
+        ],
+        body,
+    )
+        .into_response();
+    if !created {
+        response
+            .headers_mut()
+            .insert(IDEMPOTENCY_REPLAYED_HEADER, HeaderValue::from_static("true"));
+    }
+    response
+}
+
+/// Set on a create answered from an earlier request with the same idempotency key.
+pub const IDEMPOTENCY_REPLAYED_HEADER: &str = "idempotency-replayed";
+
+pub(crate) const NDJSON: &str = "application/x-ndjson";
+
+/// Per-request settings and deadline, captured while the handler runs. Streamed bodies are
+/// polled after the middleware scopes have exited, so each item re-enters them with `run`.
+#[derive(Debug, Clone, Copy)]
+struct RequestScope {
+    amounts_as_strings: bool,
+    whole_second_timestamps: bool,
+    deadline: Option<Deadline>,
+}
+
+impl RequestScope {
+    fn current() -> Self {
+        Self {
+            amounts_as_strings: amount_format::as_strings(),
+            whole_second_timestamps: timestamp_precision::whole_seconds(),
+            deadline: Deadline::current(),
+        }
+    }
+
+    async fn run<F: Future>(self, fut: F) -> F::Output {
+        let fut = timestamp_precision::scope(self.whole_second_timestamps, fut);
+        let fut = amount_format::scope(self.amounts_as_strings, fut);
+        match self.deadline {
+            Some(deadline) => deadline.scope(fut).await,
+            None => fut.await,
+        }
+    }
+}
+
+/// Creates each item independently. Large batches (or clients asking for NDJSON) get one result
+/// line per item as it completes, so neither side has to buffer the whole response.
+pub async fn create_transactions_batch<S: Storage + Clone>(
+    State(svc): State<TransactionService<S>>,
+    State(config): State<Arc<ApiConfig>>,
+    _scope: RequireScope<Write>,
+    Tenant(tenant): Tenant,
+    headers: HeaderMap,
+    JsonBody(mut req): JsonBody<BatchCreateRequest>,
+) -> Response {
+    for item in &mut req.items {
+        item.tenant_id = tenant.clone();
+    }
+    let wants_ndjson = headers
+        .get(header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.contains(NDJSON));
+
+    if wants_ndjson || req.items.len() > config.batch_stream_threshold {
+        let scope = RequestScope::current();
+        let lines = stream::iter(req.items.into_iter().enumerate()).then(move |(index, item)| {
+            let svc = svc.clone();
+            scope.run(async move {
+                let result = BatchItemResult::from_outcome(index, svc.create(item).await);
+                serde_json::to_vec(&result).map(|mut line| {
+                    line.push(b'\n');
+                    Bytes::from(line)
+                })
+            })
+        });
+        return ([(header::CONTENT_TYPE, NDJSON)], Body::from_stream(lines)).into_response();
+    }
+
+    let mut results = Vec::with_capacity(req.items.len());
+    for (index, item) in req.items.into_iter().enumerate() {
+        results.push(BatchItemResult::from_outcome(index, svc.create(item).await));
+    }
+    Json(ApiResponse::new(results)).into_response()
+}
+
+/// Creates one transaction per CSV row, in file order. Rows that fail to parse or validate are
+/// reported with their line number and do not stop the rest of the import.
+pub async fn import_transactions<S: Storage>(
+    State(svc): State<TransactionService<S>>,
+    _scope: RequireScope<Write>,
+    Tenant(tenant): Tenant,
+    body: String,
+) -> Result<Json<ApiResponse<ImportSummary>>, AppError> {
+    let mut summary = ImportSummary::default();
+    for (line, parsed) in csv_import::parse(&body)? {
+        let outcome = match parsed {
+            Ok(mut req) => {
+                req.tenant_id = tenant.clone();
+                svc.create(req).await.map(|(txn, created)| (txn.id, created))
+            }
+            Err(e) => Err(e),
+        };
+        summary.record(line, outcome);
+    }
+    Ok(Json(ApiResponse::new(summary)))
+}
+
+/// Strong validator for one representation of a transaction. `version` changes on every
+/// mutation; a field selection, string amounts and MessagePack each give a different body, so
+/// they are part of the tag too.
+fn etag(txn: &Transaction, selection: &FieldSelection, msgpack: bool) -> String {
+    let mut tag = format!("{}-{}", txn.id, txn.version);
+    if let Some(fields) = selection.key() {
+        tag.push_str(";fields=");
+        tag.push_str(&fields);
+    }
+    if amount_format::as_strings() {
+        tag.push_str(";amounts=string");
+    }
+    if msgpack {
+        tag.push_str(";msgpack");
+    }
+    format!("\"{}\"", tag)
+}
+
+/// Request headers that pick between representations of the same transaction.
+const VARY_REPRESENTATION: &str = "Accept, X-Amount-Format";
+
+/// Whether an `If-None-Match` header value matches `etag`. Weak comparison, per RFC 9110.
+fn if_none_match(header: &str, etag: &str) -> bool {
+    header
+        .split(',')
+        .map(|candidate| candidate.trim())
+        .any(|candidate| candidate == "*" || candidate.trim_start_matches("W/") == etag)
 }
 
 pub async fn get_transaction<S: Storage>(
     State(svc): State<TransactionService<S>>,
+    _scope: RequireScope<Read>,
     Path(id): Path<Uuid>,
-) -> Result<impl IntoResponse, AppError> {
+    QueryParams(selection): QueryParams<FieldSelection>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
     let txn = svc.get(id).await?;
+    let etag = etag(&txn, &selection, msgpack::lists_msgpack(headers.get(header::ACCEPT)));
+    let vary = (header::VARY, VARY_REPRESENTATION.to_string());
+    let not_modified = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| if_none_match(v, &etag));
+    if not_modified {
+        return Ok((StatusCode::NOT_MODIFIED, [(header::ETAG, etag), vary]).into_response());
+    }
+    let body = Json(ApiResponse::new(selection.apply(&txn)));
+    Ok(([(header::ETAG, etag), vary], body).into_response())
+}
+
+/// Server-Sent Events feed of every change to a transaction. Each event is named after its kind and
+/// carries the transaction as JSON. Subscribers that fall behind skip the events they missed.
+pub async fn stream_transactions<S: Storage>(
+    State(svc): State<TransactionService<S>>,
+    _scope: RequireScope<Read>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let scope = RequestScope::current();
+    let events = stream::unfold(svc.subscribe(), move |mut rx| async move {
+        let sse = match rx.recv().await {
+            Ok(event) => scope
+                .run(async {
+                    Event::default()
+                        .event(event.kind.as_str())
+                        .id(event.sequence.to_string())
+                        .json_data(&event.transaction)
+                        .unwrap_or_else(|_| Event::default().comment("unserializable event"))
+                })
+                .await,
+            Err(RecvError::Lagged(skipped)) => {
+                tracing::warn!(skipped, "event stream subscriber lagged");
+                Event::default()
+                    .event("lagged")
+                    .data(serde_json::json!({ "skipped": skipped }).to_string())
+            }
+            Err(RecvError::Closed) => return None,
+        };
+        Some((Ok(sse), rx))
+    });
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+pub async fn get_transaction_by_key<S: Storage>(
+    State(svc): State<TransactionService<S>>,
+    _scope: RequireScope<Read>,
+    Tenant(tenant): Tenant,
+    Path(key): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    let txn = svc.get_by_key(tenant.as_deref(), &key).await?;
     Ok(Json(ApiResponse::new(txn)))
 }
 
+/// How list items are rendered: the `?fields=` selection plus list-only options.
+#[derive(Debug, Default, Deserialize)]
+pub struct ListViewParams {
+    #[serde(flatten)]
+    selection: FieldSelection,
+    /// Cut each description to this many characters, marking the cut with `…`. Only affects
+    /// the response; single fetches always return the full text.
+    truncate_description: Option<usize>,
+}
+
+fn truncate_description(description: &mut String, max_chars: usize) {
+    if let Some((cut, _)) = description.char_indices().nth(max_chars) {
+        description.truncate(cut);
+        description.push('…');
+    }
+}
+
 pub async fn list_transactions<S: Storage>(
     State(svc): State<TransactionService<S>>,
-    Query(filters): Query<ListFilters>,
+    State(config): State<Arc<ApiConfig>>,
+    _scope: RequireScope<Read>,
+    OriginalUri(uri): OriginalUri,
+    headers: HeaderMap,
+    QueryParams(mut filters): QueryParams<ListFilters>,
+    QueryParams(view): QueryParams<ListViewParams>,
+) -> Result<impl IntoResponse, AppError> {
+    if filters.limit.is_none() && filters.page.is_none() && filters.per_page.is_none() {
+        filters.limit = Some(config.default_list_limit);
+    }
+    let limit = filters.limit;
+    // Status counts always come back; they ride on the status facet, which storage counts in
+    // the same pass that filters. It is only echoed under `facets` when asked for.
+    let status_facet_requested = filters.facets.contains(&Facet::Status);
+    if !status_facet_requested {
+        filters.facets.push(Facet::Status);
+    }
+    let mut result = svc.list(filters).await?;
+    let mut facets = result.facets.take().unwrap_or_default();
+    let status_counts = if status_facet_requested {
+        facets.status.clone()
+    } else {
+        facets.status.take()
+    };
+    let facets = (facets != Facets::default()).then_some(facets);
+    let has_more = result.next_cursor.is_some()
+        || result.page.is_some_and(|p| p.page < p.total_pages);
+    let next_cursor = result.next_cursor.map(|c| c.to_string());
+    let origin = match &config.public_base_url {
+        Some(base) => base.clone(),
+        None => headers
+            .get(header::HOST)
+            .and_then(|host| host.to_str().ok())
+            .map(|host| format!("http://{}", host))
+            .unwrap_or_default(),
+    };
+    let links = PageLinks::new(&origin, &uri, next_cursor.as_deref(), result.page);
+    let meta = ResponseMeta {
+        total: Some(result.total),
+        limit,
+        next_cursor,
+        status_counts: Some(status_counts.unwrap_or_default()),
+        facets,
+        page: result.page.map(|p| p.page),
+        per_page: result.page.map(|p| p.per_page),
+        total_pages: result.page.map(|p| p.total_pages),
+        has_more: Some(has_more),
+        links: Some(links),
+    };
+    if let Some(max_chars) = view.truncate_description {
+        for txn in &mut result.items {
+            truncate_description(&mut txn.description, max_chars);
+        }
+    }
+    let items: Vec<_> = result.items.iter().map(|txn| view.selection.apply(txn)).collect();
+    Ok(Json(ApiResponse::with_meta(items, meta)).into_response())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RecentParams {
+    #[serde(default = "default_recent")]
+    n: usize,
+}
+
+fn default_recent() -> usize {
+    10
+}
+
+pub async fn recent_transactions<S: Storage>(
+    State(svc): State<TransactionService<S>>,
+    _scope: RequireScope<Read>,
+    QueryParams(params): QueryParams<RecentParams>,
 ) -> Result<impl IntoResponse, AppError> {
-    let txns = svc.list(filters).await?;
+    let txns = svc.recent(params.n).await?;
     Ok(Json(ApiResponse::new(txns)))
 }
 
+pub async fn balance<S: Storage>(
+    State(svc): State<TransactionService<S>>,
+    _scope: RequireScope<Read>,
+    QueryParams(filters): QueryParams<BalanceFilters>,
+) -> Result<impl IntoResponse, AppError> {
+    let balances = svc.balance(filters).await?;
+    Ok(Json(ApiResponse::new(balances)))
+}
+
 pub async fn update_transaction_status<S: Storage>(
     State(svc): State<TransactionService<S>>,
+    _scope: RequireScope<Write>,
     Path(id): Path<Uuid>,
-    Json(req): Json<UpdateStatusRequest>,
+    JsonBody(req): JsonBody<UpdateStatusRequest>,
 ) -> Result<impl IntoResponse, AppError> {
     let txn = svc.update_status(id, req).await?;
     Ok(Json(ApiResponse::new(txn)))
 }
+
+pub async fn get_transactions_batch<S: Storage>(
+    State(svc): State<TransactionService<S>>,
+    _scope: RequireScope<Read>,
+    JsonBody(req): JsonBody<BatchGetRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let result = svc.get_many(req).await?;
+    Ok(Json(ApiResponse::new(result)))
+}
+
+pub async fn update_status_batch<S: Storage>(
+    State(svc): State<TransactionService<S>>,
+    _scope: RequireScope<Write>,
+    JsonBody(req): JsonBody<StatusBatchRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let results = svc.update_status_batch(req).await?;
+    Ok(Json(ApiResponse::new(results)))
+}
+
+pub async fn update_transaction<S: Storage>(
+    State(svc): State<TransactionService<S>>,
+    _scope: RequireScope<Write>,
+    Path(id): Path<Uuid>,
+    JsonBody(req): JsonBody<UpdateTransactionRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let txn = svc.update_description(id, req).await?;
+    Ok(Json(ApiResponse::new(txn)))
+}
+
+pub async fn update_transaction_amount<S: Storage>(
+    State(svc): State<TransactionService<S>>,
+    _scope: RequireScope<Write>,
+    Path(id): Path<Uuid>,
+    JsonBody(req): JsonBody<UpdateAmountRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let txn = svc.update_amount(id, req).await?;
+    Ok(Json(ApiResponse::new(txn)))
+}
+
+pub async fn add_note<S: Storage>(
+    State(svc): State<TransactionService<S>>,
+    _scope: RequireScope<Write>,
+    Path(id): Path<Uuid>,
+    JsonBody(req): JsonBody<AddNoteRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let note = svc.add_note(id, req).await?;
+    Ok((StatusCode::CREATED, Json(ApiResponse::new(note))))
+}
+
+pub async fn list_notes<S: Storage>(
+    State(svc): State<TransactionService<S>>,
+    _scope: RequireScope<Read>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let notes = svc.list_notes(id).await?;
+    Ok(Json(ApiResponse::new(notes)))
+}
+
+pub const DELETE_IDEMPOTENCY_KEY_HEADER: &str = "delete-idempotency-key";
+
+pub async fn void_transaction<S: Storage>(
+    State(svc): State<TransactionService<S>>,
+    _scope: RequireScope<Write>,
+    Path(id): Path<Uuid>,
+    req: Option<JsonBody<VoidRequest>>,
+) -> Result<impl IntoResponse, AppError> {
+    let req = req.map(|JsonBody(req)| req).unwrap_or_default();
+    let txn = svc.void(id, req).await?;
+    Ok(Json(ApiResponse::new(txn)))
+}
+
+pub async fn delete_transaction<S: Storage>(
+    State(svc): State<TransactionService<S>>,
+    _scope: RequireScope<Write>,
+    Tenant(tenant): Tenant,
+    Path(id): Path<Uuid>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, AppError> {
+    let key = headers
+        .get(DELETE_IDEMPOTENCY_KEY_HEADER)
+        .and_then(|v| v.to_str().ok());
+    let txn = svc.delete(id, tenant.as_deref(), key).await?;
+    Ok(Json(ApiResponse::new(txn)))
+}
+
+pub async fn restore_transaction<S: Storage>(
+    State(svc): State<TransactionService<S>>,
+    _scope: RequireScope<Write>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let txn = svc.restore(id).await?;
+    Ok(Json(ApiResponse::new(txn)))
+}
+
+pub async fn list_currencies<S: Storage>(
+    State(svc): State<TransactionService<S>>,
+    _scope: RequireScope<Admin>,
+) -> impl IntoResponse {
+    let states: Vec<CurrencyState> = Currency::ALL
+        .into_iter()
+        .map(|currency| CurrencyState {
+            currency,
+            enabled: svc.is_currency_enabled(currency),
+        })
+        .collect();
+    Json(ApiResponse::new(states))
+}
+
+/// JSON Schema for `POST /transactions` bodies, generated from the live validation settings.
+pub async fn create_transaction_schema<S: Storage>(
+    State(svc): State<TransactionService<S>>,
+    _scope: RequireScope<Read>,
+) -> impl IntoResponse {
+    Json(svc.create_request_schema())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StateMachineQuery {
+    #[serde(default)]
+    format: GraphFormat,
+}
+
+/// Exports the allowed status transitions as a JSON adjacency list or, with `?format=dot`, as a
+/// Graphviz digraph.
+pub async fn state_machine(
+    _scope: RequireScope<Admin>,
+    QueryParams(query): QueryParams<StateMachineQuery>,
+) -> Response {
+    let graph = TransitionGraph::current();
+    match query.format {
+        GraphFormat::Json => Json(ApiResponse::new(graph)).into_response(),
+        GraphFormat::Dot => (
+            [(header::CONTENT_TYPE, "text/vnd.graphviz")],
+            graph.to_dot(),
+        )
+            .into_response(),
+    }
+}
+
+pub async fn set_currency_enabled<S: Storage>(
+    State(svc): State<TransactionService<S>>,
+    _scope: RequireScope<Admin>,
+    Path(currency): Path<Currency>,
+    JsonBody(req): JsonBody<SetCurrencyEnabledRequest>,
+) -> impl IntoResponse {
+    svc.set_currency_enabled(currency, req.enabled);
+    tracing::info!(%currency, enabled = req.enabled, "currency toggled");
+    Json(ApiResponse::new(CurrencyState {
+        currency,
+        enabled: req.enabled,
+    }))
+}
+
+pub async fn set_exchange_rate<S: Storage>(
+    State(svc): State<TransactionService<S>>,
+    _scope: RequireScope<Admin>,
+    Path((base, quote)): Path<(Currency, Currency)>,
+    JsonBody(req): JsonBody<SetExchangeRateRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let rate = svc.set_rate(base, quote, req).await?;
+    tracing::info!(%base, %quote, rate = rate.rate, "exchange rate set");
+    Ok(Json(ApiResponse::new(rate)))
+}
+
+pub async fn get_exchange_rate<S: Storage>(
+    State(svc): State<TransactionService<S>>,
+    _scope: RequireScope<Read>,
+    Path((base, quote)): Path<(Currency, Currency)>,
+) -> Result<impl IntoResponse, AppError> {
+    let rate = svc.get_rate(base, quote).await?;
+    Ok(Json(ApiResponse::new(rate)))
+}
@@ -0,0 +1,131 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use axum::extract::{Request, State};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+
+use crate::error::AppError;
+
+/// Number of API requests currently being handled, with an optional ceiling above which new
+/// requests are turned away.
+#[derive(Debug, Default)]
+pub struct InFlight {
+    count: AtomicUsize,
+    limit: Option<usize>,
+}
+
+impl InFlight {
+    pub fn new(limit: Option<usize>) -> Self {
+        Self {
+            count: AtomicUsize::new(0),
+            limit,
+        }
+    }
+
+    pub fn current(&self) -> usize {
+        self.count.load(Ordering::SeqCst)
+    }
+
+    pub fn limit(&self) -> Option<usize> {
+        self.limit
+    }
+
+    pub fn at_capacity(&self) -> bool {
+        self.limit.is_some_and(|limit| self.current() >= limit)
+    }
+
+    fn enter(self: &Arc<Self>) -> InFlightGuard {
+        self.count.fetch_add(1, Ordering::SeqCst);
+        InFlightGuard(self.clone())
+    }
+}
+
+/// Decrements the count on drop, so requests that error or panic are still released.
+struct InFlightGuard(Arc<InFlight>);
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.0.count.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Counts the request for its whole lifetime and answers 503 instead of running it when the
+/// limit is already reached.
+pub async fn shed_load(
+    State(in_flight): State<Arc<InFlight>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let guard = in_flight.enter();
+    if in_flight.limit.is_some_and(|limit| in_flight.current() > limit) {
+        drop(guard);
+        return AppError::Unavailable("Server is at capacity; retry shortly".into())
+            .into_response();
+    }
+    let response = next.run(req).await;
+    drop(guard);
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::routing::get;
+    use axum::{middleware, Router};
+    use tokio::sync::Semaphore;
+    use tower::ServiceExt;
+
+    fn app(in_flight: Arc<InFlight>, release: Arc<Semaphore>) -> Router {
+        Router::new()
+            .route(
+                "/slow",
+                get(move || async move {
+                    let _permit = release.acquire().await.unwrap();
+                    "done"
+                }),
+            )
+            .route("/panic", get(|| async { panic!("handler bug") as &str }))
+            .layer(middleware::from_fn_with_state(in_flight, shed_load))
+    }
+
+    fn get_req(uri: &str) -> Request {
+        Request::builder().uri(uri).body(Body::empty()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn requests_over_the_limit_are_shed() {
+        let in_flight = Arc::new(InFlight::new(Some(2)));
+        let release = Arc::new(Semaphore::new(0));
+        let app = app(in_flight.clone(), release.clone());
+
+        let slow: Vec<_> = (0..2)
+            .map(|_| tokio::spawn(app.clone().oneshot(get_req("/slow"))))
+            .collect();
+        while in_flight.current() < 2 {
+            tokio::task::yield_now().await;
+        }
+        assert!(in_flight.at_capacity());
+
+        let shed = app.clone().oneshot(get_req("/slow")).await.unwrap();
+        assert_eq!(shed.status(), 503);
+        assert_eq!(in_flight.current(), 2);
+
+        release.add_permits(2);
+        for handle in slow {
+            assert_eq!(handle.await.unwrap().unwrap().status(), 200);
+        }
+        assert_eq!(in_flight.current(), 0);
+    }
+
+    #[tokio::test]
+    async fn panicking_handler_releases_its_slot() {
+        let in_flight = Arc::new(InFlight::new(Some(1)));
+        let app = app(in_flight.clone(), Arc::new(Semaphore::new(0)));
+
+        let result = tokio::spawn(app.oneshot(get_req("/panic"))).await;
+        assert!(result.is_err());
+        assert_eq!(in_flight.current(), 0);
+    }
+}
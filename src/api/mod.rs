@@ -1,26 +1,483 @@
+pub mod access_log;
+pub mod body_log;
+pub mod csv_import;
+pub mod extract;
 pub mod handlers;
+pub mod jwt;
+pub mod load_shed;
+pub mod msgpack;
+pub mod replay;
 pub mod responses;
 
-use axum::routing::{get, patch, post};
-use axum::Router;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
+use axum::extract::{DefaultBodyLimit, FromRef, Request, State};
+use axum::http::{header, Method, StatusCode, Uri};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, patch, post, put};
+use axum::{middleware, Json, Router};
+use serde_json::json;
+use tower_http::compression::predicate::{NotForContentType, Predicate, SizeAbove};
+use tower_http::compression::CompressionLayer;
+use tower_http::limit::RequestBodyLimitLayer;
+use tower_http::request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer};
+use uuid::Uuid;
+
+use crate::api::access_log::LatencyBuckets;
+use crate::api::jwt::JwtConfig;
+use crate::api::load_shed::InFlight;
+use crate::api::replay::ReplayCache;
+use crate::domain::models::{amount_format, timestamp_precision};
 use crate::domain::service::TransactionService;
+use crate::error::{AppError, ErrorDetail};
+use crate::storage::deadline::Deadline;
 use crate::storage::Storage;
 
+const DEFAULT_BATCH_STREAM_THRESHOLD: usize = 100;
+const DEFAULT_MAX_BODY_BYTES: usize = 64 * 1024;
+const DEFAULT_COMPRESSION_MIN_BYTES: u16 = 1024;
+const DEFAULT_LIST_LIMIT: usize = 100;
+const DEFAULT_HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// HTTP-layer settings that sit outside the domain service.
+#[derive(Clone)]
+pub struct ApiConfig {
+    /// When set, transaction routes require a valid bearer token.
+    pub jwt: Option<JwtConfig>,
+    /// Labels used for the `latency_bucket` field of the access log.
+    pub latency_buckets: LatencyBuckets,
+    /// Batch creates with more items than this stream NDJSON instead of a buffered array.
+    pub batch_stream_threshold: usize,
+    /// Largest accepted request body; bigger bodies get a 413.
+    pub max_body_bytes: usize,
+    /// Compress responses (gzip or brotli) for clients sending `Accept-Encoding`.
+    pub compression: bool,
+    /// Responses with a smaller `Content-Length` are sent uncompressed.
+    pub compression_min_bytes: u16,
+    /// Replace internal error messages with a generic one and a reference id; the full message
+    /// only goes to the log.
+    pub redact_internal_errors: bool,
+    /// API requests handled at once before further ones get a 503. `None` never sheds load.
+    pub max_in_flight: Option<usize>,
+    /// Log request and response bodies at `debug`. For debugging only: bodies are buffered.
+    pub log_bodies: bool,
+    /// Budget for each API request. Storage wrapped in `DeadlineStorage` gives up once it is
+    /// spent and the client gets a 504; items of a streamed batch report 504 on their own line.
+    /// `None` sets no deadline.
+    pub request_timeout: Option<Duration>,
+    /// Page size for a list that asks for neither `limit` nor `page`/`per_page`; more takes
+    /// explicit pagination. At most `MAX_PAGE_SIZE`.
+    pub default_list_limit: usize,
+    /// How long `/health/ready` waits on the storage health check before reporting 503.
+    pub health_check_timeout: Duration,
+    /// Keep each create response this long and answer idempotent replays with the same bytes
+    /// without asking the service. Capped by the service's idempotency TTL. `None` disables the
+    /// cache.
+    pub idempotency_response_ttl: Option<Duration>,
+    /// Serialize `amount`, `fee_amount` and `net_amount` as strings such as `"150.75"` unless a
+    /// request asks otherwise with `X-Amount-Format`.
+    pub amounts_as_strings: bool,
+    /// Serialize `created_at` and `updated_at` without fractional seconds.
+    pub whole_second_timestamps: bool,
+    /// Scheme and host clients reach the API at, e.g. `https://api.example.com`, used for the
+    /// absolute URLs in list `meta.links`. `None` uses `http://` and the request's `Host`.
+    pub public_base_url: Option<String>,
+    /// Prefix every route is mounted under, health checks included, e.g. `/payments` when a
+    /// gateway forwards that path unchanged. `Location` headers carry it too. `None` mounts at
+    /// the root.
+    pub base_path: Option<String>,
+}
+
+impl Default for ApiConfig {
+    fn default() -> Self {
+        Self {
+            jwt: None,
+            latency_buckets: LatencyBuckets::default(),
+            batch_stream_threshold: DEFAULT_BATCH_STREAM_THRESHOLD,
+            max_body_bytes: DEFAULT_MAX_BODY_BYTES,
+            compression: true,
+            compression_min_bytes: DEFAULT_COMPRESSION_MIN_BYTES,
+            redact_internal_errors: false,
+            max_in_flight: None,
+            log_bodies: false,
+            request_timeout: None,
+            default_list_limit: DEFAULT_LIST_LIMIT,
+            health_check_timeout: DEFAULT_HEALTH_CHECK_TIMEOUT,
+            idempotency_response_ttl: None,
+            amounts_as_strings: false,
+            whole_second_timestamps: false,
+            public_base_url: None,
+            base_path: None,
+        }
+    }
+}
+
+/// Router state. Handlers extract the pieces they need via `FromRef`.
+#[derive(Clone)]
+pub struct AppState<S: Storage> {
+    pub service: TransactionService<S>,
+    pub config: Arc<ApiConfig>,
+    /// When the router was built, i.e. server start. Reported as uptime by `/health`.
+    pub started_at: Instant,
+    pub in_flight: Arc<InFlight>,
+    pub replays: Arc<ReplayCache>,
+}
+
+impl<S: Storage + Clone> FromRef<AppState<S>> for TransactionService<S> {
+    fn from_ref(state: &AppState<S>) -> Self {
+        state.service.clone()
+    }
+}
+
+impl<S: Storage> FromRef<AppState<S>> for Arc<ApiConfig> {
+    fn from_ref(state: &AppState<S>) -> Self {
+        state.config.clone()
+    }
+}
+
+impl<S: Storage> FromRef<AppState<S>> for Instant {
+    fn from_ref(state: &AppState<S>) -> Self {
+        state.started_at
+    }
+}
+
+impl<S: Storage> FromRef<AppState<S>> for Arc<InFlight> {
+    fn from_ref(state: &AppState<S>) -> Self {
+        state.in_flight.clone()
+    }
+}
+
+impl<S: Storage> FromRef<AppState<S>> for Arc<ReplayCache> {
+    fn from_ref(state: &AppState<S>) -> Self {
+        state.replays.clone()
+    }
+}
+
 pub fn build_router<S: Storage + Clone>(service: TransactionService<S>) -> Router {
-    Router::new()
+    build_router_with_config(service, ApiConfig::default())
+}
+
+pub fn build_router_with_config<S: Storage + Clone>(
+    service: TransactionService<S>,
+    mut config: ApiConfig,
+) -> Router {
+    let jwt = config.jwt.clone();
+    let max_body_bytes = config.max_body_bytes;
+    let compression = config.compression.then_some(config.compression_min_bytes);
+    let redact_internal_errors = config.redact_internal_errors;
+    let log_bodies = config.log_bodies;
+    let request_timeout = config.request_timeout;
+    let amounts_as_strings = config.amounts_as_strings;
+    let whole_second_timestamps = config.whole_second_timestamps;
+    // `nest` wants exactly one leading and no trailing slash; `/` alone means no prefix.
+    let base_path = config
+        .base_path
+        .as_deref()
+        .map(|path| path.trim_matches('/'))
+        .filter(|path| !path.is_empty())
+        .map(|path| format!("/{}", path));
+    // Handlers building URLs for clients prefix them with the normalized path.
+    config.base_path = base_path.clone();
+    let latency_buckets = Arc::new(config.latency_buckets.clone());
+    let in_flight = Arc::new(InFlight::new(config.max_in_flight));
+    let replays = Arc::new(ReplayCache::new(
+        config.idempotency_response_ttl,
+        service.subscribe(),
+    ));
+    let state = AppState {
+        service,
+        config: Arc::new(config),
+        started_at: Instant::now(),
+        in_flight: in_flight.clone(),
+        replays,
+    };
+    let health = Router::new()
         .route("/health", get(handlers::health))
+        .route("/health/ready", get(handlers::ready::<S>))
+        .with_state(state.clone());
+
+    let mut api = Router::new()
         .route(
             "/api/v1/transactions",
             post(handlers::create_transaction::<S>).get(handlers::list_transactions::<S>),
         )
+        .route(
+            "/api/v1/transactions/batch",
+            post(handlers::create_transactions_batch::<S>),
+        )
+        .route(
+            "/api/v1/transactions/batch-get",
+            post(handlers::get_transactions_batch::<S>),
+        )
+        .route(
+            "/api/v1/transactions/import",
+            post(handlers::import_transactions::<S>),
+        )
+        .route(
+            "/api/v1/transactions/status-batch",
+            post(handlers::update_status_batch::<S>),
+        )
+        .route(
+            "/api/v1/transactions/recent",
+            get(handlers::recent_transactions::<S>),
+        )
+        .route(
+            "/api/v1/transactions/balance",
+            get(handlers::balance::<S>),
+        )
+        .route(
+            "/api/v1/transactions/stream",
+            get(handlers::stream_transactions::<S>),
+        )
         .route(
             "/api/v1/transactions/{id}",
-            get(handlers::get_transaction::<S>),
+            get(handlers::get_transaction::<S>)
+                .patch(handlers::update_transaction::<S>)
+                .delete(handlers::delete_transaction::<S>),
+        )
+        .route(
+            "/api/v1/transactions/by-key/{key}",
+            get(handlers::get_transaction_by_key::<S>),
         )
         .route(
             "/api/v1/transactions/{id}/status",
             patch(handlers::update_transaction_status::<S>),
         )
-        .with_state(service)
+        .route(
+            "/api/v1/transactions/{id}/amount",
+            patch(handlers::update_transaction_amount::<S>),
+        )
+        .route(
+            "/api/v1/transactions/{id}/void",
+            post(handlers::void_transaction::<S>),
+        )
+        .route(
+            "/api/v1/transactions/{id}/restore",
+            post(handlers::restore_transaction::<S>),
+        )
+        .route(
+            "/api/v1/transactions/{id}/notes",
+            post(handlers::add_note::<S>).get(handlers::list_notes::<S>),
+        )
+        .route(
+            "/api/v1/admin/currencies",
+            get(handlers::list_currencies::<S>),
+        )
+        .route(
+            "/api/v1/admin/currencies/{currency}",
+            put(handlers::set_currency_enabled::<S>),
+        )
+        .route(
+            "/api/v1/rates/{base}/{quote}",
+            get(handlers::get_exchange_rate::<S>).put(handlers::set_exchange_rate::<S>),
+        )
+        .route(
+            "/api/v1/schema/create-transaction",
+            get(handlers::create_transaction_schema::<S>),
+        )
+        .route(
+            "/api/v1/admin/state-machine",
+            get(handlers::state_machine),
+        )
+        .method_not_allowed_fallback(method_not_allowed)
+        .with_state(state);
+
+    if let Some(jwt) = jwt {
+        api = api.route_layer(middleware::from_fn_with_state(
+            Arc::new(jwt),
+            jwt::authenticate,
+        ));
+    }
+    if let Some(budget) = request_timeout {
+        api = api.layer(middleware::from_fn_with_state(budget, with_deadline));
+    }
+    api = api
+        .layer(middleware::from_fn_with_state(
+            amounts_as_strings,
+            with_amount_format,
+        ))
+        .layer(middleware::from_fn_with_state(
+            whole_second_timestamps,
+            with_timestamp_precision,
+        ));
+    // Health routes stay outside so probes still answer while load is being shed.
+    api = api.layer(middleware::from_fn_with_state(in_flight, load_shed::shed_load));
+
+    let mut router = Router::new().merge(health).merge(api);
+    if let Some(base_path) = base_path {
+        router = Router::new().nest(&base_path, router);
+    }
+    router = router.fallback(unknown_route);
+    // Innermost, so MessagePack clients get the redacted body encoded like any other.
+    if redact_internal_errors {
+        router = router.layer(middleware::map_response(redact_internal_error));
+    }
+    if log_bodies {
+        router = router.layer(middleware::from_fn_with_state(
+            max_body_bytes,
+            body_log::log_bodies,
+        ));
+    }
+    router = router
+        .layer(DefaultBodyLimit::disable())
+        .layer(RequestBodyLimitLayer::new(max_body_bytes))
+        .layer(middleware::map_response(move |resp: Response| async move {
+            payload_too_large_as_json(resp, max_body_bytes)
+        }))
+        .layer(middleware::from_fn_with_state(
+            max_body_bytes,
+            msgpack::negotiate,
+        ));
+    if let Some(min_bytes) = compression {
+        router = router.layer(compression_layer(min_bytes));
+    }
+
+    router
+        .layer(middleware::from_fn_with_state(
+            latency_buckets,
+            access_log::access_log,
+        ))
+        .layer(PropagateRequestIdLayer::x_request_id())
+        .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
+}
+
+/// Streaming responses are left alone so each event or line reaches the client as soon as it is
+/// written instead of waiting on the encoder's buffer.
+fn compression_layer(min_bytes: u16) -> CompressionLayer<impl Predicate> {
+    let predicate = SizeAbove::new(min_bytes)
+        .and(NotForContentType::GRPC)
+        .and(NotForContentType::IMAGES)
+        .and(NotForContentType::SSE)
+        .and(NotForContentType::const_new(handlers::NDJSON));
+    CompressionLayer::new().compress_when(predicate)
+}
+
+/// The body limit surfaces as a plain-text 413 either from `RequestBodyLimitLayer` (declared
+/// length too large) or from an extractor hitting the limit mid-stream. Rewrite both into the
+/// standard error body.
+fn payload_too_large_as_json(resp: Response, limit: usize) -> Response {
+    let is_json = resp
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .is_some_and(|v| v.as_bytes().starts_with(b"application/json"));
+    if resp.status() == StatusCode::PAYLOAD_TOO_LARGE && !is_json {
+        return AppError::PayloadTooLarge { limit }.into_response();
+    }
+    resp
+}
+
+/// Unmatched paths get the standard error body instead of axum's empty 404.
+async fn unknown_route(method: Method, uri: Uri) -> AppError {
+    AppError::RouteNotFound(format!("{} {}", method, uri.path()))
+}
+
+/// Known paths called with a method they do not support.
+async fn method_not_allowed(method: Method, uri: Uri) -> AppError {
+    AppError::MethodNotAllowed(format!("{} {}", method, uri.path()))
+}
+
+pub const AMOUNT_FORMAT_HEADER: &str = "x-amount-format";
+
+/// Picks how amounts in the response are written: `X-Amount-Format: string` or `number`, falling
+/// back to the configured default.
+async fn with_amount_format(
+    State(default_as_strings): State<bool>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let as_strings = match req.headers().get(AMOUNT_FORMAT_HEADER).map(|v| v.as_bytes()) {
+        Some(b"string") => true,
+        Some(b"number") => false,
+        _ => default_as_strings,
+    };
+    amount_format::scope(as_strings, next.run(req)).await
+}
+
+async fn with_timestamp_precision(
+    State(whole_seconds): State<bool>,
+    req: Request,
+    next: Next,
+) -> Response {
+    timestamp_precision::scope(whole_seconds, next.run(req)).await
+}
+
+/// Sets the request's `Deadline` for `DeadlineStorage` to enforce.
+async fn with_deadline(State(budget): State<Duration>, req: Request, next: Next) -> Response {
+    Deadline::after(budget).scope(next.run(req)).await
+}
+
+/// Swaps an internal error body for a generic message and a reference id. The reference is
+/// recorded on the `ErrorDetail` so the access log ties it to the full message.
+async fn redact_internal_error(resp: Response) -> Response {
+    let Some(mut detail) = resp.extensions().get::<ErrorDetail>().cloned() else {
+        return resp;
+    };
+    if detail.error_type != "INTERNAL_ERROR" {
+        return resp;
+    }
+    let reference = Uuid::new_v4().to_string();
+    let body = json!({
+        "error": {
+            "code": resp.status().as_u16(),
+            "type": detail.error_type,
+            "message": "Internal server error",
+            "reference": reference,
+        }
+    });
+    detail.reference = Some(reference);
+    let mut redacted = (resp.status(), Json(body)).into_response();
+    redacted.extensions_mut().insert(detail);
+    redacted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use crate::test_support::Capture;
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn internal_error_body_is_redacted_but_logged_in_full() {
+        let capture = Capture::default();
+        let subscriber = tracing_subscriber::fmt()
+            .json()
+            .flatten_event(true)
+            .with_writer(capture.clone())
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let app = Router::new()
+            .route(
+                "/boom",
+                get(|| async { AppError::Internal("db password rejected".into()) }),
+            )
+            .layer(middleware::map_response(redact_internal_error))
+            .layer(middleware::from_fn_with_state(
+                Arc::new(LatencyBuckets::default()),
+                access_log::access_log,
+            ));
+        let resp = app
+            .oneshot(Request::builder().uri("/boom").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        let bytes = resp.into_body().collect().await.unwrap().to_bytes();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["error"]["message"], "Internal server error");
+        assert_eq!(body["error"]["type"], "INTERNAL_ERROR");
+        let reference = body["error"]["reference"].as_str().unwrap();
+
+        let line = capture
+            .json_lines()
+            .into_iter()
+            .find(|v| v["target"] == "api_error")
+            .expect("error log line");
+        assert_eq!(line["level"], "ERROR");
+        assert_eq!(line["reference"], reference);
+        assert!(line["reason"].as_str().unwrap().contains("db password rejected"));
+    }
 }
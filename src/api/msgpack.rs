@@ -0,0 +1,90 @@
+use axum::body::{to_bytes, Body};
+use axum::extract::{Request, State};
+use axum::http::header::{ACCEPT, CONTENT_LENGTH, CONTENT_TYPE};
+use axum::http::HeaderValue;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+
+use crate::error::AppError;
+
+pub const MSGPACK: &str = "application/msgpack";
+
+/// Whether a media-type header lists `application/msgpack`, ignoring parameters.
+pub(crate) fn lists_msgpack(value: Option<&HeaderValue>) -> bool {
+    value
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| {
+            v.split(',')
+                .any(|media| media.split(';').next().unwrap_or_default().trim() == MSGPACK)
+        })
+}
+
+/// Content negotiation between JSON and MessagePack. Handlers only ever see and produce JSON:
+/// MessagePack request bodies are transcoded to JSON on the way in, and JSON responses
+/// (including error bodies) are transcoded to MessagePack on the way out when the client's
+/// `Accept` asks for it. Other response types such as SSE or NDJSON pass through untouched.
+pub async fn negotiate(State(limit): State<usize>, req: Request, next: Next) -> Response {
+    let wants_msgpack = lists_msgpack(req.headers().get(ACCEPT));
+
+    let response = if lists_msgpack(req.headers().get(CONTENT_TYPE)) {
+        match decode_request(req, limit).await {
+            Ok(req) => next.run(req).await,
+            Err(e) => e.into_response(),
+        }
+    } else {
+        next.run(req).await
+    };
+
+    if wants_msgpack {
+        encode_response(response).await
+    } else {
+        response
+    }
+}
+
+async fn decode_request(req: Request, limit: usize) -> Result<Request, AppError> {
+    let (mut parts, body) = req.into_parts();
+    let bytes = to_bytes(body, limit)
+        .await
+        .map_err(|_| AppError::PayloadTooLarge { limit })?;
+    let value: serde_json::Value = rmp_serde::from_slice(&bytes)
+        .map_err(|e| AppError::Validation(format!("Invalid MessagePack body: {}", e)))?;
+    let json = serde_json::to_vec(&value)
+        .map_err(|e| AppError::Validation(format!("Unsupported MessagePack body: {}", e)))?;
+
+    parts
+        .headers
+        .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    parts.headers.remove(CONTENT_LENGTH);
+    Ok(Request::from_parts(parts, Body::from(json)))
+}
+
+async fn encode_response(response: Response) -> Response {
+    let is_json = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .is_some_and(|v| v.as_bytes().starts_with(b"application/json"));
+    if !is_json {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let packed = match to_bytes(body, usize::MAX).await {
+        Ok(bytes) => serde_json::from_slice::<serde_json::Value>(&bytes)
+            .map_err(|e| e.to_string())
+            .and_then(|value| rmp_serde::to_vec_named(&value).map_err(|e| e.to_string())),
+        Err(e) => Err(e.to_string()),
+    };
+    match packed {
+        Ok(packed) => {
+            parts
+                .headers
+                .insert(CONTENT_TYPE, HeaderValue::from_static(MSGPACK));
+            parts.headers.remove(CONTENT_LENGTH);
+            Response::from_parts(parts, Body::from(packed))
+        }
+        Err(e) => {
+            AppError::Internal(format!("Failed to encode MessagePack: {}", e)).into_response()
+        }
+    }
+}
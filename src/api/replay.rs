@@ -0,0 +1,257 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use axum::body::Bytes;
+use tokio::sync::broadcast::{self, error::TryRecvError};
+use uuid::Uuid;
+
+use crate::domain::events::{TransactionEvent, TransactionEventKind};
+
+/// A create response kept for replaying its idempotency key.
+#[derive(Debug, Clone)]
+pub struct CachedCreate {
+    pub transaction_id: Uuid,
+    /// Serialized request the response answered. A retry only hits the cache with the same
+    /// payload; anything else goes to the service, which replays or reports a conflict.
+    pub fingerprint: Vec<u8>,
+    pub body: Bytes,
+    stored_at: Instant,
+    expires_at: Instant,
+}
+
+type Scope = (Option<String>, String);
+
+#[derive(Debug, Default)]
+struct Entries {
+    by_scope: HashMap<Scope, CachedCreate>,
+    /// Scopes cached for each transaction, so a change drops them without a scan.
+    by_transaction: HashMap<Uuid, Vec<Scope>>,
+    /// Scopes in the order they were stored, which is also the order they expire in.
+    by_age: VecDeque<(Instant, Scope)>,
+    /// Transactions changed within the last TTL, in the order their last change was seen. A
+    /// response rendered before such a change may arrive after its event and must not be kept.
+    changed: HashMap<Uuid, Instant>,
+    changed_by_age: VecDeque<(Instant, Uuid)>,
+}
+
+impl Entries {
+    fn remove(&mut self, scope: &Scope) {
+        let Some(entry) = self.by_scope.remove(scope) else {
+            return;
+        };
+        if let Some(scopes) = self.by_transaction.get_mut(&entry.transaction_id) {
+            scopes.retain(|s| s != scope);
+            if scopes.is_empty() {
+                self.by_transaction.remove(&entry.transaction_id);
+            }
+        }
+    }
+
+    fn invalidate(&mut self, id: Uuid, now: Instant) {
+        self.changed.insert(id, now);
+        self.changed_by_age.push_back((now, id));
+        for scope in self.by_transaction.remove(&id).unwrap_or_default() {
+            self.by_scope.remove(&scope);
+        }
+    }
+
+    fn evict_expired(&mut self, ttl: Duration, now: Instant) {
+        while let Some((stored_at, _)) = self.by_age.front() {
+            if now.duration_since(*stored_at) < ttl {
+                break;
+            }
+            let (stored_at, scope) = self.by_age.pop_front().expect("front exists");
+            // A later put for the same scope has its own place in the queue.
+            if self.by_scope.get(&scope).is_some_and(|e| e.stored_at == stored_at) {
+                self.remove(&scope);
+            }
+        }
+        while let Some((seen_at, _)) = self.changed_by_age.front() {
+            if now.duration_since(*seen_at) < ttl {
+                break;
+            }
+            let (seen_at, id) = self.changed_by_age.pop_front().expect("front exists");
+            if self.changed.get(&id) == Some(&seen_at) {
+                self.changed.remove(&id);
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// Serialized create responses by tenant and idempotency key, so a replay is answered with the
+/// original body without going through the service. Entries live for `ttl`, or less when their
+/// idempotency key expires sooner, and are dropped as soon as the service reports a change to
+/// their transaction, whoever made it: the cache reads the service's event stream before every
+/// lookup. Without a TTL nothing is cached.
+#[derive(Debug)]
+pub struct ReplayCache {
+    ttl: Option<Duration>,
+    entries: Mutex<Entries>,
+    events: Mutex<broadcast::Receiver<TransactionEvent>>,
+}
+
+impl ReplayCache {
+    pub fn new(ttl: Option<Duration>, events: broadcast::Receiver<TransactionEvent>) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(Entries::default()),
+            events: Mutex::new(events),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.ttl.is_some()
+    }
+
+    /// Drops the entries of every transaction changed since the last call. When events were
+    /// missed there is no telling which entries went stale, so everything goes and `false` is
+    /// returned.
+    fn apply_events(&self, entries: &mut Entries) -> bool {
+        let mut complete = true;
+        let mut events = self.events.lock().unwrap_or_else(|e| e.into_inner());
+        loop {
+            match events.try_recv() {
+                Ok(event) if event.kind == TransactionEventKind::Created => {}
+                Ok(event) => entries.invalidate(event.transaction.id, Instant::now()),
+                Err(TryRecvError::Lagged(_)) => {
+                    entries.clear();
+                    complete = false;
+                }
+                Err(TryRecvError::Empty | TryRecvError::Closed) => return complete,
+            }
+        }
+    }
+
+    pub fn get(
+        &self,
+        tenant: Option<&str>,
+        key: &str,
+        fingerprint: &[u8],
+    ) -> Option<CachedCreate> {
+        self.ttl?;
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        self.apply_events(&mut entries);
+        let scope = (tenant.map(str::to_string), key.to_string());
+        let entry = entries.by_scope.get(&scope)?;
+        if Instant::now() >= entry.expires_at {
+            entries.remove(&scope);
+            return None;
+        }
+        (entry.fingerprint == fingerprint).then(|| entry.clone())
+    }
+
+    /// Caches `body` for the TTL, or for `key_lifetime` if that ends sooner: once the service
+    /// stops replaying the key, the cache must too.
+    pub fn put(
+        &self,
+        tenant: Option<&str>,
+        key: &str,
+        transaction_id: Uuid,
+        fingerprint: Vec<u8>,
+        body: Bytes,
+        key_lifetime: Option<Duration>,
+    ) {
+        let Some(ttl) = self.ttl else {
+            return;
+        };
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let complete = self.apply_events(&mut entries);
+        let now = Instant::now();
+        entries.evict_expired(ttl, now);
+        // The transaction may have changed after `body` was rendered.
+        if !complete || entries.changed.contains_key(&transaction_id) {
+            return;
+        }
+        let scope = (tenant.map(str::to_string), key.to_string());
+        entries.remove(&scope);
+        entries
+            .by_transaction
+            .entry(transaction_id)
+            .or_default()
+            .push(scope.clone());
+        entries.by_age.push_back((now, scope.clone()));
+        entries.by_scope.insert(
+            scope,
+            CachedCreate {
+                transaction_id,
+                fingerprint,
+                body,
+                stored_at: now,
+                expires_at: now + key_lifetime.map_or(ttl, |lifetime| lifetime.min(ttl)),
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::models::Transaction;
+    use chrono::Utc;
+
+    fn event(kind: TransactionEventKind, id: Uuid) -> TransactionEvent {
+        TransactionEvent {
+            sequence: 1,
+            kind,
+            transaction: Transaction {
+                id,
+                ..Transaction::test_pending(Utc::now())
+            },
+        }
+    }
+
+    #[test]
+    fn entries_expire_and_are_invalidated_by_events() {
+        let id = Uuid::new_v4();
+        let (events, rx) = broadcast::channel(8);
+        let cache = ReplayCache::new(Some(Duration::from_secs(60)), rx);
+        cache.put(None, "k", id, b"req".to_vec(), Bytes::from_static(b"body"), None);
+
+        assert_eq!(cache.get(None, "k", b"req").unwrap().body, "body");
+        assert!(cache.get(None, "k", b"other").is_none());
+        assert!(cache.get(Some("acme"), "k", b"req").is_none());
+        events.send(event(TransactionEventKind::Created, id)).unwrap();
+        assert!(cache.get(None, "k", b"req").is_some());
+        events.send(event(TransactionEventKind::StatusChanged, id)).unwrap();
+        assert!(cache.get(None, "k", b"req").is_none());
+        // A response rendered before the change it missed is not kept.
+        cache.put(None, "k", id, b"req".to_vec(), Bytes::from_static(b"body"), None);
+        assert!(cache.get(None, "k", b"req").is_none());
+
+        let (_events, rx) = broadcast::channel(8);
+        let expired = ReplayCache::new(Some(Duration::ZERO), rx);
+        expired.put(None, "k", id, b"req".to_vec(), Bytes::from_static(b"body"), None);
+        assert!(expired.get(None, "k", b"req").is_none());
+    }
+
+    #[test]
+    fn entries_end_with_their_idempotency_key() {
+        let (_events, rx) = broadcast::channel(8);
+        let cache = ReplayCache::new(Some(Duration::from_secs(60)), rx);
+        let body = Bytes::from_static(b"body");
+        let id = Uuid::new_v4();
+        cache.put(None, "lapsed", id, b"req".to_vec(), body.clone(), Some(Duration::ZERO));
+        assert!(cache.get(None, "lapsed", b"req").is_none());
+        let hour = Some(Duration::from_secs(3600));
+        cache.put(None, "kept", id, b"req".to_vec(), body, hour);
+        assert!(cache.get(None, "kept", b"req").is_some());
+    }
+
+    #[test]
+    fn missed_events_drop_everything() {
+        let (events, rx) = broadcast::channel(1);
+        let cache = ReplayCache::new(Some(Duration::from_secs(60)), rx);
+        let body = Bytes::from_static(b"body");
+        cache.put(None, "k", Uuid::new_v4(), b"req".to_vec(), body, None);
+
+        for _ in 0..2 {
+            events.send(event(TransactionEventKind::Updated, Uuid::new_v4())).unwrap();
+        }
+        assert!(cache.get(None, "k", b"req").is_none());
+    }
+}
@@ -1,12 +1,193 @@
-use serde::Serialize;
+use std::collections::BTreeMap;
+
+use axum::http::{StatusCode, Uri};
+use serde::ser::{Error as _, SerializeMap};
+use serde::{Deserialize, Serialize, Serializer};
+
+use crate::domain::models::{
+    comma_separated, Facets, Transaction, TransactionField, TransactionStatus,
+};
+use crate::error::AppError;
+use crate::storage::PageInfo;
 
 #[derive(Debug, Serialize)]
 pub struct ApiResponse<T: Serialize> {
     pub data: T,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub meta: Option<ResponseMeta>,
 }
 
 impl<T: Serialize> ApiResponse<T> {
     pub fn new(data: T) -> Self {
-        Self { data }
+        Self { data, meta: None }
+    }
+
+    pub fn with_meta(data: T, meta: ResponseMeta) -> Self {
+        Self {
+            data,
+            meta: Some(meta),
+        }
+    }
+}
+
+/// Collection-level information that accompanies list responses.
+#[derive(Debug, Default, Serialize)]
+pub struct ResponseMeta {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+    /// Pass as `?cursor=` to fetch the next page; absent on the last page.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+    /// Matches per status across the full filtered set, not just this page.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status_counts: Option<BTreeMap<TransactionStatus, usize>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub facets: Option<Facets>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub per_page: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_pages: Option<usize>,
+    /// Whether matches follow this page.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub has_more: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub links: Option<PageLinks>,
+}
+
+/// Absolute URLs of this page and its neighbours, keeping every other query parameter of the
+/// request. Cursors only lead forward, so cursor pages never carry `prev`.
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct PageLinks {
+    #[serde(rename = "self")]
+    pub current: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prev: Option<String>,
+}
+
+impl PageLinks {
+    /// `origin` is the scheme and authority the client reached us at, e.g.
+    /// `https://api.example.com`; `uri` is the request as it arrived.
+    pub fn new(
+        origin: &str,
+        uri: &Uri,
+        next_cursor: Option<&str>,
+        page: Option<PageInfo>,
+    ) -> Self {
+        let origin = origin.trim_end_matches('/');
+        let link = |name: &str, value: &str| {
+            let mut query: Vec<&str> = uri
+                .query()
+                .unwrap_or_default()
+                .split('&')
+                .filter(|pair| !pair.is_empty() && pair.split('=').next() != Some(name))
+                .collect();
+            let param = format!("{}={}", name, value);
+            query.push(&param);
+            format!("{}{}?{}", origin, uri.path(), query.join("&"))
+        };
+        let (next, prev) = match page {
+            Some(PageInfo {
+                page, total_pages, ..
+            }) => (
+                (page < total_pages).then(|| link("page", &(page + 1).to_string())),
+                (page > 1).then(|| link("page", &(page - 1).to_string())),
+            ),
+            None => (next_cursor.map(|cursor| link("cursor", cursor)), None),
+        };
+        Self {
+            current: format!("{}{}", origin, uri),
+            next,
+            prev,
+        }
+    }
+}
+
+/// Sparse fieldset from `?fields=id,amount,status`. Unknown names are a 400; no selection returns
+/// every field.
+#[derive(Debug, Default, Deserialize)]
+pub struct FieldSelection {
+    #[serde(default, deserialize_with = "comma_separated")]
+    fields: Vec<TransactionField>,
+}
+
+impl FieldSelection {
+    pub fn apply<'a>(&'a self, txn: &'a Transaction) -> Sparse<'a> {
+        Sparse {
+            txn,
+            fields: &self.fields,
+        }
+    }
+
+    /// The selected names in serialization order joined by `+`, so the same selection always
+    /// gives the same key. `None` when every field is returned.
+    pub fn key(&self) -> Option<String> {
+        if self.fields.is_empty() {
+            return None;
+        }
+        let selected: Vec<&str> = Transaction::FIELDS
+            .iter()
+            .copied()
+            .filter(|name| self.fields.iter().any(|field| field.name() == *name))
+            .collect();
+        Some(selected.join("+"))
+    }
+}
+
+/// A transaction serialized with only the selected fields, in their usual order.
+pub struct Sparse<'a> {
+    txn: &'a Transaction,
+    fields: &'a [TransactionField],
+}
+
+impl Serialize for Sparse<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.fields.is_empty() {
+            return self.txn.serialize(serializer);
+        }
+        let full = serde_json::to_value(self.txn).map_err(S::Error::custom)?;
+        let mut map = serializer.serialize_map(Some(self.fields.len()))?;
+        for name in Transaction::FIELDS {
+            if self.fields.iter().any(|field| field.name() == *name) {
+                map.serialize_entry(name, &full[*name])?;
+            }
+        }
+        map.end()
+    }
+}
+
+/// Outcome of one item in a batch create. `status` is what the single-item endpoint would have
+/// returned for the same request.
+#[derive(Debug, Serialize)]
+pub struct BatchItemResult {
+    pub index: usize,
+    pub status: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<Transaction>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<serde_json::Value>,
+}
+
+impl BatchItemResult {
+    pub fn from_outcome(index: usize, outcome: Result<(Transaction, bool), AppError>) -> Self {
+        match outcome {
+            Ok((txn, created)) => Self {
+                index,
+                status: if created { StatusCode::CREATED } else { StatusCode::OK }.as_u16(),
+                data: Some(txn),
+                error: None,
+            },
+            Err(e) => Self {
+                index,
+                status: e.status_code().as_u16(),
+                data: None,
+                error: Some(e.to_body()["error"].take()),
+            },
+        }
     }
 }
@@ -0,0 +1,263 @@
+//! Typed client for the REST API. Error bodies are mapped back onto `AppError`, so callers can
+//! match on the same variants the server produced.
+
+use reqwest::{Method, RequestBuilder, Response};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::domain::models::{
+    CreateTransactionRequest, ListFilters, Transaction, UpdateStatusRequest,
+};
+use crate::error::AppError;
+
+#[derive(Debug, Clone)]
+pub struct TransactionClient {
+    http: reqwest::Client,
+    base_url: String,
+    api_key: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Envelope<T> {
+    data: T,
+}
+
+impl TransactionClient {
+    /// `base_url` is the server root, e.g. `http://localhost:8080`.
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            api_key: None,
+        }
+    }
+
+    /// Sent as a bearer token on every request.
+    pub fn with_api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    /// Returns the transaction and whether it was newly created (`false` for a replay).
+    pub async fn create(
+        &self,
+        req: &CreateTransactionRequest,
+    ) -> Result<(Transaction, bool), AppError> {
+        let resp = self.send(self.request(Method::POST, "").json(req)).await?;
+        let created = resp.status() == reqwest::StatusCode::CREATED;
+        Ok((decode(resp).await?, created))
+    }
+
+    pub async fn get(&self, id: Uuid) -> Result<Transaction, AppError> {
+        let resp = self.send(self.request(Method::GET, &format!("/{}", id))).await?;
+        decode(resp).await
+    }
+
+    /// One page of matches. `facets` are not requested.
+    pub async fn list(&self, filters: &ListFilters) -> Result<Vec<Transaction>, AppError> {
+        let mut query: Vec<(&str, String)> = Vec::new();
+        if !filters.status.is_empty() {
+            let statuses: Vec<_> = filters.status.iter().map(|s| s.to_string()).collect();
+            query.push(("status", statuses.join(",")));
+        }
+        let optional = [
+            ("currency", filters.currency.map(|c| c.to_string())),
+            ("q", filters.q.clone()),
+            ("tag", filters.tag.clone()),
+            ("limit", filters.limit.map(|l| l.to_string())),
+            ("cursor", filters.cursor.clone()),
+            ("page", filters.page.map(|p| p.to_string())),
+            ("per_page", filters.per_page.map(|p| p.to_string())),
+        ];
+        query.extend(optional.into_iter().filter_map(|(k, v)| v.map(|v| (k, v))));
+        let resp = self.send(self.request(Method::GET, "").query(&query)).await?;
+        decode(resp).await
+    }
+
+    pub async fn update_status(
+        &self,
+        id: Uuid,
+        req: &UpdateStatusRequest,
+    ) -> Result<Transaction, AppError> {
+        let path = format!("/{}/status", id);
+        let resp = self.send(self.request(Method::PATCH, &path).json(req)).await?;
+        decode(resp).await
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let url = format!("{}/api/v1/transactions{}", self.base_url, path);
+        let builder = self.http.request(method, url);
+        match &self.api_key {
+            Some(key) => builder.bearer_auth(key),
+            None => builder,
+        }
+    }
+
+    /// Sends the request, turning transport failures and error statuses into `AppError`.
+    async fn send(&self, builder: RequestBuilder) -> Result<Response, AppError> {
+        let resp = builder
+            .send()
+            .await
+            .map_err(|e| AppError::Unavailable(format!("Request failed: {}", e)))?;
+        if resp.status().is_success() {
+            return Ok(resp);
+        }
+        let status = resp.status();
+        let body = resp.text().await.unwrap_or_default();
+        Err(match serde_json::from_str::<Value>(&body) {
+            Ok(value) if value["error"].is_object() => from_error_body(&value["error"]),
+            _ => AppError::Internal(format!("Unexpected {} response: {}", status, body)),
+        })
+    }
+}
+
+async fn decode<T: DeserializeOwned>(resp: Response) -> Result<T, AppError> {
+    resp.json::<Envelope<T>>()
+        .await
+        .map(|envelope| envelope.data)
+        .map_err(|e| AppError::Internal(format!("Invalid response body: {}", e)))
+}
+
+/// Inverse of `AppError::to_body`. The message is the variant's `Display` output, so the fixed
+/// prefix is stripped to recover the original payload.
+fn from_error_body(error: &Value) -> AppError {
+    let message = error["message"].as_str().unwrap_or_default();
+    let after = |prefix: &str| message.strip_prefix(prefix).unwrap_or(message).to_string();
+    match error["type"].as_str().unwrap_or_default() {
+        "NOT_FOUND" => match message.strip_prefix("No route for ") {
+            Some(route) => AppError::RouteNotFound(route.to_string()),
+            None => AppError::NotFound(after("Transaction not found: ")),
+        },
+        "VALIDATION_FAILED" => AppError::Validation(after("Validation error: ")),
+        "RATE_NOT_FOUND" => {
+            let pair = after("No exchange rate from ");
+            let parsed = pair
+                .split_once(" to ")
+                .and_then(|(base, quote)| Some((base.parse().ok()?, quote.parse().ok()?)));
+            match parsed {
+                Some((base, quote)) => AppError::RateNotFound { base, quote },
+                None => AppError::Internal(message.to_string()),
+            }
+        }
+        "IDEMPOTENCY_CONFLICT" => AppError::IdempotencyConflict,
+        "VERSION_CONFLICT" => match serde_json::from_value(error["current"].clone()) {
+            Ok(current) => AppError::VersionConflict {
+                current: Box::new(current),
+            },
+            Err(_) => AppError::Internal(message.to_string()),
+        },
+        "INVALID_STATE_TRANSITION" => {
+            let transition = after("Invalid state transition from ");
+            match transition.split_once(" to ") {
+                Some((from, to)) => AppError::InvalidStateTransition {
+                    from: from.to_string(),
+                    to: to.to_string(),
+                },
+                None => AppError::Internal(message.to_string()),
+            }
+        }
+        "NOT_PENDING" => AppError::NotPending {
+            status: after("Transaction is ")
+                .split(';')
+                .next()
+                .unwrap_or_default()
+                .to_string(),
+        },
+        "NOT_DELETED" => AppError::NotDeleted(after("Transaction is not deleted: ")),
+        "PAYLOAD_TOO_LARGE" => AppError::PayloadTooLarge {
+            limit: after("Request body exceeds the ")
+                .split('-')
+                .next()
+                .and_then(|limit| limit.parse().ok())
+                .unwrap_or_default(),
+        },
+        "TOO_MANY_REQUESTS" => AppError::TooManyRequests(after("Too many requests: ")),
+        "UNAUTHORIZED" => AppError::Unauthorized(after("Unauthorized: ")),
+        "FORBIDDEN" => AppError::Forbidden(after("Forbidden: ")),
+        "METHOD_NOT_ALLOWED" => AppError::MethodNotAllowed(after("Method not allowed: ")),
+        "UNSUPPORTED_MEDIA_TYPE" => {
+            AppError::UnsupportedMediaType(after("Unsupported media type: "))
+        }
+        "SERVICE_UNAVAILABLE" => AppError::Unavailable(after("Service unavailable: ")),
+        "DEADLINE_EXCEEDED" => AppError::DeadlineExceeded(after("Deadline exceeded: ")),
+        _ => AppError::Internal(after("Internal error: ")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::build_router;
+    use crate::domain::models::{Currency, TransactionStatus};
+    use crate::domain::service::TransactionService;
+    use crate::storage::memory::InMemoryStorage;
+    use tokio::net::TcpListener;
+
+    async fn serve() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = build_router(TransactionService::new(InMemoryStorage::new()));
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{}", addr)
+    }
+
+    fn create_req(key: &str) -> CreateTransactionRequest {
+        CreateTransactionRequest {
+            idempotency_key: Some(key.into()),
+            tenant_id: None,
+            amount: 25.0,
+            currency: Some(Currency::Eur),
+            description: "Client test".into(),
+            expires_at: None,
+            execute_at: None,
+            tags: vec!["sdk".into()],
+        }
+    }
+
+    #[tokio::test]
+    async fn client_round_trips_against_a_live_server() {
+        let client = TransactionClient::new(serve().await);
+
+        let (txn, created) = client.create(&create_req("client-1")).await.unwrap();
+        assert!(created);
+        assert_eq!(txn.currency, Currency::Eur);
+        let (replayed, created) = client.create(&create_req("client-1")).await.unwrap();
+        assert!(!created);
+        assert_eq!(replayed.id, txn.id);
+
+        assert_eq!(client.get(txn.id).await.unwrap().id, txn.id);
+
+        let update = UpdateStatusRequest {
+            status: TransactionStatus::Completed,
+            expected_version: Some(txn.version),
+            reason: None,
+        };
+        let completed = client.update_status(txn.id, &update).await.unwrap();
+        assert_eq!(completed.status, TransactionStatus::Completed);
+
+        let filters = ListFilters {
+            status: vec![TransactionStatus::Completed],
+            ..ListFilters::default()
+        };
+        let listed = client.list(&filters).await.unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].id, txn.id);
+
+        let missing = client.get(Uuid::nil()).await.unwrap_err();
+        assert!(matches!(missing, AppError::NotFound(ref id) if *id == Uuid::nil().to_string()));
+        match client.update_status(txn.id, &update).await.unwrap_err() {
+            AppError::VersionConflict { current } => assert_eq!(current.version, 2),
+            other => panic!("expected version conflict, got {:?}", other),
+        }
+        let invalid = CreateTransactionRequest {
+            amount: -1.0,
+            ..create_req("client-2")
+        };
+        assert!(matches!(
+            client.create(&invalid).await.unwrap_err(),
+            AppError::Validation(ref m) if m == "Amount must be greater than zero"
+        ));
+    }
+}
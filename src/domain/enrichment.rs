@@ -0,0 +1,62 @@
+use std::time::Duration;
+
+use futures_util::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+
+use crate::domain::models::Transaction;
+use crate::error::AppError;
+
+/// Derived fields attached to a transaction before it is persisted.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Enrichment {
+    pub risk_score: Option<f64>,
+    pub geolocation: Option<String>,
+}
+
+/// Computes an `Enrichment` for a transaction about to be created. Implementations typically
+/// call out to another service, so the service bounds each call with a timeout.
+pub trait EnrichmentProvider: Send + Sync + 'static {
+    fn enrich<'a>(&'a self, txn: &'a Transaction) -> BoxFuture<'a, Result<Enrichment, AppError>>;
+}
+
+/// What `create` does when enrichment fails or times out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnrichmentFailureMode {
+    /// Fail the create with `AppError::Unavailable`.
+    Reject,
+    /// Persist the transaction with `Enrichment::default()`.
+    Proceed,
+}
+
+#[derive(Debug, Clone)]
+pub struct EnrichmentConfig {
+    pub timeout: Duration,
+    pub on_failure: EnrichmentFailureMode,
+}
+
+impl Default for EnrichmentConfig {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_millis(500),
+            on_failure: EnrichmentFailureMode::Proceed,
+        }
+    }
+}
+
+/// Provider returning a fixed enrichment after an optional delay. Useful in tests and demos.
+#[derive(Debug, Clone, Default)]
+pub struct FixedEnrichmentProvider {
+    pub enrichment: Enrichment,
+    pub delay: Duration,
+}
+
+impl EnrichmentProvider for FixedEnrichmentProvider {
+    fn enrich<'a>(&'a self, _txn: &'a Transaction) -> BoxFuture<'a, Result<Enrichment, AppError>> {
+        Box::pin(async move {
+            if !self.delay.is_zero() {
+                tokio::time::sleep(self.delay).await;
+            }
+            Ok(self.enrichment.clone())
+        })
+    }
+}
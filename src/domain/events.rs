@@ -0,0 +1,37 @@
+use serde::Serialize;
+
+use crate::domain::models::Transaction;
+
+/// Buffered events per subscriber. The channel never blocks publishers: a subscriber that falls
+/// further behind than this misses the oldest events, which the SSE stream reports as a `lagged`
+/// event and which also shows up as a gap in `sequence`.
+pub const EVENT_CHANNEL_CAPACITY: usize = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionEventKind {
+    Created,
+    StatusChanged,
+    /// Any other change: description, amount, soft delete or restore.
+    Updated,
+}
+
+impl TransactionEventKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Created => "created",
+            Self::StatusChanged => "status_changed",
+            Self::Updated => "updated",
+        }
+    }
+}
+
+/// Published by `TransactionService` after a change is persisted.
+#[derive(Debug, Clone, Serialize)]
+pub struct TransactionEvent {
+    /// Position in the service's event order: starts at 1 and grows by one per event, in the
+    /// order events are delivered.
+    pub sequence: u64,
+    pub kind: TransactionEventKind,
+    pub transaction: Transaction,
+}
@@ -0,0 +1,85 @@
+use std::time::Duration;
+
+use chrono::TimeDelta;
+use tokio::task::JoinHandle;
+
+use crate::domain::models::{Transaction, TransactionStatus};
+use crate::domain::service::TransactionService;
+use crate::domain::sweep::sweep_pending;
+use crate::error::AppError;
+use crate::storage::Storage;
+
+/// Cancels pending transactions past their expiry: `expires_at` when set, otherwise `max_age`
+/// after creation. Returns how many were cancelled. Transactions that change concurrently are
+/// skipped rather than treated as failures.
+pub async fn expire_stale<S: Storage>(
+    service: &TransactionService<S>,
+    max_age: TimeDelta,
+) -> Result<usize, AppError> {
+    let now = service.now();
+    let expired = |txn: &Transaction| txn.is_expired(now, max_age);
+    sweep_pending(service, expired, TransactionStatus::Cancelled, Some("expired")).await
+}
+
+/// Runs `expire_stale` every `interval` for the lifetime of the process.
+pub fn spawn_expiry_task<S: Storage + Clone>(
+    service: TransactionService<S>,
+    max_age: TimeDelta,
+    interval: Duration,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            match expire_stale(&service, max_age).await {
+                Ok(0) => {}
+                Ok(count) => tracing::info!(count, "cancelled stale pending transactions"),
+                Err(e) => tracing::error!(error = %e, "expiry scan failed"),
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use crate::domain::service::MAX_PAGE_SIZE;
+    use crate::storage::memory::InMemoryStorage;
+
+    fn pending(age: TimeDelta) -> Transaction {
+        Transaction::test_pending(Utc::now() - age)
+    }
+
+    #[tokio::test]
+    async fn cancels_only_stale_pending() {
+        let storage = InMemoryStorage::new();
+        let stale = pending(TimeDelta::hours(2));
+        let fresh = pending(TimeDelta::minutes(5));
+        storage.insert(stale.clone()).await.unwrap();
+        storage.insert(fresh.clone()).await.unwrap();
+        let service = TransactionService::new(storage);
+
+        let cancelled = expire_stale(&service, TimeDelta::hours(1)).await.unwrap();
+
+        assert_eq!(cancelled, 1);
+        let stale = service.get(stale.id).await.unwrap();
+        assert_eq!(stale.status, TransactionStatus::Cancelled);
+        let fresh = service.get(fresh.id).await.unwrap();
+        assert_eq!(fresh.status, TransactionStatus::Pending);
+
+        assert_eq!(expire_stale(&service, TimeDelta::hours(1)).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn cancels_past_the_list_cap_and_across_pages() {
+        let storage = InMemoryStorage::new();
+        let stale = MAX_PAGE_SIZE + 1;
+        for _ in 0..stale {
+            storage.insert(pending(TimeDelta::hours(2))).await.unwrap();
+        }
+        let service = TransactionService::new(storage).with_max_list_results(10);
+
+        assert_eq!(expire_stale(&service, TimeDelta::hours(1)).await.unwrap(), stale);
+    }
+}
@@ -0,0 +1,70 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use crate::domain::models::{Currency, RoundingMode};
+use crate::error::AppError;
+
+/// Fee charged on a transaction in one currency: a flat part plus a share of the amount.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeeRule {
+    /// Flat part in minor units.
+    pub flat_minor: i64,
+    /// Percentage part in hundredths of a percent, so 2.9% is 290.
+    pub basis_points: u32,
+}
+
+/// Per-currency fee rules. Currencies without a rule carry no fee.
+#[derive(Debug, Clone, Default)]
+pub struct FeePolicy {
+    rules: HashMap<Currency, FeeRule>,
+}
+
+impl FeePolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Charges `flat` (in major units) plus `percent`% of the amount for `currency`.
+    pub fn with_rule(mut self, currency: Currency, flat: f64, percent: f64) -> Self {
+        let rule = FeeRule {
+            flat_minor: currency.to_minor(flat, RoundingMode::HalfUp),
+            basis_points: (percent * 100.0).round() as u32,
+        };
+        self.rules.insert(currency, rule);
+        self
+    }
+
+    /// Fee in minor units for `amount_minor` of `currency`, or `None` when no rule applies.
+    pub fn fee_minor(
+        &self,
+        currency: Currency,
+        amount_minor: i64,
+        mode: RoundingMode,
+    ) -> Option<i64> {
+        let rule = self.rules.get(&currency)?;
+        let share = mode.divide(amount_minor * i64::from(rule.basis_points), 10_000);
+        Some(rule.flat_minor + share)
+    }
+}
+
+/// Parses `CUR=flat+percent%` rules separated by commas, e.g. `USD=0.30+2.9%,EUR=0.25+1.4%`.
+impl FromStr for FeePolicy {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut policy = Self::new();
+        for entry in s.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let invalid = || AppError::Validation(format!("Invalid fee rule: {}", entry));
+            let (currency, rule) = entry.split_once('=').ok_or_else(invalid)?;
+            let (flat, percent) = rule.split_once('+').ok_or_else(invalid)?;
+            let percent = percent.trim().strip_suffix('%').ok_or_else(invalid)?;
+            let flat: f64 = flat.trim().parse().map_err(|_| invalid())?;
+            let percent: f64 = percent.trim().parse().map_err(|_| invalid())?;
+            if !flat.is_finite() || flat < 0.0 || !percent.is_finite() || percent < 0.0 {
+                return Err(invalid());
+            }
+            policy = policy.with_rule(currency.trim().parse()?, flat, percent);
+        }
+        Ok(policy)
+    }
+}
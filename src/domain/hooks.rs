@@ -0,0 +1,38 @@
+use futures_util::future::BoxFuture;
+
+use crate::domain::models::{Transaction, TransactionStatus};
+use crate::error::AppError;
+
+/// Custom logic run when `TransactionService::update_status` changes a transaction's status.
+pub trait TransitionHook: Send + Sync + 'static {
+    fn on_transition<'a>(
+        &'a self,
+        txn: &'a Transaction,
+        from: TransactionStatus,
+        to: TransactionStatus,
+    ) -> BoxFuture<'a, Result<(), AppError>>;
+}
+
+/// Business rule consulted before `TransactionService::update_status` writes a change, e.g. to
+/// require approval for completing large amounts. Guards run in registration order with the
+/// transaction's current state, after the state machine has allowed the transition; the first
+/// error vetoes it and is returned to the caller unchanged.
+pub trait TransitionGuard: Send + Sync + 'static {
+    fn allow(
+        &self,
+        txn: &Transaction,
+        from: TransactionStatus,
+        to: TransactionStatus,
+    ) -> Result<(), AppError>;
+}
+
+/// How hook failures affect the transition.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HookPolicy {
+    /// Hooks run after the change is written; failures are logged and the change stands.
+    #[default]
+    BestEffort,
+    /// Hooks run before the change is written with the transaction's current state; the first
+    /// failure aborts the transition and is returned to the caller.
+    Mandatory,
+}
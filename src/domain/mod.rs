@@ -1,3 +1,13 @@
+pub mod clock;
+pub mod enrichment;
+pub mod events;
+pub mod expiry;
+pub mod fees;
+pub mod hooks;
 pub mod models;
+pub mod rates;
+pub mod scheduler;
 pub mod service;
+pub mod state_machine;
+pub mod sweep;
 pub mod validation;
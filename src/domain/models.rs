@@ -1,12 +1,20 @@
-use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashSet};
 use std::fmt;
+use std::str::FromStr;
 use uuid::Uuid;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+use crate::domain::enrichment::Enrichment;
+use crate::error::AppError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum TransactionStatus {
     Pending,
+    /// Picked up by the scheduler once `execute_at` passed; no longer cancellable.
+    Processing,
     Completed,
     Failed,
     Cancelled,
@@ -16,6 +24,7 @@ impl fmt::Display for TransactionStatus {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::Pending => write!(f, "PENDING"),
+            Self::Processing => write!(f, "PROCESSING"),
             Self::Completed => write!(f, "COMPLETED"),
             Self::Failed => write!(f, "FAILED"),
             Self::Cancelled => write!(f, "CANCELLED"),
@@ -23,7 +32,30 @@ impl fmt::Display for TransactionStatus {
     }
 }
 
+impl FromStr for TransactionStatus {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "PENDING" => Ok(Self::Pending),
+            "PROCESSING" => Ok(Self::Processing),
+            "COMPLETED" => Ok(Self::Completed),
+            "FAILED" => Ok(Self::Failed),
+            "CANCELLED" => Ok(Self::Cancelled),
+            other => Err(AppError::Validation(format!("Unknown status: {}", other))),
+        }
+    }
+}
+
 impl TransactionStatus {
+    pub const ALL: [TransactionStatus; 5] = [
+        Self::Pending,
+        Self::Processing,
+        Self::Completed,
+        Self::Failed,
+        Self::Cancelled,
+    ];
+
     /// Returns whether transitioning from `self` to `target` is allowed.
     pub fn can_transition_to(self, target: Self) -> bool {
         matches!(
@@ -31,11 +63,14 @@ impl TransactionStatus {
             (Self::Pending, Self::Completed)
                 | (Self::Pending, Self::Failed)
                 | (Self::Pending, Self::Cancelled)
+                | (Self::Pending, Self::Processing)
+                | (Self::Processing, Self::Completed)
+                | (Self::Processing, Self::Failed)
         )
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
 #[serde(rename_all = "UPPERCASE")]
 pub enum Currency {
     Usd,
@@ -47,37 +82,996 @@ pub enum Currency {
     Chf,
 }
 
+impl fmt::Display for Currency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Usd => write!(f, "USD"),
+            Self::Eur => write!(f, "EUR"),
+            Self::Gbp => write!(f, "GBP"),
+            Self::Jpy => write!(f, "JPY"),
+            Self::Cad => write!(f, "CAD"),
+            Self::Aud => write!(f, "AUD"),
+            Self::Chf => write!(f, "CHF"),
+        }
+    }
+}
+
+/// Codes are matched case-insensitively, so `usd` and `Usd` are `USD`.
+impl FromStr for Currency {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_uppercase().as_str() {
+            "USD" => Ok(Self::Usd),
+            "EUR" => Ok(Self::Eur),
+            "GBP" => Ok(Self::Gbp),
+            "JPY" => Ok(Self::Jpy),
+            "CAD" => Ok(Self::Cad),
+            "AUD" => Ok(Self::Aud),
+            "CHF" => Ok(Self::Chf),
+            _ => Err(AppError::Validation(format!(
+                "Unknown currency {:?}; expected one of {}",
+                s,
+                Self::ALLOWED.join(", ")
+            ))),
+        }
+    }
+}
+
+/// Goes through `FromStr` so unknown codes report the supported list instead of serde's
+/// variant names.
+impl<'de> Deserialize<'de> for Currency {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let code = String::deserialize(deserializer)?;
+        code.parse().map_err(|e: AppError| match e {
+            AppError::Validation(message) => serde::de::Error::custom(message),
+            other => serde::de::Error::custom(other),
+        })
+    }
+}
+
 impl Currency {
     pub const ALLOWED: &[&str] = &["USD", "EUR", "GBP", "JPY", "CAD", "AUD", "CHF"];
+
+    pub const ALL: [Currency; 7] = [
+        Self::Usd,
+        Self::Eur,
+        Self::Gbp,
+        Self::Jpy,
+        Self::Cad,
+        Self::Aud,
+        Self::Chf,
+    ];
+
+    /// Default maximum single-transaction amount, in major units of the currency.
+    pub fn max_amount(self) -> f64 {
+        match self {
+            Self::Usd | Self::Eur | Self::Gbp | Self::Chf => 1_000_000.0,
+            Self::Cad | Self::Aud => 1_500_000.0,
+            Self::Jpy => 150_000_000.0,
+        }
+    }
+
+    /// Number of minor-unit digits, e.g. 2 for cents.
+    pub fn decimal_places(self) -> i32 {
+        match self {
+            Self::Jpy => 0,
+            _ => 2,
+        }
+    }
+
+    /// Minor units per major unit, e.g. 100 for cents.
+    pub fn minor_per_major(self) -> i64 {
+        10i64.pow(self.decimal_places() as u32)
+    }
+
+    /// Converts a decimal `amount` to whole minor units, e.g. 150.75 USD to 15075.
+    pub fn to_minor(self, amount: f64, mode: RoundingMode) -> i64 {
+        mode.round_to_places(amount, self.decimal_places() as u32)
+    }
+
+    /// Decimal view of `minor` units, as sent on the wire.
+    pub fn to_major(self, minor: i64) -> f64 {
+        minor as f64 / self.minor_per_major() as f64
+    }
+
+    /// Rounds `amount` to this currency's minor unit.
+    pub fn round(self, amount: f64, mode: RoundingMode) -> f64 {
+        self.to_major(self.to_minor(amount, mode))
+    }
+
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Self::Usd => "$",
+            Self::Eur => "€",
+            Self::Gbp => "£",
+            Self::Jpy => "¥",
+            Self::Cad => "CA$",
+            Self::Aud => "A$",
+            Self::Chf => "CHF",
+        }
+    }
+
+    /// Display form of `minor` units, e.g. `$150.75` or `¥100`. Letter symbols get a separating
+    /// space (`CHF 12.00`).
+    pub fn format(self, minor: i64) -> String {
+        let symbol = self.symbol();
+        let space = if symbol.ends_with(char::is_alphabetic) { " " } else { "" };
+        let decimal = self.to_decimal_string(minor);
+        match decimal.strip_prefix('-') {
+            Some(digits) => format!("-{}{}{}", symbol, space, digits),
+            None => format!("{}{}{}", symbol, space, decimal),
+        }
+    }
+
+    /// `minor` units as an exact decimal with this currency's places, e.g. `150.75` or `3.00`.
+    pub fn to_decimal_string(self, minor: i64) -> String {
+        let sign = if minor < 0 { "-" } else { "" };
+        let scale = self.minor_per_major().unsigned_abs();
+        let (whole, fraction) = (minor.unsigned_abs() / scale, minor.unsigned_abs() % scale);
+        match self.decimal_places() as usize {
+            0 => format!("{}{}", sign, whole),
+            places => format!("{}{}.{:0places$}", sign, whole, fraction),
+        }
+    }
+}
+
+/// How amounts are brought to a currency's minor unit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RoundingMode {
+    /// Ties away from zero: 0.125 becomes 0.13.
+    #[default]
+    HalfUp,
+    /// Banker's rounding, ties to the even digit: 0.125 becomes 0.12.
+    HalfEven,
+    /// Always down: 0.129 becomes 0.12.
+    Floor,
+}
+
+impl RoundingMode {
+    /// Rounds `value` to `places` decimal digits, returned as a whole number of `10^-places`
+    /// units. Works on the shortest decimal form of `value` (what `Display` prints, e.g.
+    /// `1.005`) rather than on `value * 10^places`, so a tie the client wrote stays a tie even
+    /// though its binary value sits just below it.
+    pub(crate) fn round_to_places(self, value: f64, places: u32) -> i64 {
+        if !value.is_finite() {
+            return value as i64;
+        }
+        let text = value.abs().to_string();
+        let (int_digits, frac_digits) = text.split_once('.').unwrap_or((&text, ""));
+        let places = places as usize;
+        let kept: String = frac_digits.chars().chain(std::iter::repeat('0')).take(places).collect();
+        let dropped = frac_digits.get(places..).unwrap_or_default();
+        let whole = format!("{}{}", int_digits, kept).parse().unwrap_or(i64::MAX);
+
+        let vs_half = match dropped.as_bytes() {
+            [] => None,
+            _ if dropped.bytes().all(|d| d == b'0') => None,
+            [first, rest @ ..] => Some(match first.cmp(&b'5') {
+                Ordering::Equal if rest.iter().any(|d| *d != b'0') => Ordering::Greater,
+                other => other,
+            }),
+        };
+        self.finish(whole, vs_half, value < 0.0)
+    }
+
+    /// `numerator / denominator` rounded to a whole number, computed exactly.
+    pub(crate) fn divide(self, numerator: i64, denominator: i64) -> i64 {
+        let (n, d) = (numerator.unsigned_abs(), denominator.unsigned_abs());
+        let (whole, remainder) = (n / d, n % d);
+        let vs_half = (remainder != 0).then(|| (remainder * 2).cmp(&d));
+        self.finish(whole as i64, vs_half, (numerator < 0) != (denominator < 0))
+    }
+
+    /// Rounds the magnitude `whole`, given how the discarded remainder compares to one half
+    /// (`None` when nothing was discarded), and restores the sign.
+    fn finish(self, whole: i64, vs_half: Option<Ordering>, negative: bool) -> i64 {
+        let round_away = match (self, vs_half) {
+            (_, None) => false,
+            (Self::HalfUp, Some(vs_half)) => vs_half != Ordering::Less,
+            (Self::HalfEven, Some(vs_half)) => {
+                vs_half == Ordering::Greater || (vs_half == Ordering::Equal && whole % 2 == 1)
+            }
+            // Toward negative infinity, so only negative values move away from zero.
+            (Self::Floor, Some(_)) => negative,
+        };
+        let magnitude = whole.saturating_add(i64::from(round_away));
+        if negative {
+            -magnitude
+        } else {
+            magnitude
+        }
+    }
+}
+
+impl FromStr for RoundingMode {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "half_up" => Ok(Self::HalfUp),
+            "half_even" => Ok(Self::HalfEven),
+            "floor" => Ok(Self::Floor),
+            other => Err(AppError::Validation(format!(
+                "Unknown rounding mode {:?}; expected half_up, half_even or floor",
+                other
+            ))),
+        }
+    }
+}
+
+/// Deployment-wide set of currencies accepted for new transactions. Unlike the admin toggle,
+/// this is fixed at startup.
+#[derive(Debug, Clone, Default)]
+pub struct CurrencyPolicy {
+    /// `None` allows every supported currency.
+    allowed: Option<HashSet<Currency>>,
+}
+
+impl CurrencyPolicy {
+    pub fn allow_all() -> Self {
+        Self::default()
+    }
+
+    pub fn only(currencies: impl IntoIterator<Item = Currency>) -> Self {
+        Self {
+            allowed: Some(currencies.into_iter().collect()),
+        }
+    }
+
+    pub fn allows(&self, currency: Currency) -> bool {
+        self.allowed.as_ref().is_none_or(|set| set.contains(&currency))
+    }
 }
 
+// `remote = "Self"` turns the derives into inherent functions so the impls below can put the
+// decimal `amount` and `formatted_amount` on the wire in place of `amount_minor`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(remote = "Self")]
 pub struct Transaction {
     pub id: Uuid,
-    pub idempotency_key: String,
-    pub amount: f64,
+    /// `None` for transactions created without a key; those never replay.
+    #[serde(default)]
+    pub idempotency_key: Option<String>,
+    /// Caller the idempotency key is scoped to; keys never replay across tenants.
+    pub tenant_id: Option<String>,
+    /// Amount in the currency's minor units (cents for USD), so stored values and sums never
+    /// drift. Serialized as the decimal `amount`.
+    #[serde(skip)]
+    pub amount_minor: i64,
+    /// Fee withheld from the amount, in minor units, when a fee policy applied at creation.
+    /// Serialized as the decimal `fee_amount` alongside `net_amount`.
+    #[serde(skip)]
+    pub fee_minor: Option<i64>,
     pub currency: Currency,
     pub description: String,
     pub status: TransactionStatus,
+    pub expires_at: Option<DateTime<Utc>>,
+    /// When the scheduler should move the transaction from `PENDING` to `PROCESSING`.
+    #[serde(default)]
+    pub execute_at: Option<DateTime<Utc>>,
+    /// Incremented on every mutation; used for optimistic concurrency control.
+    pub version: u64,
+    #[serde(with = "timestamp_precision")]
     pub created_at: DateTime<Utc>,
+    #[serde(with = "timestamp_precision")]
     pub updated_at: DateTime<Utc>,
+    /// Set when the transaction is soft-deleted; deleted transactions are hidden from reads.
+    pub deleted_at: Option<DateTime<Utc>>,
+    /// Derived fields from the configured `EnrichmentProvider`, if any.
+    pub enrichment: Option<Enrichment>,
+    /// Why the transaction was voided or cancelled, when one was given.
+    pub cancellation_reason: Option<String>,
+    /// Why the transaction was marked failed.
+    pub failure_reason: Option<String>,
+    /// Rate from `currency` to the reporting currency as stored when the transaction was
+    /// created, if snapshotting is configured and a rate was known.
+    #[serde(default)]
+    pub exchange_rate: Option<ExchangeRate>,
+    /// Lowercase labels for grouping and filtering.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Payload of the create that made the transaction. Edits leave it alone, so a retry of
+    /// that create still replays. Not part of the API representation.
+    #[serde(skip)]
+    pub created_with: Option<CreateFingerprint>,
 }
 
-#[derive(Debug, Deserialize)]
+/// The parts of a create request an idempotent retry must repeat.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CreateFingerprint {
+    pub amount_minor: i64,
+    pub currency: Currency,
+    pub description: String,
+}
+
+impl Transaction {
+    /// Field names on the wire, in serialization order.
+    pub const FIELDS: &[&str] = &[
+        "amount",
+        "id",
+        "idempotency_key",
+        "tenant_id",
+        "currency",
+        "description",
+        "status",
+        "expires_at",
+        "execute_at",
+        "version",
+        "created_at",
+        "updated_at",
+        "deleted_at",
+        "enrichment",
+        "cancellation_reason",
+        "failure_reason",
+        "exchange_rate",
+        "tags",
+        "formatted_amount",
+        "fee_amount",
+        "net_amount",
+    ];
+
+    /// Decimal amount in major units, e.g. 150.75.
+    pub fn amount(&self) -> f64 {
+        self.currency.to_major(self.amount_minor)
+    }
+
+    /// What remains of the amount after the fee, in minor units.
+    pub fn net_minor(&self) -> Option<i64> {
+        self.fee_minor.map(|fee| self.amount_minor - fee)
+    }
+
+    /// Whether a create with this payload repeats the one that made the transaction. Without
+    /// a recorded fingerprint the current fields stand in for it.
+    pub fn created_with_same(
+        &self,
+        amount_minor: i64,
+        currency: Currency,
+        description: &str,
+    ) -> bool {
+        match &self.created_with {
+            Some(original) => {
+                original.amount_minor == amount_minor
+                    && original.currency == currency
+                    && original.description == description
+            }
+            None => {
+                self.amount_minor == amount_minor
+                    && self.currency == currency
+                    && self.description == description
+            }
+        }
+    }
+}
+
+impl Serialize for Transaction {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use amount_format::Decimal;
+
+        #[derive(Serialize)]
+        struct WithAmount<'a> {
+            amount: Decimal,
+            #[serde(flatten, serialize_with = "Transaction::serialize")]
+            txn: &'a Transaction,
+            formatted_amount: String,
+            fee_amount: Option<Decimal>,
+            net_amount: Option<Decimal>,
+        }
+
+        let decimal = |minor| Decimal::new(self.currency, minor);
+        WithAmount {
+            amount: decimal(self.amount_minor),
+            txn: self,
+            formatted_amount: self.currency.format(self.amount_minor),
+            fee_amount: self.fee_minor.map(decimal),
+            net_amount: self.net_minor().map(decimal),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Transaction {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct WithAmount {
+            #[serde(deserialize_with = "amount_format::number_or_string")]
+            amount: f64,
+            #[serde(flatten, deserialize_with = "Transaction::deserialize")]
+            txn: Transaction,
+            #[serde(default, deserialize_with = "amount_format::optional_number_or_string")]
+            fee_amount: Option<f64>,
+        }
+
+        let WithAmount {
+            amount,
+            mut txn,
+            fee_amount,
+        } = WithAmount::deserialize(deserializer)?;
+        txn.amount_minor = txn.currency.to_minor(amount, RoundingMode::HalfUp);
+        txn.fee_minor = fee_amount.map(|fee| txn.currency.to_minor(fee, RoundingMode::HalfUp));
+        Ok(txn)
+    }
+}
+
+/// Wire format for decimal amounts (`amount`, `fee_amount`, `net_amount`). Numbers by default;
+/// inside `scope(true, ..)` they are quoted exact decimals such as `"150.75"`, for clients that
+/// parse JSON numbers as doubles. Either form is accepted on deserialization.
+pub mod amount_format {
+    use std::future::Future;
+
+    use serde::{Deserialize, Deserializer, Serialize};
+
+    use super::Currency;
+
+    tokio::task_local! {
+        static AS_STRINGS: bool;
+    }
+
+    /// Whether amounts serialized on this task are written as strings.
+    pub fn as_strings() -> bool {
+        AS_STRINGS.try_with(|as_strings| *as_strings).unwrap_or(false)
+    }
+
+    /// Runs `fut` with amounts serialized as strings when `as_strings` is set.
+    pub async fn scope<F: Future>(as_strings: bool, fut: F) -> F::Output {
+        AS_STRINGS.scope(as_strings, fut).await
+    }
+
+    #[derive(Serialize)]
+    #[serde(untagged)]
+    pub(super) enum Decimal {
+        Number(f64),
+        Text(String),
+    }
+
+    impl Decimal {
+        pub(super) fn new(currency: Currency, minor: i64) -> Self {
+            if as_strings() {
+                Self::Text(currency.to_decimal_string(minor))
+            } else {
+                Self::Number(currency.to_major(minor))
+            }
+        }
+    }
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum NumberOrString {
+        Number(f64),
+        Text(String),
+    }
+
+    impl NumberOrString {
+        fn into_number<E: serde::de::Error>(self) -> Result<f64, E> {
+            match self {
+                Self::Number(n) => Ok(n),
+                Self::Text(s) => s
+                    .trim()
+                    .parse()
+                    .map_err(|_| E::custom(format!("invalid decimal amount {:?}", s))),
+            }
+        }
+    }
+
+    pub(super) fn number_or_string<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<f64, D::Error> {
+        NumberOrString::deserialize(deserializer)?.into_number()
+    }
+
+    pub(super) fn optional_number_or_string<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<f64>, D::Error> {
+        Option::<NumberOrString>::deserialize(deserializer)?
+            .map(NumberOrString::into_number)
+            .transpose()
+    }
+}
+
+/// Wire format for `created_at`/`updated_at`. Values are stored at full precision; inside a
+/// whole-seconds scope they are serialized truncated, e.g. `2024-01-01T12:00:00Z`. Any RFC 3339
+/// timestamp is accepted on deserialization.
+pub mod timestamp_precision {
+    use std::future::Future;
+
+    use chrono::{DateTime, SecondsFormat, SubsecRound, Utc};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    tokio::task_local! {
+        static WHOLE_SECONDS: bool;
+    }
+
+    /// Whether timestamps serialized on this task drop their fractional seconds.
+    pub fn whole_seconds() -> bool {
+        WHOLE_SECONDS.try_with(|whole| *whole).unwrap_or(false)
+    }
+
+    /// Runs `fut` with timestamps serialized in whole seconds when `whole_seconds` is set.
+    pub async fn scope<F: Future>(whole_seconds: bool, fut: F) -> F::Output {
+        WHOLE_SECONDS.scope(whole_seconds, fut).await
+    }
+
+    /// Runs `f` at full precision whatever the surrounding scope, for writes that must not
+    /// lose it.
+    pub fn full_precision<R>(f: impl FnOnce() -> R) -> R {
+        WHOLE_SECONDS.sync_scope(false, f)
+    }
+
+    pub fn serialize<S: Serializer>(
+        value: &DateTime<Utc>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        if whole_seconds() {
+            let truncated = value.trunc_subsecs(0);
+            serializer.serialize_str(&truncated.to_rfc3339_opts(SecondsFormat::Secs, true))
+        } else {
+            value.serialize(serializer)
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<DateTime<Utc>, D::Error> {
+        DateTime::deserialize(deserializer)
+    }
+}
+
+impl Transaction {
+    /// Returns whether a pending transaction has outlived its expiry at `now`. A per-transaction
+    /// `expires_at` takes precedence over `default_ttl` measured from `created_at`.
+    pub fn is_expired(&self, now: DateTime<Utc>, default_ttl: TimeDelta) -> bool {
+        if self.status != TransactionStatus::Pending {
+            return false;
+        }
+        let deadline = self.expires_at.unwrap_or(self.created_at + default_ttl);
+        now >= deadline
+    }
+}
+
+#[cfg(test)]
+impl Transaction {
+    /// A pending 10.00 USD transaction with a fresh id and idempotency key, created at `now`.
+    /// Tests override what they care about with struct update syntax.
+    pub(crate) fn test_pending(now: DateTime<Utc>) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            idempotency_key: Some(Uuid::new_v4().to_string()),
+            tenant_id: None,
+            amount_minor: 1000,
+            fee_minor: None,
+            currency: Currency::Usd,
+            description: "test".into(),
+            status: TransactionStatus::Pending,
+            expires_at: None,
+            execute_at: None,
+            version: 1,
+            created_at: now,
+            updated_at: now,
+            deleted_at: None,
+            enrichment: None,
+            cancellation_reason: None,
+            failure_reason: None,
+            exchange_rate: None,
+            tags: Vec::new(),
+            created_with: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct CreateTransactionRequest {
-    pub idempotency_key: String,
+    /// Omit to always create a new transaction, unless the service requires keys.
+    #[serde(default)]
+    pub idempotency_key: Option<String>,
+    /// Idempotency scope, set from the caller's identity rather than the request body.
+    #[serde(skip)]
+    pub tenant_id: Option<String>,
     pub amount: f64,
-    pub currency: Currency,
+    /// Falls back to the service's default currency when omitted.
+    #[serde(default)]
+    pub currency: Option<Currency>,
     pub description: String,
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Defer processing until this time; see `scheduler`.
+    #[serde(default)]
+    pub execute_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BatchCreateRequest {
+    pub items: Vec<CreateTransactionRequest>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BatchGetRequest {
+    pub ids: Vec<Uuid>,
+}
+
+/// Transactions found by a batch get, in request order, and the ids that matched nothing.
+#[derive(Debug, Serialize)]
+pub struct BatchGetResult {
+    pub found: Vec<Transaction>,
+    pub missing: Vec<Uuid>,
 }
 
 #[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StatusBatchRequest {
+    pub ids: Vec<Uuid>,
+    pub status: TransactionStatus,
+    /// Applied to every id; required for the same statuses as a single update.
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+/// What happened to one id of a batch status update.
+#[derive(Debug, Serialize)]
+#[serde(tag = "result", rename_all = "snake_case")]
+pub enum StatusBatchOutcome {
+    Updated { transaction: Box<Transaction> },
+    NotFound,
+    InvalidTransition { from: String },
+    Failed { error: serde_json::Value },
+}
+
+#[derive(Debug, Serialize)]
+pub struct StatusBatchItem {
+    pub id: Uuid,
+    #[serde(flatten)]
+    pub outcome: StatusBatchOutcome,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct UpdateStatusRequest {
     pub status: TransactionStatus,
+    /// When set, the update only applies if the stored version still matches.
+    #[serde(default)]
+    pub expected_version: Option<u64>,
+    /// Required when moving to `FAILED` or `CANCELLED`.
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+/// Partial update of a transaction's mutable fields. Absent fields are left unchanged.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UpdateTransactionRequest {
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UpdateAmountRequest {
+    pub amount: f64,
+}
+
+/// Free-text annotation on a transaction. Notes are append-only and never change the
+/// transaction itself.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Note {
+    pub id: Uuid,
+    pub transaction_id: Uuid,
+    pub author: String,
+    pub text: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AddNoteRequest {
+    pub author: String,
+    pub text: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VoidRequest {
+    /// Required; an empty body or blank reason is rejected like a reasonless cancel.
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SetCurrencyEnabledRequest {
+    pub enabled: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CurrencyState {
+    pub currency: Currency,
+    pub enabled: bool,
+}
+
+/// Units of `quote` per one unit of `base`, as known at `as_of`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExchangeRate {
+    pub base: Currency,
+    pub quote: Currency,
+    pub rate: f64,
+    pub as_of: DateTime<Utc>,
 }
 
 #[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SetExchangeRateRequest {
+    pub rate: f64,
+    /// When the rate was observed; defaults to now.
+    #[serde(default)]
+    pub as_of: Option<DateTime<Utc>>,
+}
+
+/// A dimension to aggregate counts over when listing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Facet {
+    Status,
+    Currency,
+}
+
+impl FromStr for Facet {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "status" => Ok(Self::Status),
+            "currency" => Ok(Self::Currency),
+            other => Err(AppError::Validation(format!("Unknown facet: {}", other))),
+        }
+    }
+}
+
+/// One of `Transaction::FIELDS`, as selected by `?fields=`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransactionField(&'static str);
+
+impl TransactionField {
+    pub fn name(self) -> &'static str {
+        self.0
+    }
+}
+
+impl FromStr for TransactionField {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Transaction::FIELDS
+            .iter()
+            .find(|field| **field == s)
+            .map(|field| Self(field))
+            .ok_or_else(|| {
+                AppError::Validation(format!(
+                    "Unknown field {:?}; expected one of {}",
+                    s,
+                    Transaction::FIELDS.join(", ")
+                ))
+            })
+    }
+}
+
+/// Counts per bucket across the full filtered set, for each requested facet.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Facets {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<BTreeMap<TransactionStatus, usize>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub currency: Option<BTreeMap<Currency, usize>>,
+}
+
+/// Deserializes `a,b,c` query values into a list, skipping empty segments.
+pub(crate) fn comma_separated<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    let raw = Option::<String>::deserialize(deserializer)?;
+    raw.as_deref()
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .map(|part| part.parse().map_err(serde::de::Error::custom))
+        .collect()
+}
+
+#[derive(Debug, Default, Deserialize)]
 pub struct ListFilters {
-    pub status: Option<TransactionStatus>,
+    /// Any of these statuses, e.g. `?status=PENDING,FAILED`; empty matches all.
+    #[serde(default, deserialize_with = "comma_separated")]
+    pub status: Vec<TransactionStatus>,
     pub currency: Option<Currency>,
+    /// Case-insensitive substring match on the description.
+    pub q: Option<String>,
+    /// Only transactions carrying this tag (case-insensitive).
+    pub tag: Option<String>,
+    /// Page size; omitted returns up to the service's `max_list_results`, with `next_cursor`
+    /// set when more follow.
+    pub limit: Option<usize>,
+    /// `next_cursor` from the previous page.
+    pub cursor: Option<String>,
+    /// 1-based page number for offset pagination; exclusive with `cursor` and `limit`.
+    pub page: Option<usize>,
+    /// Page size for offset pagination.
+    pub per_page: Option<usize>,
+    /// Facets to count, e.g. `?facets=status,currency`.
+    #[serde(default, deserialize_with = "comma_separated")]
+    pub facets: Vec<Facet>,
+}
+
+/// Optional `created_at` window for `GET /transactions/balance`; `to` is exclusive.
+#[derive(Debug, Default, Deserialize)]
+pub struct BalanceFilters {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    /// Also report the balance converted into this currency.
+    pub convert_to: Option<Currency>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Balance {
+    pub by_currency: BTreeMap<Currency, f64>,
+    /// Present when `convert_to` was requested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub converted: Option<ConvertedTotal>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ConvertedTotal {
+    pub currency: Currency,
+    pub total: f64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[tokio::test]
+    async fn whole_second_timestamps() {
+        let created_at = Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap()
+            + TimeDelta::milliseconds(789);
+        let txn = pending_at(created_at, None);
+
+        let (json, full) = timestamp_precision::scope(true, async {
+            let json = serde_json::to_value(&txn).unwrap();
+            let full = timestamp_precision::full_precision(|| serde_json::to_value(&txn).unwrap());
+            (json, full)
+        })
+        .await;
+        assert_eq!(full["created_at"], "2024-01-01T12:00:00.789Z");
+        assert_eq!(serde_json::to_value(&txn).unwrap()["created_at"], full["created_at"]);
+
+        assert_eq!(json["created_at"], "2024-01-01T12:00:00Z");
+        assert_eq!(json["updated_at"], "2024-01-01T12:00:00Z");
+        let parsed: Transaction = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed.created_at, Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap());
+
+        let json = serde_json::to_value(&txn).unwrap();
+        let parsed: Transaction = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed.created_at, created_at);
+    }
+
+    fn pending_at(created_at: DateTime<Utc>, expires_at: Option<DateTime<Utc>>) -> Transaction {
+        Transaction {
+            expires_at,
+            ..Transaction::test_pending(created_at)
+        }
+    }
+
+    #[test]
+    fn rounding_modes_differ_on_ties() {
+        assert_eq!(Currency::Usd.round(0.125, RoundingMode::HalfUp), 0.13);
+        assert_eq!(Currency::Usd.round(0.125, RoundingMode::HalfEven), 0.12);
+        assert_eq!(Currency::Usd.round(0.135, RoundingMode::HalfEven), 0.14);
+        assert_eq!(Currency::Usd.round(0.129, RoundingMode::Floor), 0.12);
+        assert_eq!(Currency::Usd.round(0.29, RoundingMode::Floor), 0.29);
+        assert_eq!(Currency::Jpy.round(2.5, RoundingMode::HalfEven), 2.0);
+    }
+
+    #[test]
+    fn ties_round_on_the_decimal_value_not_its_binary_approximation() {
+        // Neither 1.005 nor 1.015 is exact in binary; both sit just below the tie.
+        assert_eq!(Currency::Usd.to_minor(1.005, RoundingMode::HalfUp), 101);
+        assert_eq!(Currency::Usd.to_minor(1.015, RoundingMode::HalfEven), 102);
+        assert_eq!(Currency::Usd.to_minor(1.025, RoundingMode::HalfEven), 102);
+        assert_eq!(Currency::Usd.to_minor(2.675, RoundingMode::HalfUp), 268);
+        assert_eq!(Currency::Usd.to_minor(0.57, RoundingMode::Floor), 57);
+        assert_eq!(Currency::Usd.to_minor(1.0051, RoundingMode::HalfEven), 101);
+        assert_eq!(Currency::Usd.to_minor(-1.005, RoundingMode::HalfUp), -101);
+        assert_eq!(Currency::Usd.to_minor(-0.001, RoundingMode::Floor), -1);
+        assert_eq!(Currency::Jpy.to_minor(1234.5, RoundingMode::HalfEven), 1234);
+
+        assert_eq!(RoundingMode::HalfUp.divide(15, 10), 2);
+        assert_eq!(RoundingMode::HalfEven.divide(25, 10), 2);
+        assert_eq!(RoundingMode::Floor.divide(29, 10), 2);
+    }
+
+    #[test]
+    fn currency_codes_parse_case_insensitively() {
+        for code in ["usd", "Usd", "USD"] {
+            assert_eq!(code.parse::<Currency>().unwrap(), Currency::Usd);
+        }
+        let parsed: Currency = serde_json::from_str("\"eur\"").unwrap();
+        assert_eq!(serde_json::to_string(&parsed).unwrap(), "\"EUR\"");
+    }
+
+    #[test]
+    fn formatted_amount_follows_currency() {
+        assert_eq!(Currency::Usd.format(15075), "$150.75");
+        assert_eq!(Currency::Usd.format(300), "$3.00");
+        assert_eq!(Currency::Usd.format(5), "$0.05");
+        assert_eq!(Currency::Jpy.format(100), "¥100");
+        assert_eq!(Currency::Chf.format(1200), "CHF 12.00");
+    }
+
+    #[test]
+    fn field_list_matches_serialized_keys() {
+        let json = serde_json::to_value(pending_at(Utc::now(), None)).unwrap();
+        let mut keys: Vec<&str> = json.as_object().unwrap().keys().map(String::as_str).collect();
+        let mut fields = Transaction::FIELDS.to_vec();
+        keys.sort_unstable();
+        fields.sort_unstable();
+        assert_eq!(keys, fields);
+    }
+
+    #[test]
+    fn amounts_are_stored_as_minor_units() {
+        assert_eq!(Currency::Usd.to_minor(150.75, RoundingMode::HalfUp), 15075);
+        assert_eq!(Currency::Usd.to_minor(0.29, RoundingMode::Floor), 29);
+        assert_eq!(Currency::Jpy.to_minor(1234.5, RoundingMode::HalfUp), 1235);
+
+        let mut txn = pending_at(Utc::now(), None);
+        txn.amount_minor = 15075;
+        let json = serde_json::to_value(&txn).unwrap();
+        assert_eq!(json["amount"], 150.75);
+        assert_eq!(json["formatted_amount"], "$150.75");
+        assert!(json.get("amount_minor").is_none());
+        let parsed: Transaction = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed.amount_minor, 15075);
+    }
+
+    #[tokio::test]
+    async fn amounts_serialize_as_strings_within_scope() {
+        let mut txn = pending_at(Utc::now(), None);
+        txn.amount_minor = 15075;
+        txn.fee_minor = Some(300);
+
+        let json = amount_format::scope(true, async { serde_json::to_value(&txn).unwrap() }).await;
+        assert_eq!(json["amount"], "150.75");
+        assert_eq!(json["fee_amount"], "3.00");
+        assert_eq!(json["net_amount"], "147.75");
+        let parsed: Transaction = serde_json::from_value(json).unwrap();
+        assert_eq!((parsed.amount_minor, parsed.fee_minor), (15075, Some(300)));
+
+        assert_eq!(serde_json::to_value(&txn).unwrap()["amount"], 150.75);
+    }
+
+    #[test]
+    fn per_transaction_ttl_expires_before_default() {
+        let created = Utc::now();
+        let default_ttl = TimeDelta::hours(24);
+        let quote = pending_at(created, Some(created + TimeDelta::minutes(5)));
+        let invoice = pending_at(created, None);
+
+        let now = created + TimeDelta::minutes(10);
+        assert!(quote.is_expired(now, default_ttl));
+        assert!(!invoice.is_expired(now, default_ttl));
+
+        let later = created + TimeDelta::hours(25);
+        assert!(invoice.is_expired(later, default_ttl));
+    }
+
+    #[test]
+    fn non_pending_never_expires() {
+        let created = Utc::now();
+        let mut txn = pending_at(created, Some(created + TimeDelta::minutes(1)));
+        txn.status = TransactionStatus::Completed;
+        assert!(!txn.is_expired(created + TimeDelta::hours(1), TimeDelta::hours(1)));
+    }
 }
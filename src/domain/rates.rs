@@ -0,0 +1,69 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use futures_util::future::BoxFuture;
+
+use crate::domain::models::Currency;
+use crate::error::AppError;
+
+/// Source of exchange rates for reporting conversions. Implementations may call out to a rates
+/// service; `Ok(None)` means the pair is unknown rather than that the lookup failed.
+pub trait ExchangeRateProvider: Send + Sync + 'static {
+    /// Units of `to` per one unit of `from`.
+    fn rate(&self, from: Currency, to: Currency) -> BoxFuture<'_, Result<Option<f64>, AppError>>;
+}
+
+/// Provider backed by a fixed table. A pair also answers its inverse, and every currency
+/// converts to itself at 1.
+#[derive(Debug, Clone, Default)]
+pub struct StaticRates {
+    rates: HashMap<(Currency, Currency), f64>,
+}
+
+impl StaticRates {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_rate(mut self, from: Currency, to: Currency, rate: f64) -> Self {
+        self.rates.insert((from, to), rate);
+        self
+    }
+
+    fn lookup(&self, from: Currency, to: Currency) -> Option<f64> {
+        if from == to {
+            return Some(1.0);
+        }
+        self.rates
+            .get(&(from, to))
+            .copied()
+            .or_else(|| self.rates.get(&(to, from)).map(|rate| 1.0 / rate))
+    }
+}
+
+impl ExchangeRateProvider for StaticRates {
+    fn rate(&self, from: Currency, to: Currency) -> BoxFuture<'_, Result<Option<f64>, AppError>> {
+        let rate = self.lookup(from, to);
+        Box::pin(async move { Ok(rate) })
+    }
+}
+
+/// Parses `FROM:TO=rate` pairs separated by commas, e.g. `EUR:USD=1.08,GBP:USD=1.27`.
+impl FromStr for StaticRates {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut rates = Self::new();
+        for entry in s.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let invalid = || AppError::Validation(format!("Invalid exchange rate: {}", entry));
+            let (pair, rate) = entry.split_once('=').ok_or_else(invalid)?;
+            let (from, to) = pair.split_once(':').ok_or_else(invalid)?;
+            let rate: f64 = rate.trim().parse().map_err(|_| invalid())?;
+            if !rate.is_finite() || rate <= 0.0 {
+                return Err(invalid());
+            }
+            rates = rates.with_rate(from.trim().parse()?, to.trim().parse()?, rate);
+        }
+        Ok(rates)
+    }
+}
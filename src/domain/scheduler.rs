@@ -0,0 +1,103 @@
+use std::time::Duration;
+
+use tokio::task::JoinHandle;
+
+use crate::domain::models::{Transaction, TransactionStatus};
+use crate::domain::service::TransactionService;
+use crate::domain::sweep::sweep_pending;
+use crate::error::AppError;
+use crate::storage::Storage;
+
+/// Moves pending transactions whose `execute_at` has passed to `PROCESSING`. Returns how many
+/// were advanced. Transactions that change concurrently are skipped rather than treated as
+/// failures.
+pub async fn advance_due<S: Storage>(service: &TransactionService<S>) -> Result<usize, AppError> {
+    let now = service.now();
+    let due = |txn: &Transaction| txn.execute_at.is_some_and(|at| at <= now);
+    sweep_pending(service, due, TransactionStatus::Processing, None).await
+}
+
+/// Runs `advance_due` every `interval` for the lifetime of the process.
+pub fn spawn_scheduler_task<S: Storage + Clone>(
+    service: TransactionService<S>,
+    interval: Duration,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            match advance_due(&service).await {
+                Ok(0) => {}
+                Ok(count) => tracing::info!(count, "moved scheduled transactions to processing"),
+                Err(e) => tracing::error!(error = %e, "schedule scan failed"),
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::clock::FixedClock;
+    use crate::domain::models::{CreateTransactionRequest, Currency};
+    use crate::domain::service::MAX_PAGE_SIZE;
+    use crate::storage::memory::InMemoryStorage;
+    use chrono::{TimeDelta, Utc};
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn advances_once_execute_at_passes() {
+        let clock = Arc::new(FixedClock::new(Utc::now()));
+        let service = TransactionService::new(InMemoryStorage::new()).with_clock(clock.clone());
+        let req = |key: &str, execute_at| CreateTransactionRequest {
+            idempotency_key: Some(key.into()),
+            tenant_id: None,
+            amount: 10.0,
+            currency: Some(Currency::Usd),
+            description: "scheduled".into(),
+            expires_at: None,
+            execute_at,
+            tags: Vec::new(),
+        };
+        let (scheduled, _) = service
+            .create(req("scheduled", Some(Utc::now() + TimeDelta::seconds(30))))
+            .await
+            .unwrap();
+        let (unscheduled, _) = service.create(req("unscheduled", None)).await.unwrap();
+
+        assert_eq!(advance_due(&service).await.unwrap(), 0);
+        clock.advance(TimeDelta::minutes(1));
+        assert_eq!(advance_due(&service).await.unwrap(), 1);
+
+        let scheduled = service.get(scheduled.id).await.unwrap();
+        assert_eq!(scheduled.status, TransactionStatus::Processing);
+        let unscheduled = service.get(unscheduled.id).await.unwrap();
+        assert_eq!(unscheduled.status, TransactionStatus::Pending);
+        assert_eq!(advance_due(&service).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn advances_past_the_list_cap_and_across_pages() {
+        let clock = Arc::new(FixedClock::new(Utc::now()));
+        let service = TransactionService::new(InMemoryStorage::new())
+            .with_clock(clock.clone())
+            .with_max_list_results(10);
+        let due = MAX_PAGE_SIZE + 1;
+        for i in 0..due {
+            let req = CreateTransactionRequest {
+                idempotency_key: Some(format!("due-{}", i)),
+                tenant_id: None,
+                amount: 10.0,
+                currency: Some(Currency::Usd),
+                description: "scheduled".into(),
+                expires_at: None,
+                execute_at: Some(service.now() + TimeDelta::seconds(1)),
+                tags: Vec::new(),
+            };
+            service.create(req).await.unwrap();
+        }
+
+        clock.advance(TimeDelta::minutes(1));
+        assert_eq!(advance_due(&service).await.unwrap(), due);
+    }
+}
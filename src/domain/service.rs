@@ -1,51 +1,613 @@
+use crate::domain::clock::{Clock, SystemClock};
+use crate::domain::enrichment::{
+    Enrichment, EnrichmentConfig, EnrichmentFailureMode, EnrichmentProvider,
+};
+use crate::domain::events::{TransactionEvent, TransactionEventKind, EVENT_CHANNEL_CAPACITY};
+use crate::domain::fees::FeePolicy;
+use crate::domain::hooks::{HookPolicy, TransitionGuard, TransitionHook};
 use crate::domain::models::{
-    CreateTransactionRequest, ListFilters, Transaction, TransactionStatus, UpdateStatusRequest,
+    AddNoteRequest, Balance, BalanceFilters, BatchGetRequest, BatchGetResult, ConvertedTotal,
+    CreateFingerprint, CreateTransactionRequest, Currency, CurrencyPolicy, ExchangeRate,
+    ListFilters, Note, RoundingMode, SetExchangeRateRequest, StatusBatchItem, StatusBatchOutcome,
+    StatusBatchRequest, Transaction, TransactionStatus, UpdateAmountRequest, UpdateStatusRequest,
+    UpdateTransactionRequest, VoidRequest,
+};
+use crate::domain::rates::ExchangeRateProvider;
+use crate::domain::validation::{
+    create_request_schema, normalize_tags, validate_amount, validate_create_request,
+    validate_description, validate_idempotency_key, validate_note, ValidationConfig,
 };
-use crate::domain::validation::validate_create_request;
 use crate::error::AppError;
-use crate::storage::Storage;
-use chrono::Utc;
+use crate::storage::{DateRange, ListQuery, ListResult, PageInfo, Storage};
+use chrono::{DateTime, TimeDelta, Utc};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::sync::{Arc, RwLock};
+use tokio::sync::{broadcast, Mutex, OwnedSemaphorePermit, Semaphore};
+use tracing::field::{display, Empty};
+use tracing::{Level, Span};
 use uuid::Uuid;
 
+/// Largest page `list` will return in one call.
+pub const MAX_PAGE_SIZE: usize = 1000;
+/// Page size for offset pagination when only `page` is given.
+pub const DEFAULT_PER_PAGE: usize = 25;
+/// Default cap on a `list` call without a `limit`; see `with_max_list_results`.
+pub const MAX_LIST_RESULTS: usize = 10_000;
+/// Largest `n` accepted by `recent`.
+pub const MAX_RECENT: usize = 100;
+/// Most ids accepted by `update_status_batch`.
+pub const MAX_STATUS_BATCH: usize = 1000;
+/// Most ids accepted by `get_many`.
+pub const MAX_BATCH_GET: usize = 1000;
+/// How long a delete idempotency key replays its outcome when no idempotency TTL is set.
+/// Receipts live in memory, so unlike create keys they cannot be kept forever.
+pub const DEFAULT_DELETE_RECEIPT_TTL: TimeDelta = TimeDelta::hours(24);
+
+enum Prepared {
+    Replay(Transaction),
+    New(Transaction),
+}
+
+/// Caps concurrent creates sharing an idempotency key prefix, i.e. everything before the first
+/// `delimiter`. Keys without the delimiter are their own prefix.
+#[derive(Debug, Clone)]
+pub struct PrefixConcurrencyLimit {
+    pub delimiter: char,
+    pub max_concurrent: usize,
+}
+
+impl PrefixConcurrencyLimit {
+    fn prefix<'a>(&self, key: &'a str) -> &'a str {
+        key.split(self.delimiter).next().unwrap_or(key)
+    }
+}
+
+type PrefixPermits = Arc<std::sync::Mutex<HashMap<String, Arc<Semaphore>>>>;
+
+/// A create's hold on its prefix. Released on drop, so a create whose future is dropped
+/// midway, e.g. by a request timeout, gives its slot back like one that finishes.
+struct PrefixSlot {
+    permits: PrefixPermits,
+    prefix: String,
+    permit: Option<OwnedSemaphorePermit>,
+}
+
+impl Drop for PrefixSlot {
+    fn drop(&mut self) {
+        drop(self.permit.take());
+        let mut permits = self.permits.lock().unwrap_or_else(|e| e.into_inner());
+        // Semaphores are only cloned under this lock, so a count of one means nobody else is
+        // using this prefix right now.
+        if permits.get(&self.prefix).is_some_and(|s| Arc::strong_count(s) == 1) {
+            permits.remove(&self.prefix);
+        }
+    }
+}
+
+/// The first outcome of a delete issued with a delete idempotency key.
+#[derive(Debug, Clone)]
+enum DeleteOutcome {
+    Deleted(Box<Transaction>),
+    NotFound,
+}
+
+/// A delete idempotency key together with the tenant it is scoped to.
+type DeleteScope = (Option<String>, String);
+
+/// Recorded delete outcomes by scoped key, with the target id and when they were recorded.
+#[derive(Debug, Default)]
+struct DeleteReceipts {
+    by_scope: HashMap<DeleteScope, (Uuid, DeleteOutcome, DateTime<Utc>)>,
+    /// Scopes in the order they were recorded, which is also the order they expire in.
+    by_age: VecDeque<(DateTime<Utc>, DeleteScope)>,
+}
+
+impl DeleteReceipts {
+    fn evict_expired(&mut self, ttl: TimeDelta, now: DateTime<Utc>) {
+        while let Some((recorded_at, _)) = self.by_age.front() {
+            if *recorded_at + ttl > now {
+                break;
+            }
+            if let Some((_, scope)) = self.by_age.pop_front() {
+                self.by_scope.remove(&scope);
+            }
+        }
+    }
+
+    fn record(&mut self, scope: DeleteScope, id: Uuid, outcome: DeleteOutcome, now: DateTime<Utc>) {
+        self.by_age.push_back((now, scope.clone()));
+        self.by_scope.insert(scope, (id, outcome, now));
+    }
+}
+
+/// Compliance requires a non-empty reason for `Failed` and `Cancelled`. Other statuses do not
+/// record one, so any reason given for them is dropped.
+fn status_reason(
+    status: TransactionStatus,
+    reason: Option<String>,
+) -> Result<Option<String>, AppError> {
+    match status {
+        TransactionStatus::Failed | TransactionStatus::Cancelled => {
+            match reason.map(|r| r.trim().to_string()).filter(|r| !r.is_empty()) {
+                Some(reason) => Ok(Some(reason)),
+                None => Err(AppError::Validation(format!(
+                    "A reason is required when marking a transaction {}",
+                    status
+                ))),
+            }
+        }
+        _ => Ok(None),
+    }
+}
+
+fn scope_full(tenant: Option<&str>, max: usize) -> AppError {
+    AppError::Validation(format!(
+        "Scope {} already holds the maximum of {} transactions",
+        tenant.unwrap_or("(default)"),
+        max
+    ))
+}
+
 #[derive(Clone)]
 pub struct TransactionService<S: Storage> {
     storage: S,
+    validation: ValidationConfig,
+    /// How long an idempotency key keeps replaying its transaction. `None` means forever.
+    idempotency_ttl: Option<TimeDelta>,
+    currency_policy: CurrencyPolicy,
+    /// Reject create requests without an idempotency key instead of always creating.
+    require_idempotency_key: bool,
+    /// Currency for create requests that omit one. Without it, `currency` is required.
+    default_currency: Option<Currency>,
+    /// Currencies switched off at runtime. Shared across clones so an admin toggle applies to
+    /// every handler immediately.
+    disabled_currencies: Arc<RwLock<HashSet<Currency>>>,
+    /// Recorded delete outcomes keyed by tenant and delete idempotency key.
+    delete_receipts: Arc<Mutex<DeleteReceipts>>,
+    enrichment: Option<(Arc<dyn EnrichmentProvider>, EnrichmentConfig)>,
+    prefix_limit: Option<PrefixConcurrencyLimit>,
+    /// In-flight create permits per key prefix. Entries are removed once idle.
+    prefix_permits: PrefixPermits,
+    events: broadcast::Sender<TransactionEvent>,
+    /// Last assigned event sequence. Held while sending so channel order matches it.
+    event_sequence: Arc<std::sync::Mutex<u64>>,
+    hooks: Vec<Arc<dyn TransitionHook>>,
+    hook_policy: HookPolicy,
+    guards: Vec<Arc<dyn TransitionGuard>>,
+    clock: Arc<dyn Clock>,
+    exchange_rates: Option<Arc<dyn ExchangeRateProvider>>,
+    /// Reporting currency whose stored rate is copied onto each new transaction.
+    rate_snapshot: Option<Currency>,
+    /// Most items an unpaginated `list` returns before truncating.
+    max_list_results: usize,
+    /// Fee withheld from new transactions; none when unset.
+    fee_policy: Option<FeePolicy>,
+    /// Most live transactions a single idempotency scope (tenant) may hold.
+    max_transactions_per_scope: Option<usize>,
+    /// Applied when normalizing amounts to the currency's minor unit.
+    rounding: RoundingMode,
 }
 
 impl<S: Storage> TransactionService<S> {
     pub fn new(storage: S) -> Self {
-        Self { storage }
+        Self {
+            storage,
+            validation: ValidationConfig::default(),
+            idempotency_ttl: None,
+            currency_policy: CurrencyPolicy::allow_all(),
+            default_currency: None,
+            require_idempotency_key: false,
+            disabled_currencies: Arc::default(),
+            delete_receipts: Arc::default(),
+            enrichment: None,
+            prefix_limit: None,
+            prefix_permits: Arc::default(),
+            events: broadcast::channel(EVENT_CHANNEL_CAPACITY).0,
+            event_sequence: Arc::default(),
+            hooks: Vec::new(),
+            hook_policy: HookPolicy::default(),
+            guards: Vec::new(),
+            clock: Arc::new(SystemClock),
+            exchange_rates: None,
+            rate_snapshot: None,
+            max_list_results: MAX_LIST_RESULTS,
+            max_transactions_per_scope: None,
+            fee_policy: None,
+            rounding: RoundingMode::default(),
+        }
+    }
+
+    pub fn with_validation(mut self, validation: ValidationConfig) -> Self {
+        self.validation = validation;
+        self
+    }
+
+    pub fn with_currency_policy(mut self, policy: CurrencyPolicy) -> Self {
+        self.currency_policy = policy;
+        self
+    }
+
+    pub fn with_default_currency(mut self, currency: Currency) -> Self {
+        self.default_currency = Some(currency);
+        self
+    }
+
+    pub fn with_rounding_mode(mut self, mode: RoundingMode) -> Self {
+        self.rounding = mode;
+        self
+    }
+
+    pub fn with_required_idempotency_key(mut self, required: bool) -> Self {
+        self.require_idempotency_key = required;
+        self
+    }
+
+    pub fn with_idempotency_ttl(mut self, ttl: TimeDelta) -> Self {
+        self.idempotency_ttl = Some(ttl);
+        self
+    }
+
+    pub fn with_enrichment(
+        mut self,
+        provider: Arc<dyn EnrichmentProvider>,
+        config: EnrichmentConfig,
+    ) -> Self {
+        self.enrichment = Some((provider, config));
+        self
+    }
+
+    pub fn with_prefix_concurrency_limit(mut self, limit: PrefixConcurrencyLimit) -> Self {
+        self.prefix_limit = Some(limit);
+        self
+    }
+
+    /// Adds a hook run on every successful `update_status`, in registration order.
+    pub fn with_transition_hook(mut self, hook: Arc<dyn TransitionHook>) -> Self {
+        self.hooks.push(hook);
+        self
+    }
+
+    pub fn with_hook_policy(mut self, policy: HookPolicy) -> Self {
+        self.hook_policy = policy;
+        self
+    }
+
+    /// Adds a guard that can veto a status change before it is written; see `TransitionGuard`.
+    pub fn with_transition_guard(mut self, guard: Arc<dyn TransitionGuard>) -> Self {
+        self.guards.push(guard);
+        self
+    }
+
+    /// Replaces the clock used for `created_at`, idempotency TTLs and expiry. Storage stamps
+    /// its own update times, so give it the same clock.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Rates `balance` falls back on for `convert_to` when none is stored for a pair.
+    pub fn with_exchange_rates(mut self, provider: Arc<dyn ExchangeRateProvider>) -> Self {
+        self.exchange_rates = Some(provider);
+        self
+    }
+
+    /// Snapshot the stored rate from each new transaction's currency to `quote` onto the
+    /// transaction, so later rate changes do not alter how it is reported.
+    pub fn with_rate_snapshot(mut self, quote: Currency) -> Self {
+        self.rate_snapshot = Some(quote);
+        self
+    }
+
+    pub fn with_max_list_results(mut self, max: usize) -> Self {
+        self.max_list_results = max.max(1);
+        self
+    }
+
+    /// Computes `fee_amount` and `net_amount` for new transactions. Fees are fixed at creation.
+    pub fn with_fee_policy(mut self, policy: FeePolicy) -> Self {
+        self.fee_policy = Some(policy);
+        self
+    }
+
+    /// Rejects creates once the caller's scope holds `max` live transactions. Replays of
+    /// existing keys are still answered.
+    pub fn with_max_transactions_per_scope(mut self, max: usize) -> Self {
+        self.max_transactions_per_scope = Some(max);
+        self
+    }
+
+    /// Storage reachability, for readiness probes.
+    pub async fn health_check(&self) -> Result<(), AppError> {
+        self.storage.health_check().await
+    }
+
+    pub fn now(&self) -> DateTime<Utc> {
+        self.clock.now()
+    }
+
+    /// How much longer `txn`'s idempotency key replays it, or `None` when keys never expire.
+    pub fn idempotency_remaining(&self, txn: &Transaction) -> Option<TimeDelta> {
+        self.idempotency_ttl
+            .map(|ttl| (txn.created_at + ttl - self.now()).max(TimeDelta::zero()))
+    }
+
+    /// Receives an event for every change to a transaction from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<TransactionEvent> {
+        self.events.subscribe()
+    }
+
+    fn publish(&self, kind: TransactionEventKind, txn: &Transaction) {
+        let mut sequence = self.event_sequence.lock().unwrap_or_else(|e| e.into_inner());
+        *sequence += 1;
+        // Sending only fails when nobody is subscribed.
+        let _ = self.events.send(TransactionEvent {
+            sequence: *sequence,
+            kind,
+            transaction: txn.clone(),
+        });
+    }
+
+    pub fn set_currency_enabled(&self, currency: Currency, enabled: bool) {
+        let mut disabled = self
+            .disabled_currencies
+            .write()
+            .unwrap_or_else(|e| e.into_inner());
+        if enabled {
+            disabled.remove(&currency);
+        } else {
+            disabled.insert(currency);
+        }
+    }
+
+    /// JSON Schema for create requests under this service's current validation rules and
+    /// accepted currencies.
+    pub fn create_request_schema(&self) -> serde_json::Value {
+        let currencies: Vec<Currency> = Currency::ALL
+            .into_iter()
+            .filter(|c| self.currency_policy.allows(*c) && self.is_currency_enabled(*c))
+            .collect();
+        create_request_schema(
+            &self.validation,
+            &currencies,
+            self.default_currency.is_none(),
+            self.require_idempotency_key,
+        )
+    }
+
+    pub fn is_currency_enabled(&self, currency: Currency) -> bool {
+        !self
+            .disabled_currencies
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .contains(&currency)
     }
 
     /// Create a transaction. Returns `(transaction, created)` where `created` is false on
     /// idempotent replay.
+    #[tracing::instrument(
+        skip_all,
+        fields(
+            idempotency_key = req.idempotency_key.as_deref(),
+            transaction_id = Empty,
+            outcome = Empty
+        ),
+        err(level = Level::WARN)
+    )]
     pub async fn create(
         &self,
         req: CreateTransactionRequest,
     ) -> Result<(Transaction, bool), AppError> {
-        validate_create_request(&req)?;
+        // Keyless creates have no prefix to throttle on.
+        let prefix = match (&self.prefix_limit, &req.idempotency_key) {
+            (Some(limit), Some(key)) => Some((limit, limit.prefix(key).to_string())),
+            _ => None,
+        };
+        let result = match prefix {
+            None => self.create_unthrottled(req).await,
+            Some((limit, prefix)) => {
+                let _slot = self.acquire_prefix_slot(prefix, limit.max_concurrent)?;
+                self.create_unthrottled(req).await
+            }
+        };
+        if let Ok((txn, created)) = &result {
+            let span = Span::current();
+            span.record("transaction_id", display(txn.id));
+            span.record("outcome", if *created { "created" } else { "replayed" });
+        }
+        result
+    }
+
+    fn acquire_prefix_slot(
+        &self,
+        prefix: String,
+        max_concurrent: usize,
+    ) -> Result<PrefixSlot, AppError> {
+        let semaphore = self
+            .prefix_permits
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(prefix.clone())
+            .or_insert_with(|| Arc::new(Semaphore::new(max_concurrent)))
+            .clone();
+        let permit = semaphore.try_acquire_owned().ok();
+        let slot = PrefixSlot {
+            permits: self.prefix_permits.clone(),
+            prefix,
+            permit,
+        };
+        if slot.permit.is_none() {
+            // Dropping the slot also forgets the semaphore if its holders finished meanwhile.
+            return Err(AppError::TooManyRequests(format!(
+                "Too many concurrent creates for key prefix {:?}",
+                slot.prefix
+            )));
+        }
+        Ok(slot)
+    }
+
+    async fn create_unthrottled(
+        &self,
+        req: CreateTransactionRequest,
+    ) -> Result<(Transaction, bool), AppError> {
+        let txn = match self.prepare_create(req).await? {
+            Prepared::Replay(existing) => return Ok((existing, false)),
+            Prepared::New(txn) => txn,
+        };
+        let txn = self.enrich(txn).await?;
+
+        match self.max_transactions_per_scope {
+            Some(max) => {
+                if !self.storage.insert_capped(txn.clone(), max).await? {
+                    return Err(scope_full(txn.tenant_id.as_deref(), max));
+                }
+            }
+            None => self.storage.insert(txn.clone()).await?,
+        }
+        self.publish(TransactionEventKind::Created, &txn);
+        Ok((txn, true))
+    }
+
+    /// Runs the checks of `create` without persisting anything. Returns the transaction an
+    /// idempotent replay would return, or the would-be new transaction with a nil id.
+    pub async fn create_dry_run(
+        &self,
+        req: CreateTransactionRequest,
+    ) -> Result<Transaction, AppError> {
+        match self.prepare_create(req).await? {
+            Prepared::Replay(existing) => Ok(existing),
+            Prepared::New(txn) => Ok(Transaction {
+                id: Uuid::nil(),
+                ..txn
+            }),
+        }
+    }
+
+    /// Validation and idempotency lookup shared by `create` and `create_dry_run`.
+    async fn prepare_create(
+        &self,
+        mut req: CreateTransactionRequest,
+    ) -> Result<Prepared, AppError> {
+        let currency = req
+            .currency
+            .or(self.default_currency)
+            .ok_or_else(|| AppError::Validation("Currency is required".into()))?;
+        req.currency = Some(currency);
+        if self.require_idempotency_key && req.idempotency_key.is_none() {
+            return Err(AppError::Validation("Idempotency key is required".into()));
+        }
+        validate_create_request(&req, &self.validation, self.now())?;
+
+        let amount_minor = currency.to_minor(req.amount, self.rounding);
+        let now = self.clock.now();
+
+        // Check idempotency; keys older than the TTL no longer replay. This comes before the
+        // currency and fee checks so a retry gets its transaction back even if those changed.
+        if let Some(key) = &req.idempotency_key {
+            let tenant = req.tenant_id.as_deref();
+            if let Some(existing) = self.storage.find_by_idempotency_key(tenant, key).await? {
+                let expired = self
+                    .idempotency_ttl
+                    .is_some_and(|ttl| existing.created_at + ttl <= now);
+                if !expired {
+                    // A key reused for a different payload is a client bug, not a retry.
+                    if !existing.created_with_same(amount_minor, currency, &req.description) {
+                        return Err(AppError::IdempotencyConflict);
+                    }
+                    return Ok(Prepared::Replay(existing));
+                }
+            }
+        }
+
+        if !self.currency_policy.allows(currency) {
+            return Err(AppError::Validation(format!(
+                "Currency {} is not supported by this deployment",
+                currency
+            )));
+        }
+
+        if !self.is_currency_enabled(currency) {
+            return Err(AppError::Validation(format!(
+                "Currency {} is currently disabled for new transactions",
+                currency
+            )));
+        }
 
-        // Check idempotency
-        if let Some(existing) = self.storage.find_by_idempotency_key(&req.idempotency_key).await? {
-            return Ok((existing, false));
+        if amount_minor <= 0 {
+            return Err(AppError::Validation(format!(
+                "Amount rounds to zero in {}",
+                currency
+            )));
         }
 
-        let now = Utc::now();
-        let txn = Transaction {
+        let fee_minor = self.fee_for(currency, amount_minor)?;
+
+        // Fails fast and covers dry runs; the insert enforces the cap atomically.
+        if let Some(max) = self.max_transactions_per_scope {
+            let tenant = req.tenant_id.as_deref();
+            if self.storage.count_by_scope(tenant).await? >= max {
+                return Err(scope_full(tenant, max));
+            }
+        }
+
+        let exchange_rate = match self.rate_snapshot {
+            Some(quote) if quote != currency => self.storage.get_rate(currency, quote).await?,
+            _ => None,
+        };
+
+        Ok(Prepared::New(Transaction {
             id: Uuid::new_v4(),
             idempotency_key: req.idempotency_key,
-            amount: req.amount,
-            currency: req.currency,
-            description: req.description,
+            tenant_id: req.tenant_id,
+            amount_minor,
+            fee_minor,
+            currency,
+            description: req.description.clone(),
             status: TransactionStatus::Pending,
+            expires_at: req.expires_at,
+            execute_at: req.execute_at,
+            version: 1,
             created_at: now,
             updated_at: now,
+            deleted_at: None,
+            enrichment: None,
+            cancellation_reason: None,
+            failure_reason: None,
+            exchange_rate,
+            tags: normalize_tags(req.tags),
+            created_with: Some(CreateFingerprint {
+                amount_minor,
+                currency,
+                description: req.description,
+            }),
+        }))
+    }
+
+    /// Runs the enrichment provider, if configured, within its timeout.
+    async fn enrich(&self, mut txn: Transaction) -> Result<Transaction, AppError> {
+        let Some((provider, config)) = &self.enrichment else {
+            return Ok(txn);
         };
 
-        self.storage.insert(txn.clone()).await?;
-        Ok((txn, true))
+        let outcome = match tokio::time::timeout(config.timeout, provider.enrich(&txn)).await {
+            Ok(result) => result,
+            Err(_) => Err(AppError::Unavailable(format!(
+                "Enrichment timed out after {:?}",
+                config.timeout
+            ))),
+        };
+
+        txn.enrichment = Some(match (outcome, config.on_failure) {
+            (Ok(enrichment), _) => enrichment,
+            (Err(e), EnrichmentFailureMode::Proceed) => {
+                tracing::warn!(error = %e, "enrichment failed; proceeding with defaults");
+                Enrichment::default()
+            }
+            (Err(e), EnrichmentFailureMode::Reject) => return Err(e),
+        });
+        Ok(txn)
     }
 
+    #[tracing::instrument(skip_all, fields(transaction_id = %id), err(level = Level::WARN))]
     pub async fn get(&self, id: Uuid) -> Result<Transaction, AppError> {
         self.storage
             .get(id)
@@ -53,24 +615,480 @@ impl<S: Storage> TransactionService<S> {
             .ok_or_else(|| AppError::NotFound(id.to_string()))
     }
 
-    pub async fn list(&self, filters: ListFilters) -> Result<Vec<Transaction>, AppError> {
-        self.storage.list(filters.status, filters.currency).await
+    /// Fetches up to `MAX_BATCH_GET` transactions at once. Repeated ids are returned once.
+    pub async fn get_many(&self, req: BatchGetRequest) -> Result<BatchGetResult, AppError> {
+        if req.ids.len() > MAX_BATCH_GET {
+            return Err(AppError::Validation(format!(
+                "A batch get may contain at most {} ids",
+                MAX_BATCH_GET
+            )));
+        }
+        let mut ids = req.ids;
+        let mut seen = HashSet::new();
+        ids.retain(|id| seen.insert(*id));
+
+        let found = self.storage.get_many(&ids).await?;
+        let found_ids: HashSet<Uuid> = found.iter().map(|t| t.id).collect();
+        let missing = ids.into_iter().filter(|id| !found_ids.contains(id)).collect();
+        Ok(BatchGetResult { found, missing })
+    }
+
+    /// Looks up a transaction by idempotency key within `tenant`'s scope.
+    pub async fn get_by_key(
+        &self,
+        tenant: Option<&str>,
+        key: &str,
+    ) -> Result<Transaction, AppError> {
+        validate_idempotency_key(key, &self.validation)?;
+        self.storage
+            .find_by_idempotency_key(tenant, key)
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("idempotency key {}", key)))
+    }
+
+    #[tracing::instrument(
+        skip_all,
+        fields(limit = ?filters.limit, returned = Empty, total = Empty),
+        err(level = Level::WARN)
+    )]
+    pub async fn list(&self, filters: ListFilters) -> Result<ListResult, AppError> {
+        if let Some(limit) = filters.limit {
+            if limit == 0 || limit > MAX_PAGE_SIZE {
+                return Err(AppError::Validation(format!(
+                    "limit must be between 1 and {}",
+                    MAX_PAGE_SIZE
+                )));
+            }
+        }
+        let page = match (filters.page, filters.per_page) {
+            (None, None) => None,
+            (page, per_page) => Some((page.unwrap_or(1), per_page.unwrap_or(DEFAULT_PER_PAGE))),
+        };
+        if let Some((page, per_page)) = page {
+            if filters.cursor.is_some() || filters.limit.is_some() {
+                return Err(AppError::Validation(
+                    "page/per_page cannot be combined with cursor or limit".into(),
+                ));
+            }
+            if page == 0 {
+                return Err(AppError::Validation("page must be at least 1".into()));
+            }
+            if per_page == 0 || per_page > MAX_PAGE_SIZE {
+                return Err(AppError::Validation(format!(
+                    "per_page must be between 1 and {}",
+                    MAX_PAGE_SIZE
+                )));
+            }
+        }
+        let limit = filters.limit.or(page.map(|(_, per_page)| per_page));
+        let query = ListQuery {
+            status: filters.status,
+            currency: filters.currency,
+            q: filters.q.filter(|q| !q.trim().is_empty()),
+            tag: filters.tag.map(|t| t.trim().to_lowercase()),
+            facets: filters.facets,
+            // Unpaginated lists are capped; the cursor lets the client carry on from there.
+            limit: Some(limit.unwrap_or(self.max_list_results)),
+            after: filters.cursor.as_deref().map(str::parse).transpose()?,
+            offset: page.map_or(0, |(page, per_page)| (page - 1).saturating_mul(per_page)),
+        };
+        let mut result = self.storage.list(&query).await?;
+        if let Some((page, per_page)) = page {
+            // Offset pages are addressed by number, not by cursor.
+            result.next_cursor = None;
+            result.page = Some(PageInfo {
+                page,
+                per_page,
+                total_pages: result.total.div_ceil(per_page),
+            });
+        }
+        let span = Span::current();
+        span.record("returned", result.items.len());
+        span.record("total", result.total);
+        Ok(result)
+    }
+
+    /// The `n` newest transactions, newest first. `n` above `MAX_RECENT` is capped.
+    pub async fn recent(&self, n: usize) -> Result<Vec<Transaction>, AppError> {
+        if n == 0 {
+            return Err(AppError::Validation("n must be at least 1".into()));
+        }
+        self.storage.recent(n.min(MAX_RECENT)).await
+    }
+
+    /// Net settled amount per currency for transactions created in the window. Refunds are not
+    /// modelled, so only `Completed` transactions count; failed, cancelled and pending ones
+    /// never moved money. With `convert_to`, also sums the balance in that currency.
+    pub async fn balance(&self, filters: BalanceFilters) -> Result<Balance, AppError> {
+        if let (Some(from), Some(to)) = (filters.from, filters.to) {
+            if from >= to {
+                return Err(AppError::Validation("from must be before to".into()));
+            }
+        }
+        let range = DateRange {
+            from: filters.from,
+            to: filters.to,
+        };
+        let sums: BTreeMap<Currency, f64> = self
+            .storage
+            .sum_by_status(TransactionStatus::Completed, range)
+            .await?
+            .into_iter()
+            .map(|(currency, minor)| (currency, currency.to_major(minor)))
+            .collect();
+        let converted = match filters.convert_to {
+            Some(target) => Some(self.convert(&sums, target).await?),
+            None => None,
+        };
+        Ok(Balance {
+            by_currency: sums,
+            converted,
+        })
+    }
+
+    async fn convert(
+        &self,
+        sums: &BTreeMap<Currency, f64>,
+        target: Currency,
+    ) -> Result<ConvertedTotal, AppError> {
+        let mut total = 0.0;
+        for (&currency, &sum) in sums {
+            let rate = self.exchange_rate(currency, target).await?.ok_or_else(|| {
+                AppError::Validation(format!(
+                    "No exchange rate from {} to {}",
+                    currency, target
+                ))
+            })?;
+            total += sum * rate;
+        }
+        Ok(ConvertedTotal {
+            currency: target,
+            total: target.round(total, self.rounding),
+        })
+    }
+
+    /// Units of `to` per one unit of `from`. Rates stored through `set_rate`, in either
+    /// direction, win over the configured provider, which only fills pairs never stored.
+    async fn exchange_rate(&self, from: Currency, to: Currency) -> Result<Option<f64>, AppError> {
+        if from == to {
+            return Ok(Some(1.0));
+        }
+        if let Some(stored) = self.storage.get_rate(from, to).await? {
+            return Ok(Some(stored.rate));
+        }
+        if let Some(inverse) = self.storage.get_rate(to, from).await? {
+            return Ok(Some(1.0 / inverse.rate));
+        }
+        match &self.exchange_rates {
+            Some(provider) => provider.rate(from, to).await,
+            None => Ok(None),
+        }
     }
 
+    #[tracing::instrument(
+        skip_all,
+        fields(transaction_id = %id, status = %req.status, version = Empty),
+        err(level = Level::WARN)
+    )]
     pub async fn update_status(
         &self,
         id: Uuid,
         req: UpdateStatusRequest,
     ) -> Result<Transaction, AppError> {
-        self.storage.update_status(id, req.status).await
+        let reason = status_reason(req.status, req.reason)?;
+        let txn = self
+            .transition(id, req.status, req.expected_version, reason)
+            .await?;
+        Span::current().record("version", txn.version);
+        Ok(txn)
+    }
+
+    /// Every status change goes through here, so guards, hooks and the hook policy apply to
+    /// voids and background sweeps alike. `reason` is already validated.
+    async fn transition(
+        &self,
+        id: Uuid,
+        to: TransactionStatus,
+        expected_version: Option<u64>,
+        reason: Option<String>,
+    ) -> Result<Transaction, AppError> {
+        if self.hooks.is_empty() && self.guards.is_empty() {
+            let txn = self
+                .storage
+                .update_status(id, to, expected_version, reason)
+                .await?;
+            self.publish(TransactionEventKind::StatusChanged, &txn);
+            return Ok(txn);
+        }
+
+        // Guards and hooks need the prior status; pinning its version makes sure that is what we
+        // replace.
+        let before = self.get(id).await?;
+        let from = before.status;
+        let checks_first = !self.guards.is_empty() || self.hook_policy == HookPolicy::Mandatory;
+        if checks_first && !from.can_transition_to(to) {
+            return Err(AppError::InvalidStateTransition {
+                from: from.to_string(),
+                to: to.to_string(),
+            });
+        }
+        for guard in &self.guards {
+            guard.allow(&before, from, to)?;
+        }
+        if self.hook_policy == HookPolicy::Mandatory {
+            for hook in &self.hooks {
+                hook.on_transition(&before, from, to).await?;
+            }
+        }
+
+        let expected_version = expected_version.or(Some(before.version));
+        let txn = self
+            .storage
+            .update_status(id, to, expected_version, reason)
+            .await?;
+        self.publish(TransactionEventKind::StatusChanged, &txn);
+
+        if self.hook_policy == HookPolicy::BestEffort {
+            for hook in &self.hooks {
+                if let Err(e) = hook.on_transition(&txn, from, to).await {
+                    tracing::warn!(id = %txn.id, %from, %to, error = %e, "transition hook failed");
+                }
+            }
+        }
+        Ok(txn)
+    }
+
+    /// Applies one status to many transactions independently; a failing id does not stop the
+    /// rest. Results come back in request order.
+    pub async fn update_status_batch(
+        &self,
+        req: StatusBatchRequest,
+    ) -> Result<Vec<StatusBatchItem>, AppError> {
+        if req.ids.len() > MAX_STATUS_BATCH {
+            return Err(AppError::Validation(format!(
+                "A status batch may contain at most {} ids",
+                MAX_STATUS_BATCH
+            )));
+        }
+
+        // Checked once up front rather than failing every id the same way.
+        let reason = status_reason(req.status, req.reason)?;
+
+        let mut results = Vec::with_capacity(req.ids.len());
+        for id in req.ids {
+            let update = UpdateStatusRequest {
+                status: req.status,
+                expected_version: None,
+                reason: reason.clone(),
+            };
+            let outcome = match self.update_status(id, update).await {
+                Ok(txn) => StatusBatchOutcome::Updated {
+                    transaction: Box::new(txn),
+                },
+                Err(AppError::NotFound(_)) => StatusBatchOutcome::NotFound,
+                Err(AppError::InvalidStateTransition { from, .. }) => {
+                    StatusBatchOutcome::InvalidTransition { from }
+                }
+                Err(e) => StatusBatchOutcome::Failed {
+                    error: e.to_body()["error"].take(),
+                },
+            };
+            results.push(StatusBatchItem { id, outcome });
+        }
+        Ok(results)
+    }
+
+    /// Cancel a pending transaction, recording why. Like any move to `Cancelled`, it needs a
+    /// reason.
+    pub async fn void(&self, id: Uuid, req: VoidRequest) -> Result<Transaction, AppError> {
+        let reason = status_reason(TransactionStatus::Cancelled, req.reason)?;
+        if reason
+            .as_ref()
+            .is_some_and(|r| r.len() > self.validation.max_description_length)
+        {
+            return Err(AppError::Validation(format!(
+                "Cancellation reason must not exceed {} characters",
+                self.validation.max_description_length
+            )));
+        }
+        self.transition(id, TransactionStatus::Cancelled, None, reason)
+            .await
+    }
+
+    /// Apply a partial update. Only the description is mutable; status has its own endpoint.
+    pub async fn update_description(
+        &self,
+        id: Uuid,
+        req: UpdateTransactionRequest,
+    ) -> Result<Transaction, AppError> {
+        let Some(description) = req.description else {
+            return self.get(id).await;
+        };
+        validate_description(&description, &self.validation)?;
+        let txn = self.storage.update_description(id, description).await?;
+        self.publish(TransactionEventKind::Updated, &txn);
+        Ok(txn)
+    }
+
+    /// Corrects the amount of a pending transaction. The new amount goes through the same
+    /// validation and rounding as on create.
+    pub async fn update_amount(
+        &self,
+        id: Uuid,
+        req: UpdateAmountRequest,
+    ) -> Result<Transaction, AppError> {
+        let txn = self.get(id).await?;
+        if txn.status != TransactionStatus::Pending {
+            return Err(AppError::NotPending {
+                status: txn.status.to_string(),
+            });
+        }
+        validate_amount(req.amount, Some(txn.currency), &self.validation)?;
+        let amount_minor = txn.currency.to_minor(req.amount, self.rounding);
+        if amount_minor <= 0 {
+            return Err(AppError::Validation(format!(
+                "Amount rounds to zero in {}",
+                txn.currency
+            )));
+        }
+        let fee_minor = self.fee_for(txn.currency, amount_minor)?;
+        let txn = self.storage.update_amount(id, amount_minor, fee_minor).await?;
+        self.publish(TransactionEventKind::Updated, &txn);
+        Ok(txn)
+    }
+
+    /// Fee the current policy withholds from `amount_minor`, which must cover it.
+    fn fee_for(&self, currency: Currency, amount_minor: i64) -> Result<Option<i64>, AppError> {
+        let fee_minor = self
+            .fee_policy
+            .as_ref()
+            .and_then(|policy| policy.fee_minor(currency, amount_minor, self.rounding));
+        if let Some(fee) = fee_minor.filter(|fee| *fee > amount_minor) {
+            return Err(AppError::Validation(format!(
+                "Amount {} is less than the {} fee",
+                currency.format(amount_minor),
+                currency.format(fee)
+            )));
+        }
+        Ok(fee_minor)
+    }
+
+    /// Attach a note to a transaction without touching the transaction itself.
+    pub async fn add_note(&self, id: Uuid, req: AddNoteRequest) -> Result<Note, AppError> {
+        validate_note(&req.author, &req.text)?;
+        let note = Note {
+            id: Uuid::new_v4(),
+            transaction_id: id,
+            author: req.author.trim().to_string(),
+            text: req.text,
+            created_at: self.now(),
+        };
+        self.storage.add_note(note).await
+    }
+
+    pub async fn list_notes(&self, id: Uuid) -> Result<Vec<Note>, AppError> {
+        self.storage.list_notes(id).await
+    }
+
+    pub async fn set_rate(
+        &self,
+        base: Currency,
+        quote: Currency,
+        req: SetExchangeRateRequest,
+    ) -> Result<ExchangeRate, AppError> {
+        if base == quote {
+            return Err(AppError::Validation(
+                "Base and quote currencies must differ".into(),
+            ));
+        }
+        if !req.rate.is_finite() || req.rate <= 0.0 {
+            return Err(AppError::Validation(
+                "Rate must be a positive number".into(),
+            ));
+        }
+        let rate = ExchangeRate {
+            base,
+            quote,
+            rate: req.rate,
+            as_of: req.as_of.unwrap_or_else(|| self.now()),
+        };
+        self.storage.upsert_rate(rate).await
+    }
+
+    pub async fn get_rate(
+        &self,
+        base: Currency,
+        quote: Currency,
+    ) -> Result<ExchangeRate, AppError> {
+        self.storage
+            .get_rate(base, quote)
+            .await?
+            .ok_or(AppError::RateNotFound { base, quote })
+    }
+
+    /// Undoes a soft delete.
+    pub async fn restore(&self, id: Uuid) -> Result<Transaction, AppError> {
+        let txn = self.storage.restore(id).await?;
+        self.publish(TransactionEventKind::Updated, &txn);
+        Ok(txn)
+    }
+
+    /// Soft-delete a transaction. With an idempotency key, a repeat call from the same tenant
+    /// returns the outcome of the first call instead of re-evaluating, so a retry can never
+    /// delete something else. Outcomes are kept for the idempotency TTL, or
+    /// `DEFAULT_DELETE_RECEIPT_TTL` without one.
+    pub async fn delete(
+        &self,
+        id: Uuid,
+        tenant: Option<&str>,
+        idempotency_key: Option<&str>,
+    ) -> Result<Transaction, AppError> {
+        let Some(key) = idempotency_key else {
+            let txn = self.storage.soft_delete(id).await?;
+            self.publish(TransactionEventKind::Updated, &txn);
+            return Ok(txn);
+        };
+
+        // Held across the storage call so concurrent retries with the same key serialize.
+        let mut receipts = self.delete_receipts.lock().await;
+        let now = self.now();
+        receipts.evict_expired(self.idempotency_ttl.unwrap_or(DEFAULT_DELETE_RECEIPT_TTL), now);
+        let scope = (tenant.map(str::to_string), key.to_string());
+        if let Some((recorded_id, outcome, _)) = receipts.by_scope.get(&scope) {
+            if *recorded_id != id {
+                return Err(AppError::IdempotencyConflict);
+            }
+            return match outcome {
+                DeleteOutcome::Deleted(txn) => Ok((**txn).clone()),
+                DeleteOutcome::NotFound => Err(AppError::NotFound(id.to_string())),
+            };
+        }
+
+        let result = self.storage.soft_delete(id).await;
+        let outcome = match &result {
+            Ok(txn) => {
+                self.publish(TransactionEventKind::Updated, txn);
+                DeleteOutcome::Deleted(Box::new(txn.clone()))
+            }
+            Err(AppError::NotFound(_)) => DeleteOutcome::NotFound,
+            // Transient failures are not recorded so the client can retry.
+            Err(_) => return result,
+        };
+        receipts.record(scope, id, outcome, now);
+        result
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::domain::models::Currency;
+    use crate::domain::clock::FixedClock;
+    use crate::domain::enrichment::FixedEnrichmentProvider;
+    use crate::domain::models::{Currency, Facet};
+    use crate::domain::rates::StaticRates;
     use crate::storage::memory::InMemoryStorage;
+    use crate::test_support::Capture;
+    use std::time::Duration;
 
     fn make_service() -> TransactionService<InMemoryStorage> {
         TransactionService::new(InMemoryStorage::new())
@@ -78,10 +1096,28 @@ mod tests {
 
     fn create_req(key: &str) -> CreateTransactionRequest {
         CreateTransactionRequest {
-            idempotency_key: key.into(),
+            idempotency_key: Some(key.into()),
+            tenant_id: None,
             amount: 250.0,
-            currency: Currency::Usd,
+            currency: Some(Currency::Usd),
             description: "Wire transfer".into(),
+            expires_at: None,
+            execute_at: None,
+            tags: Vec::new(),
+        }
+    }
+
+    fn status_req(status: TransactionStatus) -> UpdateStatusRequest {
+        UpdateStatusRequest {
+            status,
+            expected_version: None,
+            reason: Some("test".into()),
+        }
+    }
+
+    fn void_req() -> VoidRequest {
+        VoidRequest {
+            reason: Some("test".into()),
         }
     }
 
@@ -113,7 +1149,7 @@ mod tests {
         let (txn, _) = svc.create(create_req("t1")).await.unwrap();
 
         let updated = svc
-            .update_status(txn.id, UpdateStatusRequest { status: TransactionStatus::Completed })
+            .update_status(txn.id, status_req(TransactionStatus::Completed))
             .await
             .unwrap();
         assert_eq!(updated.status, TransactionStatus::Completed);
@@ -124,42 +1160,1287 @@ mod tests {
         let svc = make_service();
         let (txn, _) = svc.create(create_req("t2")).await.unwrap();
 
-        svc.update_status(txn.id, UpdateStatusRequest { status: TransactionStatus::Completed })
+        svc.update_status(txn.id, status_req(TransactionStatus::Completed))
             .await
             .unwrap();
 
         let result = svc
-            .update_status(txn.id, UpdateStatusRequest { status: TransactionStatus::Pending })
+            .update_status(txn.id, status_req(TransactionStatus::Pending))
             .await;
         assert!(result.is_err());
     }
 
+    #[tokio::test]
+    async fn stale_version_conflicts() {
+        let svc = make_service();
+        let (txn, _) = svc.create(create_req("v1")).await.unwrap();
+
+        let updated = svc
+            .update_status(
+                txn.id,
+                UpdateStatusRequest {
+                    expected_version: Some(1),
+                    ..status_req(TransactionStatus::Completed)
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(updated.version, 2);
+
+        let result = svc
+            .update_status(
+                txn.id,
+                UpdateStatusRequest {
+                    expected_version: Some(1),
+                    ..status_req(TransactionStatus::Failed)
+                },
+            )
+            .await;
+        match result {
+            Err(AppError::VersionConflict { current }) => {
+                assert_eq!(current.version, 2);
+                assert_eq!(current.status, TransactionStatus::Completed);
+            }
+            other => panic!("expected version conflict, got {:?}", other),
+        }
+    }
+
     #[tokio::test]
     async fn list_with_filters() {
         let svc = make_service();
         svc.create(create_req("a")).await.unwrap();
         svc.create(create_req("b")).await.unwrap();
 
-        let all = svc.list(ListFilters { status: None, currency: None }).await.unwrap();
-        assert_eq!(all.len(), 2);
+        let all = svc.list(ListFilters::default()).await.unwrap();
+        assert_eq!(all.items.len(), 2);
+        assert!(all.facets.is_none());
 
         let pending = svc
-            .list(ListFilters { status: Some(TransactionStatus::Pending), currency: None })
+            .list(ListFilters {
+                status: vec![TransactionStatus::Pending],
+                ..ListFilters::default()
+            })
             .await
             .unwrap();
-        assert_eq!(pending.len(), 2);
+        assert_eq!(pending.items.len(), 2);
 
         let completed = svc
-            .list(ListFilters { status: Some(TransactionStatus::Completed), currency: None })
+            .list(ListFilters {
+                status: vec![TransactionStatus::Completed],
+                ..ListFilters::default()
+            })
             .await
             .unwrap();
-        assert!(completed.is_empty());
+        assert!(completed.items.is_empty());
     }
 
     #[tokio::test]
-    async fn get_not_found() {
-        let svc = make_service();
-        let result = svc.get(Uuid::new_v4()).await;
-        assert!(matches!(result, Err(AppError::NotFound(_))));
+    async fn timestamps_come_from_the_injected_clock() {
+        let fixed = DateTime::parse_from_rfc3339("2024-03-01T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let clock = Arc::new(FixedClock::new(fixed));
+        let storage = InMemoryStorage::new().with_clock(clock.clone());
+        let svc = TransactionService::new(storage).with_clock(clock.clone());
+
+        let (txn, _) = svc.create(create_req("clock")).await.unwrap();
+        assert_eq!(txn.created_at, fixed);
+        assert_eq!(txn.updated_at, fixed);
+
+        clock.advance(TimeDelta::minutes(5));
+        let updated = svc
+            .update_status(txn.id, status_req(TransactionStatus::Completed))
+            .await
+            .unwrap();
+        assert_eq!(updated.created_at, fixed);
+        assert_eq!(updated.updated_at, fixed + TimeDelta::minutes(5));
+    }
+
+    #[tokio::test]
+    async fn expiry_and_execution_times_are_checked_against_the_injected_clock() {
+        let fixed = DateTime::parse_from_rfc3339("2024-03-01T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let svc = make_service().with_clock(Arc::new(FixedClock::new(fixed)));
+
+        let (txn, _) = svc
+            .create(CreateTransactionRequest {
+                expires_at: Some(fixed + TimeDelta::hours(1)),
+                execute_at: Some(fixed + TimeDelta::minutes(5)),
+                ..create_req("clock-checked")
+            })
+            .await
+            .unwrap();
+        assert_eq!(txn.expires_at, Some(fixed + TimeDelta::hours(1)));
+
+        let past = svc
+            .create(CreateTransactionRequest {
+                expires_at: Some(fixed - TimeDelta::hours(1)),
+                ..create_req("clock-past")
+            })
+            .await;
+        assert!(matches!(past, Err(AppError::Validation(_))));
+    }
+
+    #[tokio::test]
+    async fn expired_idempotency_key_creates_new_transaction() {
+        let clock = Arc::new(FixedClock::new(Utc::now()));
+        let svc = make_service()
+            .with_clock(clock.clone())
+            .with_idempotency_ttl(TimeDelta::seconds(10));
+        let (first, _) = svc.create(create_req("ttl")).await.unwrap();
+
+        clock.advance(TimeDelta::seconds(9));
+        let (replay, created) = svc.create(create_req("ttl")).await.unwrap();
+        assert!(!created);
+        assert_eq!(replay.id, first.id);
+
+        clock.advance(TimeDelta::seconds(1));
+        let (fresh, created) = svc.create(create_req("ttl")).await.unwrap();
+        assert!(created);
+        assert_ne!(fresh.id, first.id);
+
+        let (replay, created) = svc.create(create_req("ttl")).await.unwrap();
+        assert!(!created);
+        assert_eq!(replay.id, fresh.id);
+    }
+
+    #[tokio::test]
+    async fn delete_hides_transaction() {
+        let svc = make_service();
+        let (txn, _) = svc.create(create_req("del")).await.unwrap();
+
+        svc.delete(txn.id, None, None).await.unwrap();
+        assert!(matches!(svc.get(txn.id).await, Err(AppError::NotFound(_))));
+        assert!(svc.list(ListFilters::default()).await.unwrap().items.is_empty());
+        assert!(matches!(svc.delete(txn.id, None, None).await, Err(AppError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn repeated_delete_key_replays_recorded_outcome() {
+        let svc = make_service();
+        let (txn, _) = svc.create(create_req("del-key")).await.unwrap();
+
+        let first = svc.delete(txn.id, None, Some("d-1")).await.unwrap();
+        let second = svc.delete(txn.id, None, Some("d-1")).await.unwrap();
+        assert_eq!(first.deleted_at, second.deleted_at);
+        assert_eq!(first.version, second.version);
+
+        let missing = Uuid::new_v4();
+        let delete_missing = || svc.delete(missing, None, Some("d-2"));
+        assert!(matches!(delete_missing().await, Err(AppError::NotFound(_))));
+        assert!(matches!(delete_missing().await, Err(AppError::NotFound(_))));
+        assert!(matches!(
+            svc.delete(txn.id, None, Some("d-2")).await,
+            Err(AppError::IdempotencyConflict)
+        ));
+    }
+
+    #[tokio::test]
+    async fn delete_keys_are_scoped_by_tenant_and_expire() {
+        let clock = Arc::new(FixedClock::new(Utc::now()));
+        let svc = make_service()
+            .with_clock(clock.clone())
+            .with_idempotency_ttl(TimeDelta::seconds(10));
+        let (a, _) = svc.create(create_req("del-a")).await.unwrap();
+        let (b, _) = svc.create(create_req("del-b")).await.unwrap();
+
+        svc.delete(a.id, Some("acme"), Some("shared")).await.unwrap();
+        // Another tenant's key of the same name is its own.
+        svc.delete(b.id, Some("globex"), Some("shared")).await.unwrap();
+        let conflict = svc.delete(b.id, Some("acme"), Some("shared")).await;
+        assert!(matches!(conflict, Err(AppError::IdempotencyConflict)));
+
+        clock.advance(TimeDelta::seconds(10));
+        // The receipt is gone, so the call is evaluated afresh.
+        let expired = svc.delete(b.id, Some("acme"), Some("shared")).await;
+        assert!(matches!(expired, Err(AppError::NotFound(_))));
+        assert_eq!(svc.delete_receipts.lock().await.by_age.len(), 1);
+    }
+
+    fn slow_enrichment(mode: EnrichmentFailureMode) -> TransactionService<InMemoryStorage> {
+        let provider = FixedEnrichmentProvider {
+            enrichment: Enrichment {
+                risk_score: Some(0.9),
+                geolocation: None,
+            },
+            delay: Duration::from_millis(200),
+        };
+        let config = EnrichmentConfig {
+            timeout: Duration::from_millis(10),
+            on_failure: mode,
+        };
+        make_service().with_enrichment(Arc::new(provider), config)
+    }
+
+    #[tokio::test]
+    async fn enrichment_is_applied() {
+        let provider = FixedEnrichmentProvider {
+            enrichment: Enrichment {
+                risk_score: Some(0.2),
+                geolocation: Some("GB".into()),
+            },
+            delay: Duration::ZERO,
+        };
+        let svc = make_service().with_enrichment(Arc::new(provider), EnrichmentConfig::default());
+
+        let (txn, _) = svc.create(create_req("enrich")).await.unwrap();
+        let enrichment = svc.get(txn.id).await.unwrap().enrichment.unwrap();
+        assert_eq!(enrichment.risk_score, Some(0.2));
+        assert_eq!(enrichment.geolocation.as_deref(), Some("GB"));
+    }
+
+    #[tokio::test]
+    async fn enrichment_timeout_rejects_in_reject_mode() {
+        let svc = slow_enrichment(EnrichmentFailureMode::Reject);
+        let result = svc.create(create_req("slow")).await;
+        assert!(matches!(result, Err(AppError::Unavailable(_))));
+        assert!(svc.get_by_key(None, "slow").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn enrichment_timeout_proceeds_with_defaults() {
+        let svc = slow_enrichment(EnrichmentFailureMode::Proceed);
+        let (txn, created) = svc.create(create_req("slow")).await.unwrap();
+        assert!(created);
+        assert_eq!(txn.enrichment, Some(Enrichment::default()));
+    }
+
+    #[tokio::test]
+    async fn update_description_keeps_status() {
+        let svc = make_service();
+        let (txn, _) = svc.create(create_req("desc")).await.unwrap();
+
+        let updated = svc
+            .update_description(
+                txn.id,
+                UpdateTransactionRequest {
+                    description: Some("Wire transfer (corrected)".into()),
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(updated.description, "Wire transfer (corrected)");
+        assert_eq!(updated.status, TransactionStatus::Pending);
+        assert!(updated.updated_at >= txn.updated_at);
+
+        let blank = UpdateTransactionRequest {
+            description: Some("  ".into()),
+        };
+        assert!(matches!(
+            svc.update_description(txn.id, blank).await,
+            Err(AppError::Validation(_))
+        ));
+
+        let missing = UpdateTransactionRequest {
+            description: Some("x".into()),
+        };
+        assert!(matches!(
+            svc.update_description(Uuid::new_v4(), missing).await,
+            Err(AppError::NotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn prefix_limit_throttles_concurrent_creates() {
+        let provider = FixedEnrichmentProvider {
+            enrichment: Enrichment::default(),
+            delay: Duration::from_millis(100),
+        };
+        let config = EnrichmentConfig {
+            timeout: Duration::from_secs(5),
+            on_failure: EnrichmentFailureMode::Reject,
+        };
+        let svc = make_service()
+            .with_enrichment(Arc::new(provider), config)
+            .with_prefix_concurrency_limit(PrefixConcurrencyLimit {
+                delimiter: ':',
+                max_concurrent: 2,
+            });
+
+        let batch = (0..5).map(|i| svc.create(create_req(&format!("batch-a:{}", i))));
+        let other = svc.create(create_req("batch-b:0"));
+        let (results, other) =
+            tokio::join!(futures_util::future::join_all(batch), other);
+
+        let throttled = results
+            .iter()
+            .filter(|r| matches!(r, Err(AppError::TooManyRequests(_))))
+            .count();
+        assert_eq!(results.iter().filter(|r| r.is_ok()).count(), 2);
+        assert_eq!(throttled, 3);
+        assert!(other.is_ok());
+
+        // Permits are released once the burst completes.
+        assert!(svc.create(create_req("batch-a:5")).await.is_ok());
+        assert!(svc.prefix_permits.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn cancelled_create_releases_its_prefix_slot() {
+        let provider = FixedEnrichmentProvider {
+            enrichment: Enrichment::default(),
+            delay: Duration::from_secs(5),
+        };
+        let config = EnrichmentConfig {
+            timeout: Duration::from_secs(10),
+            on_failure: EnrichmentFailureMode::Reject,
+        };
+        let svc = make_service()
+            .with_enrichment(Arc::new(provider), config)
+            .with_prefix_concurrency_limit(PrefixConcurrencyLimit {
+                delimiter: ':',
+                max_concurrent: 1,
+            });
+
+        let abandoned = svc.create(create_req("cancel:1"));
+        assert!(tokio::time::timeout(Duration::from_millis(20), abandoned).await.is_err());
+        assert!(svc.prefix_permits.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn dry_run_does_not_persist() {
+        let svc = make_service();
+        let txn = svc.create_dry_run(create_req("dry")).await.unwrap();
+        assert!(txn.id.is_nil());
+        assert!(svc.get_by_key(None, "dry").await.is_err());
+
+        let (created, _) = svc.create(create_req("dry")).await.unwrap();
+        let replay = svc.create_dry_run(create_req("dry")).await.unwrap();
+        assert_eq!(replay.id, created.id);
+    }
+
+    #[tokio::test]
+    async fn currency_policy_rejects_disallowed() {
+        let svc = make_service().with_currency_policy(CurrencyPolicy::only([Currency::Usd]));
+        let gbp = CreateTransactionRequest {
+            currency: Some(Currency::Gbp),
+            ..create_req("policy-gbp")
+        };
+        let err = svc.create(gbp).await.unwrap_err();
+        assert!(matches!(err, AppError::Validation(ref m) if m.contains("GBP")));
+        assert!(svc.create(create_req("policy-usd")).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn omitted_currency_uses_configured_default() {
+        let no_currency = || CreateTransactionRequest {
+            currency: None,
+            ..create_req("default-ccy")
+        };
+        let err = make_service().create(no_currency()).await.unwrap_err();
+        assert!(matches!(err, AppError::Validation(ref m) if m.contains("Currency is required")));
+
+        let svc = make_service().with_default_currency(Currency::Usd);
+        let (txn, _) = svc.create(no_currency()).await.unwrap();
+        assert_eq!(txn.currency, Currency::Usd);
+    }
+
+    #[tokio::test]
+    async fn amounts_are_rounded_to_currency_precision() {
+        let svc = make_service();
+        let usd = CreateTransactionRequest {
+            amount: 150.7500001,
+            ..create_req("round-usd")
+        };
+        assert_eq!(svc.create(usd).await.unwrap().0.amount_minor, 15075);
+
+        let jpy = CreateTransactionRequest {
+            amount: 1234.5,
+            currency: Some(Currency::Jpy),
+            ..create_req("round-jpy")
+        };
+        assert_eq!(svc.create(jpy).await.unwrap().0.amount_minor, 1235);
+
+        let dust = CreateTransactionRequest {
+            amount: 0.001,
+            ..create_req("round-dust")
+        };
+        assert!(matches!(svc.create(dust).await, Err(AppError::Validation(_))));
+    }
+
+    #[tokio::test]
+    async fn configured_rounding_mode_applies_on_create() {
+        let tie = |key: &str| CreateTransactionRequest {
+            amount: 0.125,
+            ..create_req(key)
+        };
+        let half_up = make_service();
+        assert_eq!(half_up.create(tie("tie-up")).await.unwrap().0.amount_minor, 13);
+
+        let half_even = make_service().with_rounding_mode(RoundingMode::HalfEven);
+        assert_eq!(half_even.create(tie("tie-even")).await.unwrap().0.amount_minor, 12);
+    }
+
+    #[tokio::test]
+    async fn reused_key_with_different_payload_conflicts() {
+        let svc = make_service();
+        let (txn, _) = svc.create(create_req("mismatch")).await.unwrap();
+
+        let changed = CreateTransactionRequest {
+            amount: txn.amount() + 1.0,
+            ..create_req("mismatch")
+        };
+        assert!(matches!(
+            svc.create(changed).await,
+            Err(AppError::IdempotencyConflict)
+        ));
+
+        let (replayed, created) = svc.create(create_req("mismatch")).await.unwrap();
+        assert!(!created);
+        assert_eq!(replayed.id, txn.id);
+    }
+
+    #[tokio::test]
+    async fn keyless_creates_are_never_replayed() {
+        let svc = make_service();
+        let keyless = || CreateTransactionRequest {
+            idempotency_key: None,
+            ..create_req("unused")
+        };
+        let (first, created_first) = svc.create(keyless()).await.unwrap();
+        let (second, created_second) = svc.create(keyless()).await.unwrap();
+        assert!(created_first && created_second);
+        assert_ne!(first.id, second.id);
+        assert_eq!(first.idempotency_key, None);
+
+        let (keyed, _) = svc.create(create_req("keyed")).await.unwrap();
+        let (replay, created) = svc.create(create_req("keyed")).await.unwrap();
+        assert!(!created);
+        assert_eq!(replay.id, keyed.id);
+    }
+
+    #[tokio::test]
+    async fn required_idempotency_key_rejects_keyless_creates() {
+        let svc = make_service().with_required_idempotency_key(true);
+        let keyless = CreateTransactionRequest {
+            idempotency_key: None,
+            ..create_req("unused")
+        };
+        let err = svc.create(keyless).await.unwrap_err();
+        assert!(matches!(err, AppError::Validation(ref m) if m.contains("Idempotency key")));
+        assert!(svc.create(create_req("required")).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn balance_nets_completed_amounts_per_currency() {
+        let start = Utc::now();
+        let clock = Arc::new(FixedClock::new(start));
+        let svc = make_service().with_clock(clock.clone());
+        let settle = |key: &'static str, currency: Currency, amount: f64, status| {
+            let svc = svc.clone();
+            async move {
+                let req = CreateTransactionRequest {
+                    amount,
+                    currency: Some(currency),
+                    ..create_req(key)
+                };
+                let (txn, _) = svc.create(req).await.unwrap();
+                svc.update_status(txn.id, status_req(status)).await.unwrap();
+            }
+        };
+        settle("b1", Currency::Usd, 100.10, TransactionStatus::Completed).await;
+        settle("b2", Currency::Usd, 0.20, TransactionStatus::Completed).await;
+        settle("b3", Currency::Usd, 40.0, TransactionStatus::Failed).await;
+        settle("b4", Currency::Eur, 10.0, TransactionStatus::Completed).await;
+        svc.create(create_req("b5")).await.unwrap();
+        clock.advance(TimeDelta::hours(1));
+        settle("b6", Currency::Usd, 5.0, TransactionStatus::Completed).await;
+
+        let all = svc.balance(BalanceFilters::default()).await.unwrap();
+        assert_eq!(
+            all.by_currency,
+            BTreeMap::from([(Currency::Usd, 105.3), (Currency::Eur, 10.0)])
+        );
+        assert_eq!(all.converted, None);
+
+        let first_hour = BalanceFilters {
+            from: Some(start),
+            to: Some(start + TimeDelta::hours(1)),
+            ..BalanceFilters::default()
+        };
+        let windowed = svc.balance(first_hour).await.unwrap();
+        assert_eq!(windowed.by_currency[&Currency::Usd], 100.3);
+
+        let inverted = BalanceFilters {
+            from: Some(start),
+            to: Some(start),
+            ..BalanceFilters::default()
+        };
+        assert!(matches!(svc.balance(inverted).await, Err(AppError::Validation(_))));
+    }
+
+    #[tokio::test]
+    async fn idempotency_keys_are_scoped_per_tenant() {
+        let svc = make_service();
+        let for_tenant = |tenant: &str| CreateTransactionRequest {
+            tenant_id: Some(tenant.into()),
+            ..create_req("shared-key")
+        };
+        let (a, created_a) = svc.create(for_tenant("acme")).await.unwrap();
+        let (b, created_b) = svc.create(for_tenant("globex")).await.unwrap();
+        assert!(created_a && created_b);
+        assert_ne!(a.id, b.id);
+
+        let (replay, created) = svc.create(for_tenant("acme")).await.unwrap();
+        assert!(!created);
+        assert_eq!(replay.id, a.id);
+
+        assert_eq!(svc.get_by_key(Some("globex"), "shared-key").await.unwrap().id, b.id);
+        assert!(matches!(
+            svc.get_by_key(None, "shared-key").await,
+            Err(AppError::NotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn balance_converts_into_reporting_currency() {
+        let rates = StaticRates::new().with_rate(Currency::Eur, Currency::Usd, 1.1);
+        let svc = make_service().with_exchange_rates(Arc::new(rates));
+        for (key, currency, amount) in [("c1", Currency::Usd, 50.0), ("c2", Currency::Eur, 100.0)] {
+            let req = CreateTransactionRequest {
+                amount,
+                currency: Some(currency),
+                ..create_req(key)
+            };
+            let (txn, _) = svc.create(req).await.unwrap();
+            svc.update_status(txn.id, status_req(TransactionStatus::Completed))
+                .await
+                .unwrap();
+        }
+
+        let to_usd = BalanceFilters {
+            convert_to: Some(Currency::Usd),
+            ..BalanceFilters::default()
+        };
+        let balance = svc.balance(to_usd).await.unwrap();
+        assert_eq!(balance.by_currency[&Currency::Eur], 100.0);
+        assert_eq!(
+            balance.converted,
+            Some(ConvertedTotal {
+                currency: Currency::Usd,
+                total: 160.0
+            })
+        );
+
+        let to_gbp = BalanceFilters {
+            convert_to: Some(Currency::Gbp),
+            ..BalanceFilters::default()
+        };
+        let err = svc.balance(to_gbp).await.unwrap_err();
+        assert!(matches!(err, AppError::Validation(ref m) if m.contains("No exchange rate")));
+    }
+
+    #[tokio::test]
+    async fn balance_prefers_stored_rates_over_the_static_table() {
+        async fn completed_eur_balance_in(
+            svc: &TransactionService<InMemoryStorage>,
+            target: Currency,
+        ) -> f64 {
+            let filters = BalanceFilters {
+                convert_to: Some(target),
+                ..BalanceFilters::default()
+            };
+            svc.balance(filters).await.unwrap().converted.unwrap().total
+        }
+        let rate = |rate| SetExchangeRateRequest { rate, as_of: None };
+        let rates = StaticRates::new().with_rate(Currency::Eur, Currency::Usd, 1.1);
+        let svc = make_service().with_exchange_rates(Arc::new(rates));
+        let req = CreateTransactionRequest {
+            amount: 100.0,
+            currency: Some(Currency::Eur),
+            ..create_req("stored-rate")
+        };
+        let (txn, _) = svc.create(req).await.unwrap();
+        svc.update_status(txn.id, status_req(TransactionStatus::Completed))
+            .await
+            .unwrap();
+
+        assert_eq!(completed_eur_balance_in(&svc, Currency::Usd).await, 110.0);
+        svc.set_rate(Currency::Eur, Currency::Usd, rate(1.2)).await.unwrap();
+        assert_eq!(completed_eur_balance_in(&svc, Currency::Usd).await, 120.0);
+        // A stored pair answers its inverse too.
+        svc.set_rate(Currency::Gbp, Currency::Eur, rate(1.25)).await.unwrap();
+        assert_eq!(completed_eur_balance_in(&svc, Currency::Gbp).await, 80.0);
+    }
+
+    #[tokio::test]
+    async fn unpaginated_list_is_truncated_at_cap() {
+        let svc = make_service().with_max_list_results(3);
+        for i in 0..5 {
+            svc.create(create_req(&format!("cap-{}", i))).await.unwrap();
+        }
+
+        let capped = svc.list(ListFilters::default()).await.unwrap();
+        assert_eq!(capped.items.len(), 3);
+        assert_eq!(capped.total, 5);
+        assert!(capped.next_cursor.is_some());
+
+        let rest = svc
+            .list(ListFilters {
+                cursor: capped.next_cursor.map(|c| c.to_string()),
+                ..ListFilters::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(rest.items.len(), 2);
+        assert!(rest.next_cursor.is_none());
+
+        let paged = svc
+            .list(ListFilters {
+                limit: Some(4),
+                ..ListFilters::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(paged.items.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn recent_returns_newest_first_and_caps_n() {
+        let clock = Arc::new(FixedClock::new(Utc::now()));
+        let storage = InMemoryStorage::new().with_clock(clock.clone());
+        let svc = TransactionService::new(storage).with_clock(clock.clone());
+        let mut ids = Vec::new();
+        for i in 0..MAX_RECENT + 5 {
+            clock.advance(TimeDelta::seconds(1));
+            let (txn, _) = svc.create(create_req(&format!("recent-{}", i))).await.unwrap();
+            ids.push(txn.id);
+        }
+        svc.delete(*ids.last().unwrap(), None, None).await.unwrap();
+
+        let recent = svc.recent(3).await.unwrap();
+        let recent_ids: Vec<_> = recent.iter().map(|t| t.id).collect();
+        let expected: Vec<_> = ids.iter().rev().skip(1).take(3).copied().collect();
+        assert_eq!(recent_ids, expected);
+
+        assert_eq!(svc.recent(1000).await.unwrap().len(), MAX_RECENT);
+        assert!(matches!(svc.recent(0).await, Err(AppError::Validation(_))));
+    }
+
+    #[tokio::test]
+    async fn update_amount_only_applies_to_pending() {
+        let svc = make_service();
+        let (txn, _) = svc.create(create_req("adjust-1")).await.unwrap();
+
+        let adjusted = svc
+            .update_amount(txn.id, UpdateAmountRequest { amount: 42.345 })
+            .await
+            .unwrap();
+        assert_eq!(adjusted.amount_minor, 4235);
+        assert_eq!(adjusted.version, txn.version + 1);
+        assert!(adjusted.updated_at >= txn.updated_at);
+
+        let invalid = svc
+            .update_amount(txn.id, UpdateAmountRequest { amount: -1.0 })
+            .await;
+        assert!(matches!(invalid, Err(AppError::Validation(_))));
+
+        svc.update_status(txn.id, status_req(TransactionStatus::Completed))
+            .await
+            .unwrap();
+        let result = svc
+            .update_amount(txn.id, UpdateAmountRequest { amount: 50.0 })
+            .await;
+        assert!(matches!(result, Err(AppError::NotPending { .. })));
+    }
+
+    #[tokio::test]
+    async fn failing_requires_a_reason() {
+        let svc = make_service();
+        let (txn, _) = svc.create(create_req("fail-reason")).await.unwrap();
+
+        for reason in [None, Some("  ".to_string())] {
+            let req = UpdateStatusRequest {
+                reason,
+                ..status_req(TransactionStatus::Failed)
+            };
+            let err = svc.update_status(txn.id, req).await.unwrap_err();
+            assert!(matches!(err, AppError::Validation(ref m) if m.contains("reason is required")));
+        }
+        assert_eq!(svc.get(txn.id).await.unwrap().status, TransactionStatus::Pending);
+
+        let req = UpdateStatusRequest {
+            reason: Some("card declined".into()),
+            ..status_req(TransactionStatus::Failed)
+        };
+        let failed = svc.update_status(txn.id, req).await.unwrap();
+        assert_eq!(failed.status, TransactionStatus::Failed);
+        assert_eq!(failed.failure_reason.as_deref(), Some("card declined"));
+
+        let (other, _) = svc.create(create_req("complete-no-reason")).await.unwrap();
+        let req = UpdateStatusRequest {
+            reason: None,
+            ..status_req(TransactionStatus::Completed)
+        };
+        assert!(svc.update_status(other.id, req).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn stored_rate_is_snapshotted_on_create() {
+        let svc = make_service().with_rate_snapshot(Currency::Eur);
+        let set = |rate| SetExchangeRateRequest { rate, as_of: None };
+        svc.set_rate(Currency::Usd, Currency::Eur, set(0.92)).await.unwrap();
+
+        let (txn, _) = svc.create(create_req("fx-1")).await.unwrap();
+        assert_eq!(txn.exchange_rate.as_ref().map(|r| r.rate), Some(0.92));
+
+        svc.set_rate(Currency::Usd, Currency::Eur, set(0.95)).await.unwrap();
+        assert_eq!(svc.get(txn.id).await.unwrap().exchange_rate.unwrap().rate, 0.92);
+        assert_eq!(svc.get_rate(Currency::Usd, Currency::Eur).await.unwrap().rate, 0.95);
+
+        let eur = CreateTransactionRequest {
+            currency: Some(Currency::Eur),
+            ..create_req("fx-2")
+        };
+        assert!(svc.create(eur).await.unwrap().0.exchange_rate.is_none());
+        assert!(matches!(
+            svc.set_rate(Currency::Usd, Currency::Usd, set(1.0)).await,
+            Err(AppError::Validation(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn create_beyond_scope_cap_is_rejected() {
+        let svc = make_service().with_max_transactions_per_scope(2);
+        svc.create(create_req("cap-1")).await.unwrap();
+        svc.create(create_req("cap-2")).await.unwrap();
+
+        let err = svc.create(create_req("cap-3")).await.unwrap_err();
+        assert!(matches!(err, AppError::Validation(ref m) if m.contains("maximum of 2")));
+
+        let (_, created) = svc.create(create_req("cap-1")).await.unwrap();
+        assert!(!created);
+        let other_tenant = CreateTransactionRequest {
+            tenant_id: Some("other".into()),
+            ..create_req("cap-3")
+        };
+        assert!(svc.create(other_tenant).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn concurrent_creates_do_not_overshoot_the_scope_cap() {
+        let svc = make_service().with_max_transactions_per_scope(3);
+        let creates = (0..20).map(|i| {
+            let svc = svc.clone();
+            tokio::spawn(async move { svc.create(create_req(&format!("race-{}", i))).await })
+        });
+        let mut created = 0;
+        for create in creates.collect::<Vec<_>>() {
+            if create.await.unwrap().is_ok() {
+                created += 1;
+            }
+        }
+        assert_eq!(created, 3);
+        assert_eq!(svc.storage.count_by_scope(None).await.unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn fee_policy_splits_amount_into_fee_and_net() {
+        let policy = FeePolicy::new().with_rule(Currency::Usd, 0.30, 2.9);
+        let svc = make_service().with_fee_policy(policy);
+
+        let (txn, _) = svc
+            .create(CreateTransactionRequest {
+                amount: 100.0,
+                ..create_req("fee-1")
+            })
+            .await
+            .unwrap();
+        assert_eq!(txn.fee_minor, Some(320));
+        assert_eq!(txn.net_minor(), Some(9680));
+        let body = serde_json::to_value(&txn).unwrap();
+        assert_eq!(body["fee_amount"], 3.2);
+        assert_eq!(body["net_amount"], 96.8);
+
+        let err = svc
+            .create(CreateTransactionRequest {
+                amount: 0.25,
+                ..create_req("fee-2")
+            })
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AppError::Validation(ref m) if m.contains("fee")));
+
+        let untouched = CreateTransactionRequest {
+            currency: Some(Currency::Eur),
+            ..create_req("fee-3")
+        };
+        assert_eq!(svc.create(untouched).await.unwrap().0.fee_minor, None);
+    }
+
+    /// Vetoes moving anything above `limit_minor` to `guarded` with a 403.
+    struct ApprovalGuard {
+        guarded: TransactionStatus,
+        limit_minor: i64,
+    }
+
+    impl TransitionGuard for ApprovalGuard {
+        fn allow(
+            &self,
+            txn: &Transaction,
+            _from: TransactionStatus,
+            to: TransactionStatus,
+        ) -> Result<(), AppError> {
+            if to == self.guarded && txn.amount_minor > self.limit_minor {
+                return Err(AppError::Forbidden(format!("{} requires approval", to)));
+            }
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn guard_vetoes_completing_large_amount() {
+        let (hook, seen) = recording_hook(false);
+        let svc = make_service()
+            .with_transition_guard(Arc::new(ApprovalGuard {
+                guarded: TransactionStatus::Completed,
+                limit_minor: 10_000,
+            }))
+            .with_transition_hook(hook);
+        let (large, _) = svc.create(create_req("guard-large")).await.unwrap();
+        let small_req = CreateTransactionRequest {
+            amount: 50.0,
+            ..create_req("guard-small")
+        };
+        let (small, _) = svc.create(small_req).await.unwrap();
+
+        let vetoed = svc
+            .update_status(large.id, status_req(TransactionStatus::Completed))
+            .await;
+        assert!(matches!(vetoed, Err(AppError::Forbidden(_))));
+        assert_eq!(svc.get(large.id).await.unwrap().status, TransactionStatus::Pending);
+        assert!(seen.lock().unwrap().is_empty());
+
+        let failed = svc
+            .update_status(large.id, status_req(TransactionStatus::Failed))
+            .await
+            .unwrap();
+        assert_eq!(failed.status, TransactionStatus::Failed);
+        let completed = svc
+            .update_status(small.id, status_req(TransactionStatus::Completed))
+            .await
+            .unwrap();
+        assert_eq!(completed.status, TransactionStatus::Completed);
+    }
+
+    #[test]
+    fn create_schema_lists_only_accepted_currencies() {
+        let svc = make_service()
+            .with_currency_policy(CurrencyPolicy::only([Currency::Usd, Currency::Eur]))
+            .with_default_currency(Currency::Usd);
+        svc.set_currency_enabled(Currency::Eur, false);
+
+        let schema = svc.create_request_schema();
+        assert_eq!(schema["properties"]["currency"]["pattern"], "^(?:[Uu][Ss][Dd])$");
+        assert_eq!(schema["required"], serde_json::json!(["amount", "description"]));
+        assert_eq!(schema["properties"]["description"]["maxLength"], 500);
+
+        let keyed = make_service().with_required_idempotency_key(true);
+        let schema = keyed.create_request_schema();
+        assert_eq!(
+            schema["required"],
+            serde_json::json!(["amount", "description", "currency", "idempotency_key"])
+        );
+    }
+
+    #[tokio::test]
+    async fn guards_and_hooks_apply_to_void() {
+        let (hook, seen) = recording_hook(false);
+        let svc = make_service()
+            .with_transition_guard(Arc::new(ApprovalGuard {
+                guarded: TransactionStatus::Cancelled,
+                limit_minor: 10_000,
+            }))
+            .with_transition_hook(hook);
+        let (large, _) = svc.create(create_req("void-guard")).await.unwrap();
+
+        let vetoed = svc.void(large.id, void_req()).await;
+        assert!(matches!(vetoed, Err(AppError::Forbidden(_))));
+        assert_eq!(svc.get(large.id).await.unwrap().status, TransactionStatus::Pending);
+        assert!(seen.lock().unwrap().is_empty());
+
+        let small_req = CreateTransactionRequest {
+            amount: 50.0,
+            ..create_req("void-small")
+        };
+        let (small, _) = svc.create(small_req).await.unwrap();
+        svc.void(small.id, void_req()).await.unwrap();
+        assert_eq!(
+            *seen.lock().unwrap(),
+            [(TransactionStatus::Pending, TransactionStatus::Cancelled)]
+        );
+    }
+
+    #[tokio::test]
+    async fn amount_update_recomputes_the_fee() {
+        let policy = FeePolicy::new().with_rule(Currency::Usd, 0.30, 2.9);
+        let svc = make_service().with_fee_policy(policy);
+        let (txn, _) = svc
+            .create(CreateTransactionRequest {
+                amount: 100.0,
+                ..create_req("fee-update")
+            })
+            .await
+            .unwrap();
+
+        let updated = svc
+            .update_amount(txn.id, UpdateAmountRequest { amount: 10.0 })
+            .await
+            .unwrap();
+        assert_eq!(updated.fee_minor, Some(59));
+        assert_eq!(updated.net_minor(), Some(941));
+
+        let below_fee = svc
+            .update_amount(txn.id, UpdateAmountRequest { amount: 0.25 })
+            .await;
+        assert!(matches!(below_fee, Err(AppError::Validation(ref m)) if m.contains("fee")));
+        assert_eq!(svc.get(txn.id).await.unwrap().amount_minor, 1000);
+    }
+
+    #[tokio::test]
+    async fn replay_survives_currency_and_fee_changes() {
+        let svc = make_service();
+        let (txn, _) = svc.create(create_req("policy-replay")).await.unwrap();
+
+        svc.set_currency_enabled(Currency::Usd, false);
+        let (replay, created) = svc.create(create_req("policy-replay")).await.unwrap();
+        assert!(!created);
+        assert_eq!(replay.id, txn.id);
+
+        let svc = svc.with_fee_policy(FeePolicy::new().with_rule(Currency::Usd, 500.0, 0.0));
+        let (replay, created) = svc.create(create_req("policy-replay")).await.unwrap();
+        assert!(!created);
+        assert_eq!(replay.id, txn.id);
+        assert!(svc.create(create_req("policy-new")).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn void_pending_records_reason() {
+        let svc = make_service();
+        let (txn, _) = svc.create(create_req("void")).await.unwrap();
+        let req = VoidRequest {
+            reason: Some("duplicate order".into()),
+        };
+
+        let voided = svc.void(txn.id, req).await.unwrap();
+
+        assert_eq!(voided.status, TransactionStatus::Cancelled);
+        assert_eq!(voided.cancellation_reason.as_deref(), Some("duplicate order"));
+        assert_eq!(voided.version, 2);
+    }
+
+    #[tokio::test]
+    async fn void_completed_is_rejected() {
+        let svc = make_service();
+        let (txn, _) = svc.create(create_req("void-done")).await.unwrap();
+        svc.update_status(txn.id, status_req(TransactionStatus::Completed))
+            .await
+            .unwrap();
+
+        let err = svc.void(txn.id, void_req()).await.unwrap_err();
+        assert!(matches!(err, AppError::InvalidStateTransition { .. }));
+    }
+
+    #[tokio::test]
+    async fn void_requires_a_reason() {
+        let svc = make_service();
+        let (txn, _) = svc.create(create_req("void-no-reason")).await.unwrap();
+
+        for reason in [None, Some("  ".to_string())] {
+            let err = svc.void(txn.id, VoidRequest { reason }).await.unwrap_err();
+            assert!(matches!(err, AppError::Validation(ref m) if m.contains("reason is required")));
+        }
+        assert_eq!(svc.get(txn.id).await.unwrap().status, TransactionStatus::Pending);
+    }
+
+    #[tokio::test]
+    async fn publishes_create_and_status_events() {
+        let svc = make_service();
+        let mut events = svc.subscribe();
+
+        let (txn, _) = svc.create(create_req("events")).await.unwrap();
+        svc.create(create_req("events")).await.unwrap();
+        svc.update_status(txn.id, status_req(TransactionStatus::Completed))
+            .await
+            .unwrap();
+
+        let created = events.recv().await.unwrap();
+        assert_eq!(created.kind, TransactionEventKind::Created);
+        assert_eq!(created.transaction.id, txn.id);
+        // The idempotent replay publishes nothing.
+        let changed = events.recv().await.unwrap();
+        assert_eq!(changed.kind, TransactionEventKind::StatusChanged);
+        assert_eq!(changed.transaction.status, TransactionStatus::Completed);
+        assert!(events.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn publishes_updates_and_deletes() {
+        let svc = make_service();
+        let (txn, _) = svc.create(create_req("updates")).await.unwrap();
+        let mut events = svc.subscribe();
+
+        svc.update_amount(txn.id, UpdateAmountRequest { amount: 10.0 })
+            .await
+            .unwrap();
+        svc.delete(txn.id, None, None).await.unwrap();
+        svc.restore(txn.id).await.unwrap();
+
+        for _ in 0..3 {
+            let event = events.try_recv().unwrap();
+            assert_eq!(event.kind, TransactionEventKind::Updated);
+            assert_eq!(event.transaction.id, txn.id);
+        }
+        assert!(events.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn event_sequence_increases_without_gaps() {
+        let svc = make_service();
+        let mut events = svc.subscribe();
+
+        for key in ["seq-1", "seq-2", "seq-3"] {
+            let (txn, _) = svc.create(create_req(key)).await.unwrap();
+            svc.update_status(txn.id, status_req(TransactionStatus::Completed))
+                .await
+                .unwrap();
+        }
+
+        let mut sequences = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            sequences.push(event.sequence);
+        }
+        assert_eq!(sequences, (1..=6).collect::<Vec<_>>());
+    }
+
+    type Transitions = Arc<std::sync::Mutex<Vec<(TransactionStatus, TransactionStatus)>>>;
+
+    struct RecordingHook {
+        seen: Transitions,
+        fail: bool,
+    }
+
+    impl TransitionHook for RecordingHook {
+        fn on_transition<'a>(
+            &'a self,
+            _txn: &'a Transaction,
+            from: TransactionStatus,
+            to: TransactionStatus,
+        ) -> futures_util::future::BoxFuture<'a, Result<(), AppError>> {
+            Box::pin(async move {
+                self.seen.lock().unwrap().push((from, to));
+                if self.fail {
+                    return Err(AppError::Unavailable("ledger offline".into()));
+                }
+                Ok(())
+            })
+        }
+    }
+
+    fn recording_hook(fail: bool) -> (Arc<RecordingHook>, Transitions) {
+        let seen = Transitions::default();
+        let hook = RecordingHook {
+            seen: seen.clone(),
+            fail,
+        };
+        (Arc::new(hook), seen)
+    }
+
+    #[tokio::test]
+    async fn hook_observes_transition() {
+        let (hook, seen) = recording_hook(false);
+        let svc = make_service().with_transition_hook(hook);
+        let (txn, _) = svc.create(create_req("hook")).await.unwrap();
+
+        svc.update_status(txn.id, status_req(TransactionStatus::Completed))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            *seen.lock().unwrap(),
+            [(TransactionStatus::Pending, TransactionStatus::Completed)]
+        );
+    }
+
+    #[tokio::test]
+    async fn hook_failure_policy() {
+        let (hook, _) = recording_hook(true);
+        let svc = make_service().with_transition_hook(hook.clone());
+        let (txn, _) = svc.create(create_req("hook-best-effort")).await.unwrap();
+        let updated = svc
+            .update_status(txn.id, status_req(TransactionStatus::Completed))
+            .await
+            .unwrap();
+        assert_eq!(updated.status, TransactionStatus::Completed);
+
+        let svc = make_service()
+            .with_transition_hook(hook)
+            .with_hook_policy(HookPolicy::Mandatory);
+        let (txn, _) = svc.create(create_req("hook-mandatory")).await.unwrap();
+        let err = svc
+            .update_status(txn.id, status_req(TransactionStatus::Completed))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AppError::Unavailable(_)));
+        assert_eq!(svc.get(txn.id).await.unwrap().status, TransactionStatus::Pending);
+    }
+
+    #[tokio::test]
+    async fn status_batch_reports_each_id() {
+        let svc = make_service();
+        let (a, _) = svc.create(create_req("batch-status-a")).await.unwrap();
+        let (b, _) = svc.create(create_req("batch-status-b")).await.unwrap();
+        let (done, _) = svc.create(create_req("batch-status-done")).await.unwrap();
+        svc.update_status(done.id, status_req(TransactionStatus::Completed))
+            .await
+            .unwrap();
+        let missing = Uuid::new_v4();
+
+        let results = svc
+            .update_status_batch(StatusBatchRequest {
+                ids: vec![a.id, done.id, missing, b.id],
+                status: TransactionStatus::Completed,
+                reason: None,
+            })
+            .await
+            .unwrap();
+
+        let ids: Vec<_> = results.iter().map(|r| r.id).collect();
+        assert_eq!(ids, [a.id, done.id, missing, b.id]);
+        assert!(matches!(results[0].outcome, StatusBatchOutcome::Updated { .. }));
+        assert!(matches!(
+            results[1].outcome,
+            StatusBatchOutcome::InvalidTransition { ref from } if from == "COMPLETED"
+        ));
+        assert!(matches!(results[2].outcome, StatusBatchOutcome::NotFound));
+        assert!(matches!(results[3].outcome, StatusBatchOutcome::Updated { .. }));
+        assert_eq!(svc.get(b.id).await.unwrap().status, TransactionStatus::Completed);
+    }
+
+    #[tokio::test]
+    async fn status_batch_is_capped() {
+        let svc = make_service();
+        let req = StatusBatchRequest {
+            ids: vec![Uuid::new_v4(); MAX_STATUS_BATCH + 1],
+            status: TransactionStatus::Completed,
+            reason: None,
+        };
+        assert!(matches!(
+            svc.update_status_batch(req).await,
+            Err(AppError::Validation(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn tags_are_normalized_and_filterable() {
+        let svc = make_service();
+        let tagged = CreateTransactionRequest {
+            tags: vec![" Payroll ".into(), "payroll".into(), "Q3".into()],
+            ..create_req("tagged")
+        };
+        let (txn, _) = svc.create(tagged).await.unwrap();
+        svc.create(create_req("untagged")).await.unwrap();
+        assert_eq!(txn.tags, ["payroll", "q3"]);
+
+        let result = svc
+            .list(ListFilters {
+                tag: Some("PAYROLL".into()),
+                ..ListFilters::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(result.items.len(), 1);
+        assert_eq!(result.items[0].id, txn.id);
+    }
+
+    #[tokio::test]
+    async fn get_by_key() {
+        let svc = make_service();
+        let (txn, _) = svc.create(create_req("lookup")).await.unwrap();
+
+        let fetched = svc.get_by_key(None, "lookup").await.unwrap();
+        assert_eq!(fetched.id, txn.id);
+        assert!(matches!(svc.get_by_key(None, "missing").await, Err(AppError::NotFound(_))));
+        let too_long = "k".repeat(ValidationConfig::default().max_idempotency_key_length + 1);
+        assert!(matches!(svc.get_by_key(None, &too_long).await, Err(AppError::Validation(_))));
+    }
+
+    #[tokio::test]
+    async fn disabled_currency_rejects_creates_only() {
+        let svc = make_service();
+        let (existing, _) = svc.create(create_req("before")).await.unwrap();
+
+        svc.set_currency_enabled(Currency::Usd, false);
+        let result = svc.create(create_req("after")).await;
+        assert!(matches!(result, Err(AppError::Validation(_))));
+
+        assert_eq!(svc.get(existing.id).await.unwrap().id, existing.id);
+        svc.update_status(existing.id, status_req(TransactionStatus::Completed))
+            .await
+            .unwrap();
+
+        svc.set_currency_enabled(Currency::Usd, true);
+        assert!(svc.create(create_req("re-enabled")).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn facets_count_full_filtered_set() {
+        let svc = make_service();
+        let (a, _) = svc.create(create_req("f1")).await.unwrap();
+        svc.create(create_req("f2")).await.unwrap();
+        let mut eur = create_req("f3");
+        eur.currency = Some(Currency::Eur);
+        svc.create(eur).await.unwrap();
+        svc.update_status(a.id, status_req(TransactionStatus::Completed))
+            .await
+            .unwrap();
+
+        let result = svc
+            .list(ListFilters {
+                currency: Some(Currency::Usd),
+                facets: vec![Facet::Status, Facet::Currency],
+                ..ListFilters::default()
+            })
+            .await
+            .unwrap();
+        let facets = result.facets.unwrap();
+        let status = facets.status.unwrap();
+        assert_eq!(status[&TransactionStatus::Pending], 1);
+        assert_eq!(status[&TransactionStatus::Completed], 1);
+        assert_eq!(facets.currency.unwrap(), [(Currency::Usd, 2)].into());
+    }
+
+    #[tokio::test]
+    async fn get_not_found() {
+        let svc = make_service();
+        let result = svc.get(Uuid::new_v4()).await;
+        assert!(matches!(result, Err(AppError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn create_emits_span_with_id_and_outcome() {
+        let capture = Capture::default();
+        let subscriber = tracing_subscriber::fmt()
+            .json()
+            .with_span_events(tracing_subscriber::fmt::format::FmtSpan::CLOSE)
+            .with_writer(capture.clone())
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let svc = make_service();
+        let (txn, _) = svc.create(create_req("traced")).await.unwrap();
+        svc.get(Uuid::new_v4()).await.unwrap_err();
+
+        let lines = capture.json_lines();
+        let create = lines
+            .iter()
+            .find(|l| l["span"]["name"] == "create")
+            .expect("create span");
+        assert_eq!(create["span"]["transaction_id"], txn.id.to_string());
+        assert_eq!(create["span"]["outcome"], "created");
+        assert!(lines.iter().any(|l| l["span"]["name"] == "get"
+            && l["level"] == "WARN"
+            && l["fields"]["error"].as_str().is_some_and(|e| e.contains("not found"))));
     }
 }
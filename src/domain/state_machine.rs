@@ -0,0 +1,75 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use serde::{Deserialize, Serialize};
+
+use crate::domain::models::TransactionStatus;
+
+/// Export format for the transition graph.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GraphFormat {
+    #[default]
+    Json,
+    Dot,
+}
+
+/// Adjacency list of the status transitions this deployment allows, derived from
+/// `TransactionStatus::can_transition_to`. Every status appears as a key, including terminal
+/// ones with no outgoing edges.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TransitionGraph {
+    pub transitions: BTreeMap<TransactionStatus, Vec<TransactionStatus>>,
+}
+
+impl TransitionGraph {
+    pub fn current() -> Self {
+        let transitions = TransactionStatus::ALL
+            .into_iter()
+            .map(|from| {
+                let targets = TransactionStatus::ALL
+                    .into_iter()
+                    .filter(|to| from.can_transition_to(*to))
+                    .collect();
+                (from, targets)
+            })
+            .collect();
+        Self { transitions }
+    }
+
+    /// Renders the graph in Graphviz DOT syntax.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph transaction_status {\n");
+        for (from, targets) in &self.transitions {
+            let _ = writeln!(dot, "    {};", from);
+            for to in targets {
+                let _ = writeln!(dot, "    {} -> {};", from, to);
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use TransactionStatus::*;
+
+    #[test]
+    fn default_matrix_edges() {
+        let graph = TransitionGraph::current();
+        assert_eq!(graph.transitions[&Pending], vec![Processing, Completed, Failed, Cancelled]);
+        assert_eq!(graph.transitions[&Processing], vec![Completed, Failed]);
+        assert!(graph.transitions[&Completed].is_empty());
+        assert!(graph.transitions[&Failed].is_empty());
+        assert!(graph.transitions[&Cancelled].is_empty());
+
+        let dot = graph.to_dot();
+        assert!(dot.contains("PENDING -> COMPLETED;"));
+        assert!(dot.contains("PENDING -> FAILED;"));
+        assert!(dot.contains("PENDING -> CANCELLED;"));
+        assert!(dot.contains("PROCESSING -> COMPLETED;"));
+        assert_eq!(dot.matches("->").count(), 6);
+    }
+}
@@ -0,0 +1,44 @@
+use crate::domain::models::{ListFilters, Transaction, TransactionStatus, UpdateStatusRequest};
+use crate::domain::service::{TransactionService, MAX_PAGE_SIZE};
+use crate::error::AppError;
+use crate::storage::Storage;
+
+/// Moves every pending transaction `due` selects to `to`, recording `reason`. Shared by the
+/// background tasks so they page and handle races the same way. Returns how many moved.
+/// Transactions that change concurrently are skipped rather than treated as failures.
+pub async fn sweep_pending<S: Storage>(
+    service: &TransactionService<S>,
+    due: impl Fn(&Transaction) -> bool,
+    to: TransactionStatus,
+    reason: Option<&str>,
+) -> Result<usize, AppError> {
+    let mut moved = 0;
+    let mut cursor = None;
+    // Page through every pending transaction; an unpaged list stops at the result cap.
+    loop {
+        let pending = service
+            .list(ListFilters {
+                status: vec![TransactionStatus::Pending],
+                limit: Some(MAX_PAGE_SIZE),
+                cursor,
+                ..ListFilters::default()
+            })
+            .await?;
+        for txn in pending.items.iter().filter(|t| due(t)) {
+            let req = UpdateStatusRequest {
+                status: to,
+                expected_version: Some(txn.version),
+                reason: reason.map(str::to_string),
+            };
+            match service.update_status(txn.id, req).await {
+                Ok(_) => moved += 1,
+                Err(AppError::VersionConflict { .. } | AppError::NotFound(_)) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        match pending.next_cursor {
+            Some(next) => cursor = Some(next.to_string()),
+            None => return Ok(moved),
+        }
+    }
+}
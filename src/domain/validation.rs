@@ -1,49 +1,313 @@
-use crate::domain::models::CreateTransactionRequest;
+use crate::domain::models::{CreateTransactionRequest, Currency};
 use crate::error::AppError;
+use chrono::{DateTime, TimeDelta, Utc};
+use regex::Regex;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
 
 const MAX_DESCRIPTION_LENGTH: usize = 500;
 const MAX_IDEMPOTENCY_KEY_LENGTH: usize = 128;
+const MAX_AMOUNT: f64 = 1_000_000_000.0;
+const MAX_TAGS: usize = 10;
+const MAX_TAG_LENGTH: usize = 50;
+const MAX_NOTE_AUTHOR_LENGTH: usize = 100;
+const MAX_NOTE_LENGTH: usize = 2_000;
+/// How far in the past `execute_at` may be, to absorb clock skew between client and server.
+const EXECUTE_AT_SKEW: TimeDelta = TimeDelta::seconds(5);
 
-pub fn validate_create_request(req: &CreateTransactionRequest) -> Result<(), AppError> {
-    if req.amount <= 0.0 {
+/// Limits applied by `validate_create_request`. Defaults match the historical hardcoded values.
+#[derive(Debug, Clone)]
+pub struct ValidationConfig {
+    pub max_description_length: usize,
+    pub max_idempotency_key_length: usize,
+    pub max_amount: f64,
+    /// Per-currency overrides of `Currency::max_amount`.
+    pub currency_limits: HashMap<Currency, f64>,
+    /// Format new idempotency keys must have, e.g. a UUID. Build with `full_match` so the whole
+    /// key has to match. `None` accepts any key within the length limit.
+    pub idempotency_key_pattern: Option<Regex>,
+}
+
+impl ValidationConfig {
+    pub fn currency_limit(&self, currency: Currency) -> f64 {
+        self.currency_limits
+            .get(&currency)
+            .copied()
+            .unwrap_or_else(|| currency.max_amount())
+    }
+}
+
+impl Default for ValidationConfig {
+    fn default() -> Self {
+        Self {
+            max_description_length: MAX_DESCRIPTION_LENGTH,
+            max_idempotency_key_length: MAX_IDEMPOTENCY_KEY_LENGTH,
+            max_amount: MAX_AMOUNT,
+            currency_limits: HashMap::new(),
+            idempotency_key_pattern: None,
+        }
+    }
+}
+
+/// Compiles `pattern` anchored at both ends, so it has to match a key in full.
+pub fn full_match(pattern: &str) -> Result<Regex, regex::Error> {
+    Regex::new(&format!("^(?:{})$", pattern))
+}
+
+/// `now` is the service's clock, which the expiry and execution times are checked against.
+pub fn validate_create_request(
+    req: &CreateTransactionRequest,
+    config: &ValidationConfig,
+    now: DateTime<Utc>,
+) -> Result<(), AppError> {
+    validate_amount(req.amount, req.currency, config)?;
+    validate_description(&req.description, config)?;
+    validate_tags(&req.tags)?;
+
+    if let Some(key) = &req.idempotency_key {
+        validate_idempotency_key(key, config)?;
+        if let Some(pattern) = &config.idempotency_key_pattern {
+            if !pattern.is_match(key) {
+                return Err(AppError::Validation(format!(
+                    "Idempotency key {:?} does not match the required format {}",
+                    key,
+                    pattern.as_str()
+                )));
+            }
+        }
+    }
+
+    if let Some(expires_at) = req.expires_at {
+        if expires_at <= now {
+            return Err(AppError::Validation(
+                "Expiry must be in the future".into(),
+            ));
+        }
+    }
+
+    if let Some(execute_at) = req.execute_at {
+        if execute_at < now - EXECUTE_AT_SKEW {
+            return Err(AppError::Validation(
+                "Execution time must not be in the past".into(),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// The per-currency limit is only checked when `currency` is known.
+pub fn validate_amount(
+    amount: f64,
+    currency: Option<Currency>,
+    config: &ValidationConfig,
+) -> Result<(), AppError> {
+    if amount <= 0.0 {
         return Err(AppError::Validation(
             "Amount must be greater than zero".into(),
         ));
     }
 
-    if !req.amount.is_finite() {
+    if !amount.is_finite() {
         return Err(AppError::Validation("Amount must be a finite number".into()));
     }
 
-    if req.description.trim().is_empty() {
+    if amount > config.max_amount {
+        return Err(AppError::Validation(format!(
+            "Amount must not exceed {}",
+            config.max_amount
+        )));
+    }
+
+    if let Some(currency) = currency {
+        let currency_limit = config.currency_limit(currency);
+        if amount > currency_limit {
+            return Err(AppError::Validation(format!(
+                "Amount for {} must not exceed {}",
+                currency, currency_limit
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+pub fn validate_description(description: &str, config: &ValidationConfig) -> Result<(), AppError> {
+    if description.trim().is_empty() {
         return Err(AppError::Validation(
             "Description must not be empty".into(),
         ));
     }
 
-    if req.description.len() > MAX_DESCRIPTION_LENGTH {
+    if description.len() > config.max_description_length {
         return Err(AppError::Validation(format!(
             "Description must not exceed {} characters",
-            MAX_DESCRIPTION_LENGTH
+            config.max_description_length
+        )));
+    }
+
+    Ok(())
+}
+
+pub fn validate_note(author: &str, text: &str) -> Result<(), AppError> {
+    if author.trim().is_empty() {
+        return Err(AppError::Validation("Note author must not be empty".into()));
+    }
+    if author.chars().count() > MAX_NOTE_AUTHOR_LENGTH {
+        return Err(AppError::Validation(format!(
+            "Note author must not exceed {} characters",
+            MAX_NOTE_AUTHOR_LENGTH
+        )));
+    }
+    if text.trim().is_empty() {
+        return Err(AppError::Validation("Note text must not be empty".into()));
+    }
+    if text.chars().count() > MAX_NOTE_LENGTH {
+        return Err(AppError::Validation(format!(
+            "Note text must not exceed {} characters",
+            MAX_NOTE_LENGTH
+        )));
+    }
+    Ok(())
+}
+
+pub fn validate_tags(tags: &[String]) -> Result<(), AppError> {
+    if tags.len() > MAX_TAGS {
+        return Err(AppError::Validation(format!(
+            "At most {} tags are allowed",
+            MAX_TAGS
         )));
     }
 
-    if req.idempotency_key.trim().is_empty() {
+    for tag in tags {
+        let tag = tag.trim();
+        if tag.is_empty() {
+            return Err(AppError::Validation("Tags must not be empty".into()));
+        }
+        if tag.chars().count() > MAX_TAG_LENGTH {
+            return Err(AppError::Validation(format!(
+                "Tag {:?} exceeds {} characters",
+                tag, MAX_TAG_LENGTH
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Trims and lowercases tags, dropping repeats while keeping first-seen order.
+pub fn normalize_tags(tags: Vec<String>) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::with_capacity(tags.len());
+    for tag in tags {
+        let tag = tag.trim().to_lowercase();
+        if !normalized.contains(&tag) {
+            normalized.push(tag);
+        }
+    }
+    normalized
+}
+
+pub fn validate_idempotency_key(key: &str, config: &ValidationConfig) -> Result<(), AppError> {
+    if key.trim().is_empty() {
         return Err(AppError::Validation(
             "Idempotency key must not be empty".into(),
         ));
     }
 
-    if req.idempotency_key.len() > MAX_IDEMPOTENCY_KEY_LENGTH {
+    if key.len() > config.max_idempotency_key_length {
         return Err(AppError::Validation(format!(
             "Idempotency key must not exceed {} characters",
-            MAX_IDEMPOTENCY_KEY_LENGTH
+            config.max_idempotency_key_length
         )));
     }
-
     Ok(())
 }
 
+/// JSON Schema (draft 2020-12) for a create request under `config`, for clients that validate
+/// before sending. `currencies` lists those accepted right now, matched case-insensitively like
+/// the server does; `currency` is only required when there is no default. Lengths are the byte
+/// limits enforced here, which equal character counts for ASCII text only.
+pub fn create_request_schema(
+    config: &ValidationConfig,
+    currencies: &[Currency],
+    currency_required: bool,
+    idempotency_key_required: bool,
+) -> Value {
+    let mut idempotency_key = json!({
+        "type": "string",
+        "minLength": 1,
+        "maxLength": config.max_idempotency_key_length,
+    });
+    if let Some(pattern) = &config.idempotency_key_pattern {
+        idempotency_key["pattern"] = pattern.as_str().into();
+    }
+    let currency_limits: Map<String, Value> = currencies
+        .iter()
+        .map(|currency| (currency.to_string(), config.currency_limit(*currency).into()))
+        .collect();
+    let mut required = vec!["amount", "description"];
+    if currency_required {
+        required.push("currency");
+    }
+    if idempotency_key_required {
+        required.push("idempotency_key");
+    }
+    // JSON Schema patterns have no case-insensitive flag, so each letter gets both cases.
+    let codes: Vec<String> = currencies
+        .iter()
+        .map(|currency| {
+            let code = currency.to_string();
+            code.chars()
+                .map(|c| format!("[{}{}]", c, c.to_ascii_lowercase()))
+                .collect()
+        })
+        .collect();
+    let currency_pattern = if codes.is_empty() {
+        // Nothing is accepted right now.
+        "^(?!)".to_string()
+    } else {
+        format!("^(?:{})$", codes.join("|"))
+    };
+    let currency_names: Vec<String> = currencies.iter().map(Currency::to_string).collect();
+
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "CreateTransactionRequest",
+        "type": "object",
+        "additionalProperties": false,
+        "required": required,
+        "properties": {
+            "idempotency_key": idempotency_key,
+            "amount": {
+                "type": "number",
+                "exclusiveMinimum": 0,
+                "maximum": config.max_amount,
+                // Not a standard keyword: the lower per-currency ceilings.
+                "x-maximum-by-currency": currency_limits,
+            },
+            "currency": {
+                "type": "string",
+                "description": format!(
+                    "One of {}, in any letter case",
+                    currency_names.join(", ")
+                ),
+                "pattern": currency_pattern,
+            },
+            "description": {
+                "type": "string",
+                "minLength": 1,
+                "maxLength": config.max_description_length,
+            },
+            "expires_at": { "type": "string", "format": "date-time" },
+            "execute_at": { "type": "string", "format": "date-time" },
+            "tags": {
+                "type": "array",
+                "maxItems": MAX_TAGS,
+                "items": { "type": "string", "minLength": 1, "maxLength": MAX_TAG_LENGTH },
+            },
+        },
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -51,57 +315,165 @@ mod tests {
 
     fn valid_request() -> CreateTransactionRequest {
         CreateTransactionRequest {
-            idempotency_key: "key-123".into(),
+            idempotency_key: Some("key-123".into()),
+            tenant_id: None,
             amount: 100.0,
-            currency: Currency::Usd,
+            currency: Some(Currency::Usd),
             description: "Test payment".into(),
+            expires_at: None,
+            execute_at: None,
+            tags: Vec::new(),
         }
     }
 
     #[test]
     fn valid_request_passes() {
-        assert!(validate_create_request(&valid_request()).is_ok());
+        let config = ValidationConfig::default();
+        assert!(validate_create_request(&valid_request(), &config, Utc::now()).is_ok());
     }
 
     #[test]
     fn zero_amount_rejected() {
         let mut req = valid_request();
         req.amount = 0.0;
-        assert!(validate_create_request(&req).is_err());
+        assert!(validate_create_request(&req, &ValidationConfig::default(), Utc::now()).is_err());
     }
 
     #[test]
     fn negative_amount_rejected() {
         let mut req = valid_request();
         req.amount = -50.0;
-        assert!(validate_create_request(&req).is_err());
+        assert!(validate_create_request(&req, &ValidationConfig::default(), Utc::now()).is_err());
     }
 
     #[test]
     fn infinite_amount_rejected() {
         let mut req = valid_request();
         req.amount = f64::INFINITY;
-        assert!(validate_create_request(&req).is_err());
+        assert!(validate_create_request(&req, &ValidationConfig::default(), Utc::now()).is_err());
     }
 
     #[test]
     fn empty_description_rejected() {
         let mut req = valid_request();
         req.description = "   ".into();
-        assert!(validate_create_request(&req).is_err());
+        assert!(validate_create_request(&req, &ValidationConfig::default(), Utc::now()).is_err());
     }
 
     #[test]
     fn long_description_rejected() {
         let mut req = valid_request();
         req.description = "x".repeat(501);
-        assert!(validate_create_request(&req).is_err());
+        assert!(validate_create_request(&req, &ValidationConfig::default(), Utc::now()).is_err());
+    }
+
+    #[test]
+    fn usd_amount_at_limit_accepted() {
+        let mut req = valid_request();
+        req.amount = Currency::Usd.max_amount();
+        assert!(validate_create_request(&req, &ValidationConfig::default(), Utc::now()).is_ok());
+    }
+
+    #[test]
+    fn usd_amount_over_limit_rejected() {
+        let mut req = valid_request();
+        req.amount = Currency::Usd.max_amount() + 0.01;
+        let config = ValidationConfig::default();
+        let err = validate_create_request(&req, &config, Utc::now()).unwrap_err();
+        assert!(err.to_string().contains("USD"));
+    }
+
+    #[test]
+    fn currency_limit_override_applies() {
+        let config = ValidationConfig {
+            currency_limits: HashMap::from([(Currency::Usd, 500.0)]),
+            ..ValidationConfig::default()
+        };
+        let mut req = valid_request();
+        req.amount = 500.01;
+        assert!(validate_create_request(&req, &config, Utc::now()).is_err());
+    }
+
+    #[test]
+    fn tag_limits_enforced() {
+        let mut req = valid_request();
+        req.tags = vec!["t".into(); MAX_TAGS + 1];
+        assert!(validate_create_request(&req, &ValidationConfig::default(), Utc::now()).is_err());
+
+        req.tags = vec!["x".repeat(MAX_TAG_LENGTH + 1)];
+        assert!(validate_create_request(&req, &ValidationConfig::default(), Utc::now()).is_err());
+
+        req.tags = vec!["x".repeat(MAX_TAG_LENGTH); MAX_TAGS];
+        assert!(validate_create_request(&req, &ValidationConfig::default(), Utc::now()).is_ok());
+    }
+
+    #[test]
+    fn amount_above_max_rejected() {
+        let mut req = valid_request();
+        req.currency = Some(Currency::Jpy);
+        req.amount = MAX_AMOUNT + 1.0;
+        assert!(validate_create_request(&req, &ValidationConfig::default(), Utc::now()).is_err());
+    }
+
+    #[test]
+    fn custom_config_rejects_shorter_description() {
+        let config = ValidationConfig {
+            max_description_length: 10,
+            ..ValidationConfig::default()
+        };
+        let mut req = valid_request();
+        req.description = "x".repeat(11);
+        assert!(validate_create_request(&req, &ValidationConfig::default(), Utc::now()).is_ok());
+        assert!(validate_create_request(&req, &config, Utc::now()).is_err());
+    }
+
+    #[test]
+    fn configured_key_pattern_must_match_whole_key() {
+        let config = ValidationConfig {
+            idempotency_key_pattern: Some(full_match("order-[0-9]+").unwrap()),
+            ..ValidationConfig::default()
+        };
+        let mut req = valid_request();
+        req.idempotency_key = Some("order-42".into());
+        assert!(validate_create_request(&req, &config, Utc::now()).is_ok());
+
+        for key in ["invoice-42", "order-42x", "xorder-42"] {
+            req.idempotency_key = Some(key.into());
+            match validate_create_request(&req, &config, Utc::now()) {
+                Err(AppError::Validation(msg)) => assert!(msg.contains(key), "{}", msg),
+                other => panic!("expected {} to be rejected, got {:?}", key, other),
+            }
+        }
+        assert!(validate_create_request(&req, &ValidationConfig::default(), Utc::now()).is_ok());
+    }
+
+    #[test]
+    fn past_expiry_rejected() {
+        let mut req = valid_request();
+        req.expires_at = Some(Utc::now() - chrono::TimeDelta::minutes(1));
+        assert!(validate_create_request(&req, &ValidationConfig::default(), Utc::now()).is_err());
+    }
+
+    #[test]
+    fn future_expiry_accepted() {
+        let mut req = valid_request();
+        req.expires_at = Some(Utc::now() + chrono::TimeDelta::minutes(5));
+        assert!(validate_create_request(&req, &ValidationConfig::default(), Utc::now()).is_ok());
+    }
+
+    #[test]
+    fn execute_at_tolerates_small_skew_only() {
+        let mut req = valid_request();
+        req.execute_at = Some(Utc::now() - TimeDelta::seconds(1));
+        assert!(validate_create_request(&req, &ValidationConfig::default(), Utc::now()).is_ok());
+        req.execute_at = Some(Utc::now() - TimeDelta::minutes(1));
+        assert!(validate_create_request(&req, &ValidationConfig::default(), Utc::now()).is_err());
     }
 
     #[test]
     fn empty_idempotency_key_rejected() {
         let mut req = valid_request();
-        req.idempotency_key = "".into();
-        assert!(validate_create_request(&req).is_err());
+        req.idempotency_key = Some("".into());
+        assert!(validate_create_request(&req, &ValidationConfig::default(), Utc::now()).is_err());
     }
 }
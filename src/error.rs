@@ -2,43 +2,217 @@ use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
 use serde_json::json;
 
+use crate::domain::models::{Currency, Transaction};
+
 #[derive(Debug, thiserror::Error)]
 pub enum AppError {
     #[error("Transaction not found: {0}")]
     NotFound(String),
 
+    /// No route matches the request; reported with the `NOT_FOUND` type like a missing
+    /// transaction.
+    #[error("No route for {0}")]
+    RouteNotFound(String),
+
     #[error("Validation error: {0}")]
     Validation(String),
 
+    #[error("No exchange rate from {base} to {quote}")]
+    RateNotFound { base: Currency, quote: Currency },
+
     #[error("Duplicate idempotency key")]
     IdempotencyConflict,
 
+    #[error("Version conflict: transaction is at version {}", current.version)]
+    VersionConflict { current: Box<Transaction> },
+
     #[error("Invalid state transition from {from} to {to}")]
     InvalidStateTransition { from: String, to: String },
 
+    #[error("Transaction is {status}; only PENDING transactions can be changed")]
+    NotPending { status: String },
+
+    #[error("Transaction is not deleted: {0}")]
+    NotDeleted(String),
+
+    #[error("Request body exceeds the {limit}-byte limit")]
+    PayloadTooLarge { limit: usize },
+
+    #[error("Too many requests: {0}")]
+    TooManyRequests(String),
+
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String),
+
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+
+    #[error("Method not allowed: {0}")]
+    MethodNotAllowed(String),
+
+    #[error("Unsupported media type: {0}")]
+    UnsupportedMediaType(String),
+
+    #[error("Service unavailable: {0}")]
+    Unavailable(String),
+
+    #[error("Deadline exceeded: {0}")]
+    DeadlineExceeded(String),
+
     #[error("Internal error: {0}")]
     Internal(String),
 }
 
-impl IntoResponse for AppError {
-    fn into_response(self) -> Response {
-        let (status, message) = match &self {
-            AppError::NotFound(_) => (StatusCode::NOT_FOUND, self.to_string()),
-            AppError::Validation(_) => (StatusCode::BAD_REQUEST, self.to_string()),
-            AppError::IdempotencyConflict => (StatusCode::CONFLICT, self.to_string()),
-            AppError::InvalidStateTransition { .. } => {
-                (StatusCode::UNPROCESSABLE_ENTITY, self.to_string())
-            }
-            AppError::Internal(_) => (StatusCode::INTERNAL_SERVER_ERROR, self.to_string()),
-        };
+impl AppError {
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::RouteNotFound(_) => StatusCode::NOT_FOUND,
+            AppError::Validation(_) => StatusCode::BAD_REQUEST,
+            AppError::RateNotFound { .. } => StatusCode::NOT_FOUND,
+            AppError::IdempotencyConflict => StatusCode::CONFLICT,
+            AppError::VersionConflict { .. } => StatusCode::CONFLICT,
+            AppError::InvalidStateTransition { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+            AppError::NotPending { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+            AppError::NotDeleted(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            AppError::PayloadTooLarge { .. } => StatusCode::PAYLOAD_TOO_LARGE,
+            AppError::TooManyRequests(_) => StatusCode::TOO_MANY_REQUESTS,
+            AppError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            AppError::Forbidden(_) => StatusCode::FORBIDDEN,
+            AppError::MethodNotAllowed(_) => StatusCode::METHOD_NOT_ALLOWED,
+            AppError::UnsupportedMediaType(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            AppError::Unavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            AppError::DeadlineExceeded(_) => StatusCode::GATEWAY_TIMEOUT,
+            AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// Stable machine-readable identifier for the error kind. Unlike the HTTP status, this is
+    /// part of the API contract and must not change once published.
+    pub fn error_type(&self) -> &'static str {
+        match self {
+            AppError::NotFound(_) => "NOT_FOUND",
+            AppError::RouteNotFound(_) => "NOT_FOUND",
+            AppError::Validation(_) => "VALIDATION_FAILED",
+            AppError::RateNotFound { .. } => "RATE_NOT_FOUND",
+            AppError::IdempotencyConflict => "IDEMPOTENCY_CONFLICT",
+            AppError::VersionConflict { .. } => "VERSION_CONFLICT",
+            AppError::InvalidStateTransition { .. } => "INVALID_STATE_TRANSITION",
+            AppError::NotPending { .. } => "NOT_PENDING",
+            AppError::NotDeleted(_) => "NOT_DELETED",
+            AppError::PayloadTooLarge { .. } => "PAYLOAD_TOO_LARGE",
+            AppError::TooManyRequests(_) => "TOO_MANY_REQUESTS",
+            AppError::Unauthorized(_) => "UNAUTHORIZED",
+            AppError::Forbidden(_) => "FORBIDDEN",
+            AppError::MethodNotAllowed(_) => "METHOD_NOT_ALLOWED",
+            AppError::UnsupportedMediaType(_) => "UNSUPPORTED_MEDIA_TYPE",
+            AppError::Unavailable(_) => "SERVICE_UNAVAILABLE",
+            AppError::DeadlineExceeded(_) => "DEADLINE_EXCEEDED",
+            AppError::Internal(_) => "INTERNAL_ERROR",
+        }
+    }
 
-        let body = json!({
+    /// The `{"error": {...}}` body sent to clients for this error.
+    pub fn to_body(&self) -> serde_json::Value {
+        let mut body = json!({
             "error": {
-                "code": status.as_u16(),
-                "message": message,
+                "code": self.status_code().as_u16(),
+                "type": self.error_type(),
+                "message": self.to_string(),
             }
         });
 
-        (status, axum::Json(body)).into_response()
+        // Hand the client the server's state so it can reconcile without another GET.
+        if let AppError::VersionConflict { current } = self {
+            body["error"]["current"] = json!(current);
+        }
+
+        body
+    }
+}
+
+/// Attached to error responses so the access log can report why a request failed; the request
+/// path is not known here.
+#[derive(Debug, Clone)]
+pub struct ErrorDetail {
+    pub error_type: &'static str,
+    pub message: String,
+    /// Set when the client was sent a redacted body quoting this reference instead of `message`.
+    pub reference: Option<String>,
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let detail = ErrorDetail {
+            error_type: self.error_type(),
+            message: self.to_string(),
+            reference: None,
+        };
+        let mut response = (self.status_code(), axum::Json(self.to_body())).into_response();
+        response.extensions_mut().insert(detail);
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::models::TransactionStatus;
+    use chrono::Utc;
+
+    #[test]
+    fn each_variant_has_a_stable_type() {
+        let current = Transaction {
+            status: TransactionStatus::Completed,
+            version: 2,
+            ..Transaction::test_pending(Utc::now())
+        };
+        let cases = [
+            (AppError::NotFound("x".into()), "NOT_FOUND"),
+            (AppError::RouteNotFound("x".into()), "NOT_FOUND"),
+            (AppError::Validation("x".into()), "VALIDATION_FAILED"),
+            (
+                AppError::RateNotFound {
+                    base: Currency::Usd,
+                    quote: Currency::Eur,
+                },
+                "RATE_NOT_FOUND",
+            ),
+            (AppError::IdempotencyConflict, "IDEMPOTENCY_CONFLICT"),
+            (
+                AppError::VersionConflict {
+                    current: Box::new(current),
+                },
+                "VERSION_CONFLICT",
+            ),
+            (
+                AppError::InvalidStateTransition {
+                    from: "COMPLETED".into(),
+                    to: "PENDING".into(),
+                },
+                "INVALID_STATE_TRANSITION",
+            ),
+            (
+                AppError::NotPending {
+                    status: "COMPLETED".into(),
+                },
+                "NOT_PENDING",
+            ),
+            (AppError::NotDeleted("x".into()), "NOT_DELETED"),
+            (AppError::PayloadTooLarge { limit: 1 }, "PAYLOAD_TOO_LARGE"),
+            (AppError::TooManyRequests("x".into()), "TOO_MANY_REQUESTS"),
+            (AppError::Unauthorized("x".into()), "UNAUTHORIZED"),
+            (AppError::Forbidden("x".into()), "FORBIDDEN"),
+            (AppError::MethodNotAllowed("x".into()), "METHOD_NOT_ALLOWED"),
+            (AppError::UnsupportedMediaType("x".into()), "UNSUPPORTED_MEDIA_TYPE"),
+            (AppError::Unavailable("x".into()), "SERVICE_UNAVAILABLE"),
+            (AppError::DeadlineExceeded("x".into()), "DEADLINE_EXCEEDED"),
+            (AppError::Internal("x".into()), "INTERNAL_ERROR"),
+        ];
+
+        for (error, expected) in cases {
+            assert_eq!(error.error_type(), expected);
+            assert_eq!(error.to_body()["error"]["type"], expected);
+        }
     }
 }
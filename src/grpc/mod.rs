@@ -0,0 +1,360 @@
+pub mod pb;
+
+#[allow(clippy::all)]
+mod generated {
+    include!(concat!(env!("OUT_DIR"), "/transactions.v1.TransactionService.rs"));
+}
+
+pub use generated::*;
+
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use tonic::service::interceptor::InterceptedService;
+use tonic::service::Interceptor;
+use tonic::{Request, Response, Status};
+use uuid::Uuid;
+
+use crate::api::extract::TENANT_HEADER;
+use crate::api::jwt::{Claims, JwtConfig, READ_SCOPE, WRITE_SCOPE};
+use crate::domain::models::{
+    CreateTransactionRequest, ListFilters, Transaction, UpdateStatusRequest,
+};
+use crate::domain::service::TransactionService;
+use crate::error::AppError;
+use crate::storage::Storage;
+use transaction_service_server::TransactionServiceServer;
+
+/// Page size for a `List` call that does not set `limit`.
+const DEFAULT_LIST_LIMIT: u32 = 100;
+
+/// gRPC front end delegating to the same `TransactionService` as the REST API.
+#[derive(Clone)]
+pub struct GrpcService<S: Storage> {
+    service: TransactionService<S>,
+}
+
+impl<S: Storage> GrpcService<S> {
+    pub fn new(service: TransactionService<S>) -> Self {
+        Self { service }
+    }
+
+    /// The server, checking bearer tokens against `jwt` when set, as the REST API does.
+    pub fn into_server(
+        self,
+        jwt: Option<JwtConfig>,
+    ) -> InterceptedService<TransactionServiceServer<Self>, Authenticate> {
+        TransactionServiceServer::with_interceptor(self, Authenticate(jwt.map(Arc::new)))
+    }
+}
+
+/// Interceptor validating the `authorization: Bearer <token>` metadata and storing its `Claims`
+/// as a request extension. Without a `JwtConfig` every call passes unauthenticated.
+#[derive(Clone)]
+pub struct Authenticate(Option<Arc<JwtConfig>>);
+
+impl Interceptor for Authenticate {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        let Some(jwt) = &self.0 else {
+            return Ok(request);
+        };
+        let token = request
+            .metadata()
+            .get("authorization")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .ok_or_else(|| AppError::Unauthorized("Missing bearer token".into()))?;
+        let claims = jwt.verify(token)?;
+        request.extensions_mut().insert(claims);
+        Ok(request)
+    }
+}
+
+/// Fails unless the caller's token grants `scope`. Calls without claims only reach a handler
+/// when authentication is off.
+fn require_scope<T>(request: &Request<T>, scope: &str) -> Result<(), AppError> {
+    match request.extensions().get::<Claims>() {
+        Some(claims) if !claims.has_scope(scope) => {
+            Err(AppError::Forbidden(format!("Missing required scope {}", scope)))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Idempotency scope: the token subject when authenticated, otherwise `x-tenant-id` metadata.
+fn tenant<T>(request: &Request<T>) -> Option<String> {
+    if let Some(claims) = request.extensions().get::<Claims>() {
+        return Some(claims.sub.clone());
+    }
+    request
+        .metadata()
+        .get(TENANT_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(str::to_owned)
+}
+
+impl From<AppError> for Status {
+    fn from(err: AppError) -> Self {
+        let message = err.to_string();
+        match err {
+            AppError::NotFound(_) => Status::not_found(message),
+            AppError::RouteNotFound(_) => Status::unimplemented(message),
+            AppError::Validation(_) => Status::invalid_argument(message),
+            AppError::RateNotFound { .. } => Status::not_found(message),
+            AppError::IdempotencyConflict => Status::already_exists(message),
+            AppError::VersionConflict { .. } => Status::aborted(message),
+            AppError::InvalidStateTransition { .. } => Status::failed_precondition(message),
+            AppError::NotPending { .. } => Status::failed_precondition(message),
+            AppError::NotDeleted(_) => Status::failed_precondition(message),
+            AppError::PayloadTooLarge { .. } => Status::resource_exhausted(message),
+            AppError::TooManyRequests(_) => Status::resource_exhausted(message),
+            AppError::Unauthorized(_) => Status::unauthenticated(message),
+            AppError::Forbidden(_) => Status::permission_denied(message),
+            AppError::MethodNotAllowed(_) => Status::unimplemented(message),
+            AppError::UnsupportedMediaType(_) => Status::invalid_argument(message),
+            AppError::Unavailable(_) => Status::unavailable(message),
+            AppError::DeadlineExceeded(_) => Status::deadline_exceeded(message),
+            AppError::Internal(_) => Status::internal(message),
+        }
+    }
+}
+
+impl From<Transaction> for pb::Transaction {
+    fn from(txn: Transaction) -> Self {
+        Self {
+            id: txn.id.to_string(),
+            amount: txn.amount(),
+            idempotency_key: txn.idempotency_key.unwrap_or_default(),
+            currency: txn.currency.to_string(),
+            description: txn.description,
+            status: txn.status.to_string(),
+            expires_at: txn.expires_at.map(|t| t.to_rfc3339()),
+            version: txn.version,
+            created_at: txn.created_at.to_rfc3339(),
+            updated_at: txn.updated_at.to_rfc3339(),
+        }
+    }
+}
+
+fn parse_id(raw: &str) -> Result<Uuid, AppError> {
+    raw.parse()
+        .map_err(|_| AppError::Validation(format!("Invalid transaction id: {}", raw)))
+}
+
+fn parse_timestamp(raw: &str) -> Result<DateTime<Utc>, AppError> {
+    DateTime::parse_from_rfc3339(raw)
+        .map(|t| t.with_timezone(&Utc))
+        .map_err(|_| AppError::Validation(format!("Invalid RFC 3339 timestamp: {}", raw)))
+}
+
+impl TryFrom<pb::CreateTransactionRequest> for CreateTransactionRequest {
+    type Error = AppError;
+
+    fn try_from(req: pb::CreateTransactionRequest) -> Result<Self, Self::Error> {
+        Ok(Self {
+            idempotency_key: (!req.idempotency_key.is_empty()).then_some(req.idempotency_key),
+            tenant_id: None,
+            amount: req.amount,
+            // proto3 has no presence for strings; empty means "use the default".
+            currency: (!req.currency.is_empty())
+                .then(|| req.currency.parse())
+                .transpose()?,
+            description: req.description,
+            expires_at: req.expires_at.as_deref().map(parse_timestamp).transpose()?,
+            execute_at: None,
+            tags: Vec::new(),
+        })
+    }
+}
+
+#[tonic::async_trait]
+impl<S: Storage> transaction_service_server::TransactionService for GrpcService<S> {
+    async fn create(
+        &self,
+        request: Request<pb::CreateTransactionRequest>,
+    ) -> Result<Response<pb::CreateTransactionResponse>, Status> {
+        require_scope(&request, WRITE_SCOPE)?;
+        let tenant_id = tenant(&request);
+        let req = CreateTransactionRequest {
+            tenant_id,
+            ..CreateTransactionRequest::try_from(request.into_inner())?
+        };
+        let (txn, created) = self.service.create(req).await?;
+        Ok(Response::new(pb::CreateTransactionResponse {
+            transaction: Some(txn.into()),
+            created,
+        }))
+    }
+
+    async fn get(
+        &self,
+        request: Request<pb::GetTransactionRequest>,
+    ) -> Result<Response<pb::Transaction>, Status> {
+        require_scope(&request, READ_SCOPE)?;
+        let id = parse_id(&request.into_inner().id)?;
+        let txn = self.service.get(id).await?;
+        Ok(Response::new(txn.into()))
+    }
+
+    async fn list(
+        &self,
+        request: Request<pb::ListTransactionsRequest>,
+    ) -> Result<Response<pb::ListTransactionsResponse>, Status> {
+        require_scope(&request, READ_SCOPE)?;
+        let req = request.into_inner();
+        let filters = ListFilters {
+            status: req.status.as_deref().map(str::parse).transpose()?.into_iter().collect(),
+            currency: req.currency.as_deref().map(str::parse).transpose()?,
+            limit: Some(req.limit.unwrap_or(DEFAULT_LIST_LIMIT) as usize),
+            cursor: req.cursor,
+            ..ListFilters::default()
+        };
+        let result = self.service.list(filters).await?;
+        Ok(Response::new(pb::ListTransactionsResponse {
+            transactions: result.items.into_iter().map(Into::into).collect(),
+            next_cursor: result.next_cursor.map(|c| c.to_string()),
+        }))
+    }
+
+    async fn update_status(
+        &self,
+        request: Request<pb::UpdateStatusRequest>,
+    ) -> Result<Response<pb::Transaction>, Status> {
+        require_scope(&request, WRITE_SCOPE)?;
+        let req = request.into_inner();
+        let id = parse_id(&req.id)?;
+        let update = UpdateStatusRequest {
+            status: req.status.parse()?,
+            expected_version: req.expected_version,
+            reason: req.reason,
+        };
+        let txn = self.service.update_status(id, update).await?;
+        Ok(Response::new(txn.into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::transaction_service_server::TransactionService as _;
+    use super::*;
+    use crate::storage::memory::InMemoryStorage;
+
+    fn grpc() -> GrpcService<InMemoryStorage> {
+        GrpcService::new(TransactionService::new(InMemoryStorage::new()))
+    }
+
+    fn create_req(currency: &str) -> pb::CreateTransactionRequest {
+        pb::CreateTransactionRequest {
+            idempotency_key: "grpc-1".into(),
+            amount: 42.0,
+            currency: currency.into(),
+            description: "gRPC create".into(),
+            expires_at: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn create_rpc_persists_transaction() {
+        let svc = grpc();
+        let resp = svc
+            .create(Request::new(create_req("USD")))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(resp.created);
+        let txn = resp.transaction.unwrap();
+        assert_eq!(txn.status, "PENDING");
+        assert_eq!(txn.currency, "USD");
+
+        let fetched = svc
+            .get(Request::new(pb::GetTransactionRequest { id: txn.id.clone() }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(fetched.id, txn.id);
+    }
+
+    fn claims(scope: &str) -> Claims {
+        Claims {
+            sub: "acme".into(),
+            exp: usize::MAX,
+            scope: scope.into(),
+        }
+    }
+
+    #[test]
+    fn interceptor_requires_a_valid_token_when_configured() {
+        let secret = b"grpc-secret";
+        let mut auth = Authenticate(Some(Arc::new(JwtConfig::hs256(secret))));
+        let missing = auth.call(Request::new(())).unwrap_err();
+        assert_eq!(missing.code(), tonic::Code::Unauthenticated);
+
+        let token = jsonwebtoken::encode(
+            &jsonwebtoken::Header::default(),
+            &Claims {
+                exp: 4_102_444_800,
+                ..claims(READ_SCOPE)
+            },
+            &jsonwebtoken::EncodingKey::from_secret(secret),
+        )
+        .unwrap();
+        let mut request = Request::new(());
+        let bearer = format!("Bearer {}", token).parse().unwrap();
+        request.metadata_mut().insert("authorization", bearer);
+        let passed = auth.call(request).unwrap();
+        assert_eq!(passed.extensions().get::<Claims>().unwrap().sub, "acme");
+
+        assert!(Authenticate(None).call(Request::new(())).is_ok());
+    }
+
+    #[tokio::test]
+    async fn scopes_are_enforced_and_subject_scopes_keys() {
+        let svc = grpc();
+        let mut read_only = Request::new(create_req("USD"));
+        read_only.extensions_mut().insert(claims(READ_SCOPE));
+        let denied = svc.create(read_only).await.unwrap_err();
+        assert_eq!(denied.code(), tonic::Code::PermissionDenied);
+
+        let mut writer = Request::new(create_req("USD"));
+        writer.extensions_mut().insert(claims(WRITE_SCOPE));
+        let created = svc.create(writer).await.unwrap().into_inner().transaction.unwrap();
+        let stored = svc.service.get(created.id.parse().unwrap()).await.unwrap();
+        assert_eq!(stored.tenant_id.as_deref(), Some("acme"));
+    }
+
+    #[tokio::test]
+    async fn list_pages_with_a_cursor() {
+        let svc = grpc();
+        for key in ["page-1", "page-2", "page-3"] {
+            let req = pb::CreateTransactionRequest {
+                idempotency_key: key.into(),
+                ..create_req("USD")
+            };
+            svc.create(Request::new(req)).await.unwrap();
+        }
+
+        let list = |cursor: Option<String>| {
+            svc.list(Request::new(pb::ListTransactionsRequest {
+                limit: Some(2),
+                cursor,
+                ..Default::default()
+            }))
+        };
+        let first = list(None).await.unwrap().into_inner();
+        assert_eq!(first.transactions.len(), 2);
+        let second = list(first.next_cursor).await.unwrap().into_inner();
+        assert_eq!(second.transactions.len(), 1);
+        assert!(second.next_cursor.is_none());
+    }
+
+    #[tokio::test]
+    async fn invalid_currency_maps_to_invalid_argument() {
+        let status = grpc()
+            .create(Request::new(create_req("XYZ")))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+    }
+}
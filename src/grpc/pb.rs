@@ -0,0 +1,85 @@
+//! Protobuf messages for `proto/transactions.proto`. Keep field tags in sync with the `.proto`.
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Transaction {
+    #[prost(string, tag = "1")]
+    pub id: String,
+    #[prost(string, tag = "2")]
+    pub idempotency_key: String,
+    #[prost(double, tag = "3")]
+    pub amount: f64,
+    #[prost(string, tag = "4")]
+    pub currency: String,
+    #[prost(string, tag = "5")]
+    pub description: String,
+    #[prost(string, tag = "6")]
+    pub status: String,
+    #[prost(string, optional, tag = "7")]
+    pub expires_at: Option<String>,
+    #[prost(uint64, tag = "8")]
+    pub version: u64,
+    #[prost(string, tag = "9")]
+    pub created_at: String,
+    #[prost(string, tag = "10")]
+    pub updated_at: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct CreateTransactionRequest {
+    #[prost(string, tag = "1")]
+    pub idempotency_key: String,
+    #[prost(double, tag = "2")]
+    pub amount: f64,
+    #[prost(string, tag = "3")]
+    pub currency: String,
+    #[prost(string, tag = "4")]
+    pub description: String,
+    #[prost(string, optional, tag = "5")]
+    pub expires_at: Option<String>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct CreateTransactionResponse {
+    #[prost(message, optional, tag = "1")]
+    pub transaction: Option<Transaction>,
+    #[prost(bool, tag = "2")]
+    pub created: bool,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct GetTransactionRequest {
+    #[prost(string, tag = "1")]
+    pub id: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ListTransactionsRequest {
+    #[prost(string, optional, tag = "1")]
+    pub status: Option<String>,
+    #[prost(string, optional, tag = "2")]
+    pub currency: Option<String>,
+    #[prost(uint32, optional, tag = "3")]
+    pub limit: Option<u32>,
+    #[prost(string, optional, tag = "4")]
+    pub cursor: Option<String>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ListTransactionsResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub transactions: Vec<Transaction>,
+    #[prost(string, optional, tag = "2")]
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct UpdateStatusRequest {
+    #[prost(string, tag = "1")]
+    pub id: String,
+    #[prost(string, tag = "2")]
+    pub status: String,
+    #[prost(uint64, optional, tag = "3")]
+    pub expected_version: Option<u64>,
+    #[prost(string, optional, tag = "4")]
+    pub reason: Option<String>,
+}
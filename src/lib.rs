@@ -1,4 +1,10 @@
 pub mod api;
+#[cfg(feature = "client")]
+pub mod client;
 pub mod domain;
 pub mod error;
+pub mod grpc;
 pub mod storage;
+pub mod telemetry;
+#[cfg(test)]
+mod test_support;
@@ -1,20 +1,224 @@
-use async_backend_skeleton::api::build_router;
-use async_backend_skeleton::domain::service::TransactionService;
+use async_backend_skeleton::api::access_log::LatencyBuckets;
+use async_backend_skeleton::api::jwt::JwtConfig;
+use async_backend_skeleton::api::{build_router_with_config, ApiConfig};
+use async_backend_skeleton::domain::expiry::spawn_expiry_task;
+use async_backend_skeleton::domain::fees::FeePolicy;
+use async_backend_skeleton::domain::models::{Currency, CurrencyPolicy, RoundingMode};
+use async_backend_skeleton::domain::rates::StaticRates;
+use async_backend_skeleton::domain::scheduler::spawn_scheduler_task;
+use async_backend_skeleton::domain::service::{
+    PrefixConcurrencyLimit, TransactionService, MAX_LIST_RESULTS, MAX_PAGE_SIZE,
+};
+use async_backend_skeleton::domain::validation::{full_match, ValidationConfig};
+use async_backend_skeleton::grpc::GrpcService;
+use async_backend_skeleton::storage::deadline::DeadlineStorage;
 use async_backend_skeleton::storage::memory::InMemoryStorage;
+use async_backend_skeleton::storage::redis_idem::{RedisCache, RedisIdempotency};
+use async_backend_skeleton::storage::Storage;
+use async_backend_skeleton::telemetry;
 use tokio::net::TcpListener;
 use tower_http::trace::TraceLayer;
-use tracing_subscriber::EnvFilter;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Reads `name` from the environment, falling back to `default` when unset or unparsable.
+fn env_or<T: FromStr>(name: &str, default: T) -> T {
+    match std::env::var(name) {
+        Ok(raw) => raw.parse().unwrap_or_else(|_| {
+            tracing::warn!("Ignoring invalid value {:?} for {}", raw, name);
+            default
+        }),
+        Err(_) => default,
+    }
+}
 
 #[tokio::main]
 async fn main() {
-    tracing_subscriber::fmt()
-        .with_env_filter(EnvFilter::try_from_default_env().unwrap_or_else(|_| "info".into()))
-        .json()
-        .init();
+    let _telemetry = telemetry::init();
+
+    let defaults = ValidationConfig::default();
+    let validation = ValidationConfig {
+        max_description_length: env_or("MAX_DESCRIPTION_LENGTH", defaults.max_description_length),
+        max_idempotency_key_length: env_or(
+            "MAX_IDEMPOTENCY_KEY_LENGTH",
+            defaults.max_idempotency_key_length,
+        ),
+        max_amount: env_or("MAX_AMOUNT", defaults.max_amount),
+        idempotency_key_pattern: std::env::var("IDEMPOTENCY_KEY_PATTERN").ok().map(|raw| {
+            full_match(&raw).unwrap_or_else(|e| {
+                tracing::error!("Invalid IDEMPOTENCY_KEY_PATTERN {:?}: {}", raw, e);
+                std::process::exit(1);
+            })
+        }),
+        ..defaults
+    };
 
-    let storage = InMemoryStorage::new();
-    let service = TransactionService::new(storage);
-    let app = build_router(service).layer(TraceLayer::new_for_http());
+    let storage = match std::env::var("STORAGE_CAPACITY").ok().and_then(|v| v.parse().ok()) {
+        Some(capacity) => InMemoryStorage::with_capacity(capacity),
+        None => InMemoryStorage::new(),
+    };
+    let storage = match std::env::var("WAL_PATH") {
+        Ok(path) => storage.with_wal(&path).unwrap_or_else(|e| {
+            tracing::error!("Failed to restore from WAL: {}", e);
+            std::process::exit(1);
+        }),
+        Err(_) => storage,
+    };
+    let storage = DeadlineStorage::new(storage);
+    match std::env::var("REDIS_URL") {
+        Ok(url) => {
+            let ttl = std::time::Duration::from_secs(env_or("IDEMPOTENCY_TTL_SECS", 24 * 60 * 60));
+            let cache = RedisCache::connect(&url, ttl).await.unwrap_or_else(|e| {
+                tracing::error!("Failed to connect to Redis: {}", e);
+                std::process::exit(1);
+            });
+            tracing::info!("Sharing idempotency keys through Redis");
+            run(RedisIdempotency::new(storage, cache), validation).await
+        }
+        Err(_) => run(storage, validation).await,
+    }
+}
+
+/// Configures the service over `storage` and serves HTTP and gRPC until either server exits.
+async fn run<S: Storage + Clone>(storage: S, validation: ValidationConfig) {
+    if let Err(e) = storage.migrate().await {
+        tracing::error!("Storage migration failed: {}", e);
+        std::process::exit(1);
+    }
+    let mut service = TransactionService::new(storage).with_validation(validation);
+    if let Ok(raw) = std::env::var("ALLOWED_CURRENCIES") {
+        let currencies = raw
+            .split(',')
+            .map(|c| c.trim().parse::<Currency>())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap_or_else(|e| {
+                tracing::error!("Invalid ALLOWED_CURRENCIES {:?}: {}", raw, e);
+                std::process::exit(1);
+            });
+        service = service.with_currency_policy(CurrencyPolicy::only(currencies));
+    }
+    if let Ok(raw) = std::env::var("DEFAULT_CURRENCY") {
+        let currency = raw.trim().parse::<Currency>().unwrap_or_else(|e| {
+            tracing::error!("Invalid DEFAULT_CURRENCY {:?}: {}", raw, e);
+            std::process::exit(1);
+        });
+        service = service.with_default_currency(currency);
+    }
+    if let Ok(raw) = std::env::var("EXCHANGE_RATES") {
+        let rates = raw.parse::<StaticRates>().unwrap_or_else(|e| {
+            tracing::error!("Invalid EXCHANGE_RATES {:?}: {}", raw, e);
+            std::process::exit(1);
+        });
+        service = service.with_exchange_rates(Arc::new(rates));
+    }
+    if let Ok(raw) = std::env::var("FEE_POLICY") {
+        let policy = raw.parse::<FeePolicy>().unwrap_or_else(|e| {
+            tracing::error!("Invalid FEE_POLICY {:?}: {}", raw, e);
+            std::process::exit(1);
+        });
+        service = service.with_fee_policy(policy);
+    }
+    if let Ok(raw) = std::env::var("RATE_SNAPSHOT_CURRENCY") {
+        let currency = raw.trim().parse::<Currency>().unwrap_or_else(|e| {
+            tracing::error!("Invalid RATE_SNAPSHOT_CURRENCY {:?}: {}", raw, e);
+            std::process::exit(1);
+        });
+        service = service.with_rate_snapshot(currency);
+    }
+    if let Some(max) = std::env::var("MAX_TRANSACTIONS_PER_SCOPE")
+        .ok()
+        .and_then(|v| v.parse().ok())
+    {
+        service = service.with_max_transactions_per_scope(max);
+    }
+    if let Ok(raw) = std::env::var("ROUNDING_MODE") {
+        let mode = raw.trim().parse::<RoundingMode>().unwrap_or_else(|e| {
+            tracing::error!("Invalid ROUNDING_MODE {:?}: {}", raw, e);
+            std::process::exit(1);
+        });
+        service = service.with_rounding_mode(mode);
+    }
+    service = service.with_max_list_results(env_or("MAX_LIST_RESULTS", MAX_LIST_RESULTS));
+    service = service.with_required_idempotency_key(env_or("REQUIRE_IDEMPOTENCY_KEY", false));
+    if let Some(secs) = std::env::var("IDEMPOTENCY_TTL_SECS").ok().and_then(|v| v.parse().ok()) {
+        service = service.with_idempotency_ttl(chrono::TimeDelta::seconds(secs));
+    }
+    if let Some(max) = std::env::var("PREFIX_CONCURRENCY_LIMIT").ok().and_then(|v| v.parse().ok()) {
+        if max == 0 {
+            tracing::error!("PREFIX_CONCURRENCY_LIMIT must be at least 1");
+            std::process::exit(1);
+        }
+        service = service.with_prefix_concurrency_limit(PrefixConcurrencyLimit {
+            delimiter: env_or("IDEMPOTENCY_PREFIX_DELIMITER", ':'),
+            max_concurrent: max,
+        });
+    }
+    let default_list_limit = env_or("DEFAULT_LIST_LIMIT", ApiConfig::default().default_list_limit);
+    if !(1..=MAX_PAGE_SIZE).contains(&default_list_limit) {
+        tracing::error!("DEFAULT_LIST_LIMIT must be between 1 and {}", MAX_PAGE_SIZE);
+        std::process::exit(1);
+    }
+    let api_config = ApiConfig {
+        jwt: std::env::var("JWT_SECRET")
+            .ok()
+            .map(|secret| JwtConfig::hs256(secret.as_bytes())),
+        latency_buckets: std::env::var("ACCESS_LOG_BUCKETS_MS")
+            .ok()
+            .and_then(|raw| {
+                raw.split(',')
+                    .map(|ms| ms.trim().parse().ok())
+                    .collect::<Option<Vec<u64>>>()
+            })
+            .map(|bounds| LatencyBuckets::from_millis(&bounds))
+            .unwrap_or_default(),
+        max_body_bytes: env_or("MAX_BODY_BYTES", ApiConfig::default().max_body_bytes),
+        compression: env_or("COMPRESSION", true),
+        compression_min_bytes: env_or(
+            "COMPRESSION_MIN_BYTES",
+            ApiConfig::default().compression_min_bytes,
+        ),
+        max_in_flight: std::env::var("MAX_IN_FLIGHT").ok().and_then(|v| v.parse().ok()),
+        log_bodies: env_or("LOG_BODIES", false),
+        base_path: std::env::var("BASE_PATH").ok(),
+        public_base_url: std::env::var("PUBLIC_BASE_URL").ok(),
+        amounts_as_strings: env_or("AMOUNTS_AS_STRINGS", false),
+        whole_second_timestamps: env_or("WHOLE_SECOND_TIMESTAMPS", false),
+        default_list_limit,
+        health_check_timeout: std::time::Duration::from_millis(env_or(
+            "HEALTH_CHECK_TIMEOUT_MS",
+            ApiConfig::default().health_check_timeout.as_millis() as u64,
+        )),
+        idempotency_response_ttl: std::env::var("IDEMPOTENCY_RESPONSE_TTL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .map(std::time::Duration::from_secs),
+        request_timeout: std::env::var("REQUEST_TIMEOUT_MS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .map(std::time::Duration::from_millis),
+        redact_internal_errors: std::env::var("ERROR_DETAIL").is_ok_and(|v| v == "redacted"),
+        ..ApiConfig::default()
+    };
+    if api_config.jwt.is_none() {
+        tracing::warn!("JWT_SECRET is not set; transaction routes are unauthenticated");
+    }
+    if let Some(secs) = std::env::var("PENDING_MAX_AGE_SECS").ok().and_then(|v| v.parse().ok()) {
+        let interval = env_or("EXPIRY_SCAN_INTERVAL_SECS", 60);
+        if interval == 0 {
+            tracing::error!("EXPIRY_SCAN_INTERVAL_SECS must be at least 1");
+            std::process::exit(1);
+        }
+        let interval = std::time::Duration::from_secs(interval);
+        spawn_expiry_task(service.clone(), chrono::TimeDelta::seconds(secs), interval);
+    }
+    let schedule_interval = env_or("SCHEDULE_SCAN_INTERVAL_SECS", 5);
+    if schedule_interval == 0 {
+        tracing::error!("SCHEDULE_SCAN_INTERVAL_SECS must be at least 1");
+        std::process::exit(1);
+    }
+    spawn_scheduler_task(service.clone(), std::time::Duration::from_secs(schedule_interval));
+    let grpc = GrpcService::new(service.clone()).into_server(api_config.jwt.clone());
+    let app = build_router_with_config(service, api_config).layer(TraceLayer::new_for_http());
 
     let port = std::env::var("PORT").unwrap_or_else(|_| "8080".into());
     let addr = format!("0.0.0.0:{}", port);
@@ -30,5 +234,18 @@ async fn main() {
         }
     };
     tracing::info!("Listening on {}", addr);
-    axum::serve(listener, app).await.expect("Server error");
+
+    let grpc_port: u16 = env_or("GRPC_PORT", 50051);
+    let grpc_addr = std::net::SocketAddr::from(([0, 0, 0, 0], grpc_port));
+    tracing::info!("gRPC listening on {}", grpc_addr);
+
+    tokio::select! {
+        result = axum::serve(listener, app) => result.expect("Server error"),
+        result = tonic::transport::Server::builder().add_service(grpc).serve(grpc_addr) => {
+            if let Err(e) = result {
+                tracing::error!("gRPC server failed on {}: {}", grpc_addr, e);
+                std::process::exit(1);
+            }
+        }
+    }
 }
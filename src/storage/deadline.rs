@@ -0,0 +1,417 @@
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::time::Duration;
+
+use tokio::time::Instant;
+use uuid::Uuid;
+
+use crate::domain::models::{Currency, ExchangeRate, Note, Transaction, TransactionStatus};
+use crate::error::AppError;
+use crate::storage::{DateRange, ListQuery, ListResult, Storage};
+
+tokio::task_local! {
+    static CURRENT: Deadline;
+}
+
+/// Point in time by which the current request must be answered. Set for the duration of a
+/// request with `scope` and read back by `DeadlineStorage`, so handlers and the service never
+/// pass it around explicitly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Deadline(Instant);
+
+impl Deadline {
+    pub fn after(budget: Duration) -> Self {
+        Self(Instant::now() + budget)
+    }
+
+    /// The deadline of the request being handled on this task, if one was set.
+    pub fn current() -> Option<Self> {
+        CURRENT.try_with(|deadline| *deadline).ok()
+    }
+
+    /// Runs `fut` with this as the current deadline.
+    pub async fn scope<F: Future>(self, fut: F) -> F::Output {
+        CURRENT.scope(self, fut).await
+    }
+}
+
+/// Decorator that aborts storage calls still running at the current request's deadline with
+/// `AppError::DeadlineExceeded`. Calls made outside a request, e.g. by background tasks, run
+/// unbounded.
+#[derive(Debug, Clone)]
+pub struct DeadlineStorage<S: Storage> {
+    inner: S,
+}
+
+impl<S: Storage> DeadlineStorage<S> {
+    pub fn new(inner: S) -> Self {
+        Self { inner }
+    }
+}
+
+async fn bounded<T>(
+    op: &str,
+    fut: impl Future<Output = Result<T, AppError>>,
+) -> Result<T, AppError> {
+    let Some(Deadline(deadline)) = Deadline::current() else {
+        return fut.await;
+    };
+    tokio::time::timeout_at(deadline, fut).await.unwrap_or_else(|_| {
+        Err(AppError::DeadlineExceeded(format!(
+            "storage {} did not finish before the request deadline",
+            op
+        )))
+    })
+}
+
+impl<S: Storage> Storage for DeadlineStorage<S> {
+    async fn migrate(&self) -> Result<(), AppError> {
+        self.inner.migrate().await
+    }
+
+    async fn health_check(&self) -> Result<(), AppError> {
+        self.inner.health_check().await
+    }
+
+    async fn insert(&self, txn: Transaction) -> Result<(), AppError> {
+        bounded("insert", self.inner.insert(txn)).await
+    }
+
+    async fn insert_capped(
+        &self,
+        txn: Transaction,
+        max_per_scope: usize,
+    ) -> Result<bool, AppError> {
+        bounded("insert_capped", self.inner.insert_capped(txn, max_per_scope)).await
+    }
+
+    async fn get(&self, id: Uuid) -> Result<Option<Transaction>, AppError> {
+        bounded("get", self.inner.get(id)).await
+    }
+
+    async fn get_many(&self, ids: &[Uuid]) -> Result<Vec<Transaction>, AppError> {
+        bounded("get_many", self.inner.get_many(ids)).await
+    }
+
+    async fn find_by_idempotency_key(
+        &self,
+        tenant: Option<&str>,
+        key: &str,
+    ) -> Result<Option<Transaction>, AppError> {
+        bounded("find_by_idempotency_key", self.inner.find_by_idempotency_key(tenant, key)).await
+    }
+
+    async fn count_by_scope(&self, tenant: Option<&str>) -> Result<usize, AppError> {
+        bounded("count_by_scope", self.inner.count_by_scope(tenant)).await
+    }
+
+    async fn list(&self, query: &ListQuery) -> Result<ListResult, AppError> {
+        bounded("list", self.inner.list(query)).await
+    }
+
+    async fn recent(&self, n: usize) -> Result<Vec<Transaction>, AppError> {
+        bounded("recent", self.inner.recent(n)).await
+    }
+
+    async fn sum_by_status(
+        &self,
+        status: TransactionStatus,
+        range: DateRange,
+    ) -> Result<BTreeMap<Currency, i64>, AppError> {
+        bounded("sum_by_status", self.inner.sum_by_status(status, range)).await
+    }
+
+    async fn update_status(
+        &self,
+        id: Uuid,
+        status: TransactionStatus,
+        expected_version: Option<u64>,
+        reason: Option<String>,
+    ) -> Result<Transaction, AppError> {
+        let update = self.inner.update_status(id, status, expected_version, reason);
+        bounded("update_status", update).await
+    }
+
+    async fn update_description(
+        &self,
+        id: Uuid,
+        description: String,
+    ) -> Result<Transaction, AppError> {
+        bounded("update_description", self.inner.update_description(id, description)).await
+    }
+
+    async fn update_amount(
+        &self,
+        id: Uuid,
+        amount_minor: i64,
+        fee_minor: Option<i64>,
+    ) -> Result<Transaction, AppError> {
+        let update = self.inner.update_amount(id, amount_minor, fee_minor);
+        bounded("update_amount", update).await
+    }
+
+    async fn void(&self, id: Uuid, reason: Option<String>) -> Result<Transaction, AppError> {
+        bounded("void", self.inner.void(id, reason)).await
+    }
+
+    async fn soft_delete(&self, id: Uuid) -> Result<Transaction, AppError> {
+        bounded("soft_delete", self.inner.soft_delete(id)).await
+    }
+
+    async fn restore(&self, id: Uuid) -> Result<Transaction, AppError> {
+        bounded("restore", self.inner.restore(id)).await
+    }
+
+    async fn add_note(&self, note: Note) -> Result<Note, AppError> {
+        bounded("add_note", self.inner.add_note(note)).await
+    }
+
+    async fn list_notes(&self, id: Uuid) -> Result<Vec<Note>, AppError> {
+        bounded("list_notes", self.inner.list_notes(id)).await
+    }
+
+    async fn upsert_rate(&self, rate: ExchangeRate) -> Result<ExchangeRate, AppError> {
+        bounded("upsert_rate", self.inner.upsert_rate(rate)).await
+    }
+
+    async fn get_rate(
+        &self,
+        base: Currency,
+        quote: Currency,
+    ) -> Result<Option<ExchangeRate>, AppError> {
+        bounded("get_rate", self.inner.get_rate(base, quote)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::{build_router_with_config, ApiConfig};
+    use crate::domain::service::TransactionService;
+    use crate::storage::memory::InMemoryStorage;
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+
+    /// Delays `list`, `insert` and `health_check` by `delay`; everything else goes straight
+    /// through.
+    #[derive(Clone)]
+    struct SleepyStorage {
+        inner: InMemoryStorage,
+        delay: Duration,
+    }
+
+    impl Storage for SleepyStorage {
+        async fn health_check(&self) -> Result<(), AppError> {
+            tokio::time::sleep(self.delay).await;
+            Ok(())
+        }
+
+        async fn insert(&self, txn: Transaction) -> Result<(), AppError> {
+            tokio::time::sleep(self.delay).await;
+            self.inner.insert(txn).await
+        }
+
+        async fn insert_capped(
+            &self,
+            txn: Transaction,
+            max_per_scope: usize,
+        ) -> Result<bool, AppError> {
+            self.inner.insert_capped(txn, max_per_scope).await
+        }
+
+        async fn get(&self, id: Uuid) -> Result<Option<Transaction>, AppError> {
+            self.inner.get(id).await
+        }
+
+        async fn get_many(&self, ids: &[Uuid]) -> Result<Vec<Transaction>, AppError> {
+            self.inner.get_many(ids).await
+        }
+
+        async fn find_by_idempotency_key(
+            &self,
+            tenant: Option<&str>,
+            key: &str,
+        ) -> Result<Option<Transaction>, AppError> {
+            self.inner.find_by_idempotency_key(tenant, key).await
+        }
+
+        async fn count_by_scope(&self, tenant: Option<&str>) -> Result<usize, AppError> {
+            self.inner.count_by_scope(tenant).await
+        }
+
+        async fn list(&self, query: &ListQuery) -> Result<ListResult, AppError> {
+            tokio::time::sleep(self.delay).await;
+            self.inner.list(query).await
+        }
+
+        async fn recent(&self, n: usize) -> Result<Vec<Transaction>, AppError> {
+            self.inner.recent(n).await
+        }
+
+        async fn sum_by_status(
+            &self,
+            status: TransactionStatus,
+            range: DateRange,
+        ) -> Result<BTreeMap<Currency, i64>, AppError> {
+            self.inner.sum_by_status(status, range).await
+        }
+
+        async fn update_status(
+            &self,
+            id: Uuid,
+            status: TransactionStatus,
+            expected_version: Option<u64>,
+            reason: Option<String>,
+        ) -> Result<Transaction, AppError> {
+            self.inner.update_status(id, status, expected_version, reason).await
+        }
+
+        async fn update_description(
+            &self,
+            id: Uuid,
+            description: String,
+        ) -> Result<Transaction, AppError> {
+            self.inner.update_description(id, description).await
+        }
+
+        async fn update_amount(
+            &self,
+            id: Uuid,
+            amount_minor: i64,
+            fee_minor: Option<i64>,
+        ) -> Result<Transaction, AppError> {
+            self.inner.update_amount(id, amount_minor, fee_minor).await
+        }
+
+        async fn void(&self, id: Uuid, reason: Option<String>) -> Result<Transaction, AppError> {
+            self.inner.void(id, reason).await
+        }
+
+        async fn soft_delete(&self, id: Uuid) -> Result<Transaction, AppError> {
+            self.inner.soft_delete(id).await
+        }
+
+        async fn restore(&self, id: Uuid) -> Result<Transaction, AppError> {
+            self.inner.restore(id).await
+        }
+
+        async fn add_note(&self, note: Note) -> Result<Note, AppError> {
+            self.inner.add_note(note).await
+        }
+
+        async fn list_notes(&self, id: Uuid) -> Result<Vec<Note>, AppError> {
+            self.inner.list_notes(id).await
+        }
+
+        async fn upsert_rate(&self, rate: ExchangeRate) -> Result<ExchangeRate, AppError> {
+            self.inner.upsert_rate(rate).await
+        }
+
+        async fn get_rate(
+            &self,
+            base: Currency,
+            quote: Currency,
+        ) -> Result<Option<ExchangeRate>, AppError> {
+            self.inner.get_rate(base, quote).await
+        }
+    }
+
+    fn list_request() -> Request<Body> {
+        Request::builder()
+            .uri("/api/v1/transactions")
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn slow_list_past_the_deadline_returns_504() {
+        let storage = DeadlineStorage::new(SleepyStorage {
+            inner: InMemoryStorage::new(),
+            delay: Duration::from_secs(5),
+        });
+        let config = ApiConfig {
+            request_timeout: Some(Duration::from_millis(20)),
+            ..ApiConfig::default()
+        };
+        let app = build_router_with_config(TransactionService::new(storage), config);
+
+        let started = std::time::Instant::now();
+        let resp = app.oneshot(list_request()).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::GATEWAY_TIMEOUT);
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn streamed_batch_items_share_the_request_deadline() {
+        let storage = DeadlineStorage::new(SleepyStorage {
+            inner: InMemoryStorage::new(),
+            delay: Duration::from_secs(5),
+        });
+        let config = ApiConfig {
+            request_timeout: Some(Duration::from_millis(20)),
+            batch_stream_threshold: 1,
+            ..ApiConfig::default()
+        };
+        let app = build_router_with_config(TransactionService::new(storage), config);
+        let item = serde_json::json!({"amount": 1.0, "currency": "USD", "description": "slow"});
+        let request = Request::builder()
+            .method("POST")
+            .uri("/api/v1/transactions/batch")
+            .header("content-type", "application/json")
+            .body(Body::from(serde_json::json!({ "items": [item, item] }).to_string()))
+            .unwrap();
+
+        let started = std::time::Instant::now();
+        let resp = app.oneshot(request).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let bytes = resp.into_body().collect().await.unwrap().to_bytes();
+        assert!(started.elapsed() < Duration::from_secs(1));
+        let lines: Vec<serde_json::Value> = std::str::from_utf8(&bytes)
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        for line in &lines {
+            assert_eq!(line["status"], 504);
+        }
+    }
+
+    #[tokio::test]
+    async fn hung_health_check_fails_readiness_promptly() {
+        let storage = SleepyStorage {
+            inner: InMemoryStorage::new(),
+            delay: Duration::from_secs(5),
+        };
+        let config = ApiConfig {
+            health_check_timeout: Duration::from_millis(20),
+            ..ApiConfig::default()
+        };
+        let app = build_router_with_config(TransactionService::new(storage), config);
+        let request = Request::builder().uri("/health/ready").body(Body::empty()).unwrap();
+
+        let started = std::time::Instant::now();
+        let resp = app.oneshot(request).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn deadline_only_applies_within_its_scope() {
+        let storage = DeadlineStorage::new(SleepyStorage {
+            inner: InMemoryStorage::new(),
+            delay: Duration::from_millis(20),
+        });
+        assert!(storage.list(&ListQuery::default()).await.is_ok());
+
+        let within = Deadline::after(Duration::from_secs(5));
+        assert!(within.scope(storage.list(&ListQuery::default())).await.is_ok());
+        let past = Deadline::after(Duration::from_millis(1));
+        assert!(matches!(
+            past.scope(storage.list(&ListQuery::default())).await,
+            Err(AppError::DeadlineExceeded(_))
+        ));
+    }
+}
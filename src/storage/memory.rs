@@ -1,68 +1,483 @@
-use crate::domain::models::{Currency, Transaction, TransactionStatus};
+use crate::domain::clock::{Clock, SystemClock};
+use crate::domain::models::{
+    timestamp_precision, CreateFingerprint, Currency, ExchangeRate, Facet, Facets, Note,
+    Transaction, TransactionStatus,
+};
 use crate::error::AppError;
-use crate::storage::Storage;
-use chrono::Utc;
-use std::collections::HashMap;
-use std::sync::Arc;
+use crate::storage::{DateRange, ListCursor, ListQuery, ListResult, Storage};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs::OpenOptions;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tokio::fs::File;
+use tokio::io::AsyncWriteExt;
 use tokio::sync::RwLock;
 use uuid::Uuid;
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone)]
 pub struct InMemoryStorage {
-    data: Arc<RwLock<HashMap<Uuid, Transaction>>>,
+    data: Arc<RwLock<Records>>,
+    /// Access order for capacity-bounded stores; `None` means unbounded.
+    lru: Option<Arc<Mutex<AccessOrder>>>,
+    /// Stamps `updated_at` and `deleted_at`.
+    clock: Arc<dyn Clock>,
+    /// Append-only JSON-lines log of every written transaction state; see `with_wal`.
+    wal: Option<Arc<tokio::sync::Mutex<File>>>,
+}
+
+impl Default for InMemoryStorage {
+    fn default() -> Self {
+        Self {
+            data: Arc::default(),
+            lru: None,
+            clock: Arc::new(SystemClock),
+            wal: None,
+        }
+    }
+}
+
+/// An idempotency key together with the tenant it is scoped to.
+type ScopedKey = (Option<String>, String);
+
+fn scoped_key(txn: &Transaction) -> Option<ScopedKey> {
+    let key = txn.idempotency_key.clone()?;
+    Some((txn.tenant_id.clone(), key))
+}
+
+/// Transactions by id, an index from scoped idempotency key to the newest live transaction with
+/// that key, and the list order. All sit behind the same lock, so they never disagree.
+#[derive(Debug, Default)]
+struct Records {
+    by_id: HashMap<Uuid, Transaction>,
+    by_key: HashMap<ScopedKey, Uuid>,
+    /// Live transactions per tenant, kept up to date by `insert`, `remove` and `set_deleted`.
+    live_by_scope: HashMap<Option<String>, usize>,
+    /// Every stored transaction in `(created_at, id)` order. `created_at` never changes after
+    /// insert, so only inserts and removals touch this.
+    order: BTreeSet<ListCursor>,
+    /// Notes per transaction in insertion order. Dropped along with an evicted transaction.
+    notes: HashMap<Uuid, Vec<Note>>,
+    rates: HashMap<(Currency, Currency), ExchangeRate>,
+}
+
+impl Records {
+    fn insert(&mut self, txn: Transaction) {
+        if let Some(key) = scoped_key(&txn) {
+            let newest = self
+                .by_key
+                .get(&key)
+                .and_then(|id| self.by_id.get(id))
+                .is_none_or(|current| {
+                    current.id == txn.id || current.created_at <= txn.created_at
+                });
+            if newest && txn.deleted_at.is_none() {
+                self.by_key.insert(key, txn.id);
+            }
+        }
+        if let Some(previous) = self.by_id.remove(&txn.id) {
+            self.order.remove(&ListCursor::after(&previous));
+            self.count_live(&previous, false);
+        }
+        self.order.insert(ListCursor::after(&txn));
+        self.count_live(&txn, true);
+        self.by_id.insert(txn.id, txn);
+    }
+
+    /// Replaces a stored transaction with its soft-deleted state.
+    fn set_deleted(&mut self, deleted: Transaction) {
+        if let Some(previous) = self.by_id.insert(deleted.id, deleted.clone()) {
+            self.count_live(&previous, false);
+        }
+        self.unindex(&deleted);
+    }
+
+    fn count_live(&mut self, txn: &Transaction, added: bool) {
+        if txn.deleted_at.is_some() {
+            return;
+        }
+        let count = self.live_by_scope.entry(txn.tenant_id.clone()).or_default();
+        if added {
+            *count += 1;
+        } else {
+            *count = count.saturating_sub(1);
+            if *count == 0 {
+                self.live_by_scope.remove(&txn.tenant_id);
+            }
+        }
+    }
+
+    fn live_in_scope(&self, tenant: Option<&str>) -> usize {
+        self.live_by_scope
+            .get(&tenant.map(str::to_string))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    fn remove(&mut self, id: Uuid) {
+        if let Some(txn) = self.by_id.remove(&id) {
+            self.count_live(&txn, false);
+            self.order.remove(&ListCursor::after(&txn));
+            self.notes.remove(&id);
+            self.unindex(&txn);
+        }
+    }
+
+    /// Drops `txn` from the key index, falling back to the next newest live transaction with
+    /// the same key. That scan only happens when an indexed transaction goes away.
+    fn unindex(&mut self, txn: &Transaction) {
+        let Some(key) = scoped_key(txn) else {
+            return;
+        };
+        if self.by_key.get(&key) != Some(&txn.id) {
+            return;
+        }
+        let previous = self
+            .by_id
+            .values()
+            .filter(|t| {
+                t.id != txn.id && t.deleted_at.is_none() && scoped_key(t).as_ref() == Some(&key)
+            })
+            .max_by_key(|t| t.created_at)
+            .map(|t| t.id);
+        match previous {
+            Some(id) => self.by_key.insert(key, id),
+            None => self.by_key.remove(&key),
+        };
+    }
+}
+
+/// One line of the write-ahead log: the transaction as the API renders it, plus the create
+/// fingerprint that representation leaves out.
+#[derive(Serialize)]
+struct WalLine<'a> {
+    #[serde(flatten)]
+    txn: &'a Transaction,
+    created_with: Option<&'a CreateFingerprint>,
+}
+
+#[derive(Deserialize)]
+struct WalEntry {
+    #[serde(flatten)]
+    txn: Transaction,
+    /// Absent from lines written before fingerprints were logged.
+    #[serde(default)]
+    created_with: Option<CreateFingerprint>,
+}
+
+/// Least-recently-accessed ordering over stored ids. Each access gets a fresh tick, so the
+/// smallest tick in `by_tick` is the eviction candidate.
+#[derive(Debug)]
+struct AccessOrder {
+    capacity: usize,
+    next_tick: u64,
+    by_tick: BTreeMap<u64, Uuid>,
+    ticks: HashMap<Uuid, u64>,
+}
+
+impl AccessOrder {
+    fn touch(&mut self, id: Uuid) {
+        if let Some(old) = self.ticks.insert(id, self.next_tick) {
+            self.by_tick.remove(&old);
+        }
+        self.by_tick.insert(self.next_tick, id);
+        self.next_tick += 1;
+    }
+
+    fn pop_least_recent(&mut self) -> Option<Uuid> {
+        let (_, id) = self.by_tick.pop_first()?;
+        self.ticks.remove(&id);
+        Some(id)
+    }
 }
 
 impl InMemoryStorage {
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// Store holding at most `capacity` transactions. Inserting beyond that evicts the
+    /// least-recently-accessed one, which is then reported as not found.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            lru: Some(Arc::new(Mutex::new(AccessOrder {
+                capacity: capacity.max(1),
+                next_tick: 0,
+                by_tick: BTreeMap::new(),
+                ticks: HashMap::new(),
+            }))),
+            ..Self::default()
+        }
+    }
+
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Persists the store in a JSON-lines log at `path`. Transactions already in the log are
+    /// loaded first, the last line for each id winning and lines that do not parse skipped,
+    /// replacing anything this store held. From then on every change is appended and synced
+    /// to disk before it is applied, so a failed write leaves the store unchanged. Notes and
+    /// exchange rates are not logged. Configure the clock and capacity first; they are kept.
+    pub fn with_wal(mut self, path: impl AsRef<Path>) -> Result<Self, AppError> {
+        let path = path.as_ref();
+        self.data = Arc::new(RwLock::new(self.replay(path)?));
+        let file = OpenOptions::new().create(true).append(true).open(path).map_err(|e| {
+            AppError::Internal(format!("Cannot open WAL {}: {}", path.display(), e))
+        })?;
+        self.wal = Some(Arc::new(tokio::sync::Mutex::new(File::from_std(file))));
+        Ok(self)
+    }
+
+    /// Records from the log at `path`; a missing file yields none. A capacity-bounded store
+    /// keeps the most recently logged transactions.
+    fn replay(&self, path: &Path) -> Result<Records, AppError> {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => {
+                return Err(AppError::Internal(format!(
+                    "Cannot read WAL {}: {}",
+                    path.display(),
+                    e
+                )))
+            }
+        };
+        let mut records = Records::default();
+        for (index, line) in contents.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let txn = match serde_json::from_str::<WalEntry>(line) {
+                Ok(WalEntry { mut txn, created_with }) => {
+                    txn.created_with = created_with;
+                    txn
+                }
+                Err(e) => {
+                    tracing::warn!(line = index + 1, error = %e, "skipping corrupt WAL line");
+                    continue;
+                }
+            };
+            let id = txn.id;
+            records.insert(txn);
+            if let Some(lru) = &self.lru {
+                let mut order = lru.lock().unwrap_or_else(|e| e.into_inner());
+                order.touch(id);
+                while records.by_id.len() > order.capacity {
+                    match order.pop_least_recent() {
+                        Some(evicted) => records.remove(evicted),
+                        None => break,
+                    }
+                }
+            }
+        }
+        Ok(records)
+    }
+
+    /// Appends `txn` and waits for it to reach the disk. Called with the write lock held and
+    /// before the change is applied, so lines land in the order changes were made and a
+    /// change that fails to log is never visible.
+    async fn log(&self, txn: &Transaction) -> Result<(), AppError> {
+        let Some(wal) = &self.wal else {
+            return Ok(());
+        };
+        // The log is read back as the source of truth, so it keeps full precision.
+        let entry = WalLine {
+            txn,
+            created_with: txn.created_with.as_ref(),
+        };
+        let mut line = timestamp_precision::full_precision(|| serde_json::to_vec(&entry))
+            .map_err(|e| AppError::Internal(format!("Cannot encode WAL entry: {}", e)))?;
+        line.push(b'\n');
+        let failed = |e: std::io::Error| AppError::Internal(format!("WAL append failed: {}", e));
+        let mut file = wal.lock().await;
+        file.write_all(&line).await.map_err(failed)?;
+        file.flush().await.map_err(failed)?;
+        file.sync_data().await.map_err(failed)
+    }
+
+    /// Logs and stores `txn`, evicting the least recently used transactions when full.
+    async fn insert_locked(&self, store: &mut Records, txn: Transaction) -> Result<(), AppError> {
+        self.log(&txn).await?;
+        if let Some(lru) = &self.lru {
+            let mut order = lru.lock().unwrap_or_else(|e| e.into_inner());
+            while !store.by_id.contains_key(&txn.id) && store.by_id.len() >= order.capacity {
+                match order.pop_least_recent() {
+                    Some(evicted) => store.remove(evicted),
+                    None => break,
+                }
+            }
+            order.touch(txn.id);
+        }
+        store.insert(txn);
+        Ok(())
+    }
+
+    fn touch(&self, id: Uuid) {
+        if let Some(lru) = &self.lru {
+            lru.lock().unwrap_or_else(|e| e.into_inner()).touch(id);
+        }
+    }
 }
 
 impl Storage for InMemoryStorage {
     async fn insert(&self, txn: Transaction) -> Result<(), AppError> {
         let mut store = self.data.write().await;
-        store.insert(txn.id, txn);
-        Ok(())
+        self.insert_locked(&mut store, txn).await
+    }
+
+    async fn insert_capped(
+        &self,
+        txn: Transaction,
+        max_per_scope: usize,
+    ) -> Result<bool, AppError> {
+        let mut store = self.data.write().await;
+        let replacing_live = store.by_id.get(&txn.id).is_some_and(|t| t.deleted_at.is_none());
+        if !replacing_live && store.live_in_scope(txn.tenant_id.as_deref()) >= max_per_scope {
+            return Ok(false);
+        }
+        self.insert_locked(&mut store, txn).await?;
+        Ok(true)
     }
 
     async fn get(&self, id: Uuid) -> Result<Option<Transaction>, AppError> {
         let store = self.data.read().await;
-        Ok(store.get(&id).cloned())
+        let txn = store.by_id.get(&id).filter(|t| t.deleted_at.is_none()).cloned();
+        if txn.is_some() {
+            self.touch(id);
+        }
+        Ok(txn)
+    }
+
+    async fn get_many(&self, ids: &[Uuid]) -> Result<Vec<Transaction>, AppError> {
+        let store = self.data.read().await;
+        let found: Vec<Transaction> = ids
+            .iter()
+            .filter_map(|id| store.by_id.get(id))
+            .filter(|t| t.deleted_at.is_none())
+            .cloned()
+            .collect();
+        for txn in &found {
+            self.touch(txn.id);
+        }
+        Ok(found)
     }
 
     async fn find_by_idempotency_key(
         &self,
+        tenant: Option<&str>,
         key: &str,
     ) -> Result<Option<Transaction>, AppError> {
         let store = self.data.read().await;
-        Ok(store.values().find(|t| t.idempotency_key == key).cloned())
+        let txn = store
+            .by_key
+            .get(&(tenant.map(str::to_owned), key.to_owned()))
+            .and_then(|id| store.by_id.get(id))
+            .cloned();
+        if let Some(txn) = &txn {
+            self.touch(txn.id);
+        }
+        Ok(txn)
     }
 
-    async fn list(
-        &self,
-        status: Option<TransactionStatus>,
-        currency: Option<Currency>,
-    ) -> Result<Vec<Transaction>, AppError> {
+    async fn count_by_scope(&self, tenant: Option<&str>) -> Result<usize, AppError> {
+        Ok(self.data.read().await.live_in_scope(tenant))
+    }
+
+    async fn list(&self, query: &ListQuery) -> Result<ListResult, AppError> {
         let store = self.data.read().await;
-        let results = store
-            .values()
-            .filter(|t| status.is_none_or(|s| t.status == s))
-            .filter(|t| currency.is_none_or(|c| t.currency == c))
+        let mut facets = (!query.facets.is_empty()).then(|| Facets {
+            status: query.facets.contains(&Facet::Status).then(BTreeMap::new),
+            currency: query.facets.contains(&Facet::Currency).then(BTreeMap::new),
+        });
+
+        let mut items = Vec::new();
+        for txn in store.order.iter().filter_map(|c| store.by_id.get(&c.id)) {
+            if txn.deleted_at.is_some() || !query.matches(txn) {
+                continue;
+            }
+            if let Some(facets) = facets.as_mut() {
+                if let Some(counts) = facets.status.as_mut() {
+                    *counts.entry(txn.status).or_default() += 1;
+                }
+                if let Some(counts) = facets.currency.as_mut() {
+                    *counts.entry(txn.currency).or_default() += 1;
+                }
+            }
+            items.push(txn);
+        }
+
+        let total = items.len();
+        if let Some(after) = query.after {
+            items.retain(|t| ListCursor::after(t) > after);
+        }
+        items.drain(..query.offset.min(items.len()));
+        let mut next_cursor = None;
+        if let Some(limit) = query.limit {
+            if items.len() > limit {
+                items.truncate(limit);
+                next_cursor = items.last().map(|t| ListCursor::after(t));
+            }
+        }
+
+        Ok(ListResult {
+            items: items.into_iter().cloned().collect(),
+            total,
+            next_cursor,
+            facets,
+            page: None,
+        })
+    }
+
+    async fn recent(&self, n: usize) -> Result<Vec<Transaction>, AppError> {
+        let store = self.data.read().await;
+        Ok(store
+            .order
+            .iter()
+            .rev()
+            .filter_map(|c| store.by_id.get(&c.id))
+            .filter(|t| t.deleted_at.is_none())
+            .take(n)
             .cloned()
-            .collect();
-        Ok(results)
+            .collect())
+    }
+
+    async fn sum_by_status(
+        &self,
+        status: TransactionStatus,
+        range: DateRange,
+    ) -> Result<BTreeMap<Currency, i64>, AppError> {
+        let store = self.data.read().await;
+        let mut sums = BTreeMap::new();
+        for txn in store.by_id.values() {
+            if txn.deleted_at.is_none() && txn.status == status && range.contains(txn.created_at) {
+                *sums.entry(txn.currency).or_default() += txn.amount_minor;
+            }
+        }
+        Ok(sums)
     }
 
     async fn update_status(
         &self,
         id: Uuid,
         status: TransactionStatus,
+        expected_version: Option<u64>,
+        reason: Option<String>,
     ) -> Result<Transaction, AppError> {
         let mut store = self.data.write().await;
         let txn = store
+            .by_id
             .get_mut(&id)
+            .filter(|t| t.deleted_at.is_none())
             .ok_or_else(|| AppError::NotFound(id.to_string()))?;
 
+        if expected_version.is_some_and(|v| v != txn.version) {
+            return Err(AppError::VersionConflict {
+                current: Box::new(txn.clone()),
+            });
+        }
+
         if !txn.status.can_transition_to(status) {
             return Err(AppError::InvalidStateTransition {
                 from: txn.status.to_string(),
@@ -70,8 +485,407 @@ impl Storage for InMemoryStorage {
             });
         }
 
-        txn.status = status;
-        txn.updated_at = Utc::now();
-        Ok(txn.clone())
+        let mut updated = txn.clone();
+        updated.status = status;
+        match status {
+            TransactionStatus::Cancelled => updated.cancellation_reason = reason,
+            TransactionStatus::Failed => updated.failure_reason = reason,
+            _ => {}
+        }
+        updated.version += 1;
+        updated.updated_at = self.clock.now();
+        self.log(&updated).await?;
+        *txn = updated.clone();
+        self.touch(id);
+        Ok(updated)
+    }
+
+    async fn update_description(
+        &self,
+        id: Uuid,
+        description: String,
+    ) -> Result<Transaction, AppError> {
+        let mut store = self.data.write().await;
+        let txn = store
+            .by_id
+            .get_mut(&id)
+            .filter(|t| t.deleted_at.is_none())
+            .ok_or_else(|| AppError::NotFound(id.to_string()))?;
+
+        let mut updated = txn.clone();
+        updated.description = description;
+        updated.version += 1;
+        updated.updated_at = self.clock.now();
+        self.log(&updated).await?;
+        *txn = updated.clone();
+        self.touch(id);
+        Ok(updated)
+    }
+
+    async fn update_amount(
+        &self,
+        id: Uuid,
+        amount_minor: i64,
+        fee_minor: Option<i64>,
+    ) -> Result<Transaction, AppError> {
+        let mut store = self.data.write().await;
+        let txn = store
+            .by_id
+            .get_mut(&id)
+            .filter(|t| t.deleted_at.is_none())
+            .ok_or_else(|| AppError::NotFound(id.to_string()))?;
+
+        if txn.status != TransactionStatus::Pending {
+            return Err(AppError::NotPending {
+                status: txn.status.to_string(),
+            });
+        }
+
+        let mut updated = txn.clone();
+        updated.amount_minor = amount_minor;
+        updated.fee_minor = fee_minor;
+        updated.version += 1;
+        updated.updated_at = self.clock.now();
+        self.log(&updated).await?;
+        *txn = updated.clone();
+        self.touch(id);
+        Ok(updated)
+    }
+
+    async fn void(&self, id: Uuid, reason: Option<String>) -> Result<Transaction, AppError> {
+        let mut store = self.data.write().await;
+        let txn = store
+            .by_id
+            .get_mut(&id)
+            .filter(|t| t.deleted_at.is_none())
+            .ok_or_else(|| AppError::NotFound(id.to_string()))?;
+
+        if !txn.status.can_transition_to(TransactionStatus::Cancelled) {
+            return Err(AppError::InvalidStateTransition {
+                from: txn.status.to_string(),
+                to: TransactionStatus::Cancelled.to_string(),
+            });
+        }
+
+        let mut updated = txn.clone();
+        updated.status = TransactionStatus::Cancelled;
+        updated.cancellation_reason = reason;
+        updated.version += 1;
+        updated.updated_at = self.clock.now();
+        self.log(&updated).await?;
+        *txn = updated.clone();
+        self.touch(id);
+        Ok(updated)
+    }
+
+    async fn soft_delete(&self, id: Uuid) -> Result<Transaction, AppError> {
+        let mut store = self.data.write().await;
+        let txn = store
+            .by_id
+            .get_mut(&id)
+            .filter(|t| t.deleted_at.is_none())
+            .ok_or_else(|| AppError::NotFound(id.to_string()))?;
+
+        let now = self.clock.now();
+        let mut deleted = txn.clone();
+        deleted.deleted_at = Some(now);
+        deleted.version += 1;
+        deleted.updated_at = now;
+        self.log(&deleted).await?;
+        store.set_deleted(deleted.clone());
+        Ok(deleted)
+    }
+
+    async fn restore(&self, id: Uuid) -> Result<Transaction, AppError> {
+        let mut store = self.data.write().await;
+        let mut txn = store
+            .by_id
+            .get(&id)
+            .cloned()
+            .ok_or_else(|| AppError::NotFound(id.to_string()))?;
+        if txn.deleted_at.is_none() {
+            return Err(AppError::NotDeleted(id.to_string()));
+        }
+
+        txn.deleted_at = None;
+        txn.version += 1;
+        txn.updated_at = self.clock.now();
+        self.log(&txn).await?;
+        // Re-inserting puts the key back in the index if this is still its newest transaction.
+        store.insert(txn.clone());
+        self.touch(id);
+        Ok(txn)
+    }
+
+    async fn add_note(&self, note: Note) -> Result<Note, AppError> {
+        let mut store = self.data.write().await;
+        let id = note.transaction_id;
+        if store.by_id.get(&id).is_none_or(|t| t.deleted_at.is_some()) {
+            return Err(AppError::NotFound(id.to_string()));
+        }
+        store.notes.entry(id).or_default().push(note.clone());
+        self.touch(id);
+        Ok(note)
+    }
+
+    async fn list_notes(&self, id: Uuid) -> Result<Vec<Note>, AppError> {
+        let store = self.data.read().await;
+        if store.by_id.get(&id).is_none_or(|t| t.deleted_at.is_some()) {
+            return Err(AppError::NotFound(id.to_string()));
+        }
+        self.touch(id);
+        Ok(store.notes.get(&id).cloned().unwrap_or_default())
+    }
+
+    async fn upsert_rate(&self, rate: ExchangeRate) -> Result<ExchangeRate, AppError> {
+        let mut store = self.data.write().await;
+        store.rates.insert((rate.base, rate.quote), rate.clone());
+        Ok(rate)
+    }
+
+    async fn get_rate(
+        &self,
+        base: Currency,
+        quote: Currency,
+    ) -> Result<Option<ExchangeRate>, AppError> {
+        let store = self.data.read().await;
+        Ok(store.rates.get(&(base, quote)).cloned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use chrono::Utc;
+    use crate::domain::models::Currency;
+
+    fn txn(key: &str) -> Transaction {
+        Transaction {
+            idempotency_key: Some(key.into()),
+            ..Transaction::test_pending(Utc::now())
+        }
+    }
+
+    #[tokio::test]
+    async fn scope_counts_follow_deletes_restores_and_evictions() {
+        let storage = InMemoryStorage::with_capacity(2);
+        let first = txn("count-1");
+        storage.insert(first.clone()).await.unwrap();
+        assert!(storage.insert_capped(txn("count-2"), 2).await.unwrap());
+        assert!(!storage.insert_capped(txn("count-3"), 2).await.unwrap());
+        assert_eq!(storage.count_by_scope(None).await.unwrap(), 2);
+
+        storage.soft_delete(first.id).await.unwrap();
+        assert_eq!(storage.count_by_scope(None).await.unwrap(), 1);
+        storage.restore(first.id).await.unwrap();
+        assert_eq!(storage.count_by_scope(None).await.unwrap(), 2);
+
+        // Full, so the least recently used transaction makes room.
+        storage.insert(txn("count-4")).await.unwrap();
+        assert_eq!(storage.count_by_scope(None).await.unwrap(), 2);
+        assert_eq!(storage.count_by_scope(Some("other")).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn key_index_matches_scan() {
+        let storage = InMemoryStorage::new();
+        let mut txns = Vec::new();
+        for i in 0..500 {
+            // Each key is reused five times, as happens once an idempotency TTL lapses.
+            let mut t = txn(&format!("key-{}", i % 100));
+            t.created_at += chrono::TimeDelta::milliseconds(i);
+            storage.insert(t.clone()).await.unwrap();
+            txns.push(t);
+        }
+        for t in txns.iter().step_by(7) {
+            let _ = storage.soft_delete(t.id).await;
+        }
+
+        let store = storage.data.read().await;
+        for i in 0..100 {
+            let key = format!("key-{}", i);
+            let scanned = store
+                .by_id
+                .values()
+                .filter(|t| t.idempotency_key.as_ref() == Some(&key) && t.deleted_at.is_none())
+                .max_by_key(|t| t.created_at)
+                .map(|t| t.id);
+            assert_eq!(store.by_key.get(&(None, key.clone())).copied(), scanned, "{}", key);
+        }
+    }
+
+    #[tokio::test]
+    async fn list_returns_creation_order() {
+        let storage = InMemoryStorage::new();
+        let base = Utc::now();
+        let created: Vec<_> = (0..3)
+            .map(|i| {
+                let mut t = txn(&format!("order-{}", i));
+                t.created_at = base + chrono::TimeDelta::seconds(i);
+                t
+            })
+            .collect();
+        // Insertion order differs from creation order.
+        for t in created.iter().rev() {
+            storage.insert(t.clone()).await.unwrap();
+        }
+
+        let listed = storage.list(&ListQuery::default()).await.unwrap();
+        let ids: Vec<_> = listed.items.iter().map(|t| t.id).collect();
+        assert_eq!(ids, created.iter().map(|t| t.id).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn ties_on_created_at_are_ordered_by_id() {
+        let storage = InMemoryStorage::new();
+        let at = Utc::now();
+        let mut tied: Vec<_> = (0..4)
+            .map(|i| {
+                let mut t = txn(&format!("tie-{}", i));
+                t.created_at = at;
+                t
+            })
+            .collect();
+        for t in &tied {
+            storage.insert(t.clone()).await.unwrap();
+        }
+        tied.sort_by_key(|t| t.id);
+        let expected: Vec<_> = tied.iter().map(|t| t.id).collect();
+
+        for _ in 0..3 {
+            let listed = storage.list(&ListQuery::default()).await.unwrap();
+            let ids: Vec<_> = listed.items.iter().map(|t| t.id).collect();
+            assert_eq!(ids, expected);
+        }
+
+        // Paging through the tie neither repeats nor skips anything.
+        let first = ListQuery {
+            limit: Some(2),
+            ..ListQuery::default()
+        };
+        let page1 = storage.list(&first).await.unwrap();
+        let page2 = storage
+            .list(&ListQuery {
+                after: page1.next_cursor,
+                ..first
+            })
+            .await
+            .unwrap();
+        let paged: Vec<_> = page1.items.iter().chain(&page2.items).map(|t| t.id).collect();
+        assert_eq!(paged, expected);
+    }
+
+    #[tokio::test]
+    async fn capacity_evicts_least_recently_accessed() {
+        let storage = InMemoryStorage::with_capacity(3);
+        let txns: Vec<_> = (0..4).map(|i| txn(&format!("key-{}", i))).collect();
+        for t in &txns[..3] {
+            storage.insert(t.clone()).await.unwrap();
+        }
+
+        storage.insert(txns[3].clone()).await.unwrap();
+
+        assert!(storage.get(txns[0].id).await.unwrap().is_none());
+        for t in &txns[1..] {
+            assert!(storage.get(t.id).await.unwrap().is_some());
+        }
+    }
+
+    #[tokio::test]
+    async fn access_refreshes_eviction_order() {
+        let storage = InMemoryStorage::with_capacity(2);
+        let (a, b, c) = (txn("a"), txn("b"), txn("c"));
+        storage.insert(a.clone()).await.unwrap();
+        storage.insert(b.clone()).await.unwrap();
+        storage.get(a.id).await.unwrap();
+
+        storage.insert(c.clone()).await.unwrap();
+
+        assert!(storage.get(a.id).await.unwrap().is_some());
+        assert!(storage.get(b.id).await.unwrap().is_none());
+        assert!(storage.get(c.id).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn wal_survives_reload() {
+        let path = std::env::temp_dir().join(format!("wal-{}.jsonl", Uuid::new_v4()));
+        let (mut kept, deleted) = (txn("kept"), txn("deleted"));
+        kept.created_with = Some(CreateFingerprint {
+            amount_minor: 500,
+            currency: Currency::Eur,
+            description: "original".into(),
+        });
+        {
+            let storage = InMemoryStorage::new().with_wal(&path).unwrap();
+            storage.insert(kept.clone()).await.unwrap();
+            storage.insert(deleted.clone()).await.unwrap();
+            storage
+                .update_status(kept.id, TransactionStatus::Completed, None, None)
+                .await
+                .unwrap();
+            storage.soft_delete(deleted.id).await.unwrap();
+        }
+        let mut wal = OpenOptions::new().append(true).open(&path).unwrap();
+        writeln!(wal, "{{not json").unwrap();
+
+        let reloaded = InMemoryStorage::new().with_wal(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let restored = reloaded.get(kept.id).await.unwrap().unwrap();
+        assert_eq!(restored.status, TransactionStatus::Completed);
+        assert_eq!(restored.version, 2);
+        assert_eq!(restored.amount_minor, kept.amount_minor);
+        assert_eq!(restored.created_with, kept.created_with);
+        let by_key = reloaded.find_by_idempotency_key(None, "kept").await.unwrap();
+        assert_eq!(by_key.map(|t| t.id), Some(kept.id));
+        assert!(reloaded.get(deleted.id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn reloading_keeps_capacity() {
+        let path = std::env::temp_dir().join(format!("wal-{}.jsonl", Uuid::new_v4()));
+        let (a, b, c) = (txn("a"), txn("b"), txn("c"));
+        {
+            let storage = InMemoryStorage::new().with_wal(&path).unwrap();
+            for t in [&a, &b, &c] {
+                storage.insert(t.clone()).await.unwrap();
+            }
+        }
+
+        let reloaded = InMemoryStorage::with_capacity(2).with_wal(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(reloaded.get(a.id).await.unwrap().is_none());
+        assert!(reloaded.get(b.id).await.unwrap().is_some());
+        assert!(reloaded.get(c.id).await.unwrap().is_some());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn failed_wal_append_leaves_the_store_unchanged() {
+        let storage = InMemoryStorage::new();
+        let original = txn("unlogged");
+        storage.insert(original.clone()).await.unwrap();
+        // Every write to /dev/full fails with ENOSPC.
+        let full = OpenOptions::new().append(true).open("/dev/full").unwrap();
+        let failing = InMemoryStorage {
+            wal: Some(Arc::new(tokio::sync::Mutex::new(File::from_std(full)))),
+            ..storage.clone()
+        };
+
+        let status = failing.update_status(original.id, TransactionStatus::Completed, None, None);
+        assert!(matches!(status.await, Err(AppError::Internal(_))));
+        assert!(failing.update_description(original.id, "changed".into()).await.is_err());
+        assert!(failing.update_amount(original.id, 1, None).await.is_err());
+        assert!(failing.void(original.id, None).await.is_err());
+        assert!(failing.soft_delete(original.id).await.is_err());
+
+        let unchanged = storage.get(original.id).await.unwrap().unwrap();
+        assert_eq!(unchanged.status, TransactionStatus::Pending);
+        assert_eq!(unchanged.version, original.version);
+        assert_eq!(unchanged.description, original.description);
+        let by_key = storage.find_by_idempotency_key(None, "unlogged").await.unwrap();
+        assert_eq!(by_key.map(|t| t.id), Some(original.id));
     }
 }
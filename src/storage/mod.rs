@@ -1,29 +1,240 @@
+pub mod deadline;
 pub mod memory;
+pub mod redis_idem;
+pub mod retry;
 
-use crate::domain::models::{Currency, Transaction, TransactionStatus};
+use crate::domain::models::{
+    Currency, ExchangeRate, Facet, Facets, Note, Transaction, TransactionStatus,
+};
 use crate::error::AppError;
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+use std::fmt;
 use std::future::Future;
+use std::str::FromStr;
 use uuid::Uuid;
 
+/// Position in the list order `(created_at, id)`. Pages resume strictly after the cursor, so
+/// inserts elsewhere in the order neither repeat nor skip items already paged past.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ListCursor {
+    pub created_at: DateTime<Utc>,
+    pub id: Uuid,
+}
+
+impl ListCursor {
+    pub fn after(txn: &Transaction) -> Self {
+        Self {
+            created_at: txn.created_at,
+            id: txn.id,
+        }
+    }
+}
+
+impl fmt::Display for ListCursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let nanos = self.created_at.timestamp_nanos_opt().unwrap_or_default();
+        write!(f, "{}_{}", nanos, self.id.simple())
+    }
+}
+
+impl FromStr for ListCursor {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || AppError::Validation(format!("Invalid cursor: {}", s));
+        let (nanos, id) = s.split_once('_').ok_or_else(invalid)?;
+        let created_at = DateTime::from_timestamp_nanos(nanos.parse().map_err(|_| invalid())?);
+        let id = id.parse().map_err(|_| invalid())?;
+        Ok(Self { created_at, id })
+    }
+}
+
+/// Filters and aggregations for `Storage::list`.
+#[derive(Debug, Clone, Default)]
+pub struct ListQuery {
+    /// Matches any of these statuses; empty matches all.
+    pub status: Vec<TransactionStatus>,
+    pub currency: Option<Currency>,
+    /// Case-insensitive description substring. SQL backends should translate this to
+    /// `description ILIKE '%' || $q || '%'` (escaping `%` and `_`) rather than filtering in Rust.
+    pub q: Option<String>,
+    /// Normalized (lowercase) tag the transaction must carry.
+    pub tag: Option<String>,
+    pub facets: Vec<Facet>,
+    /// Maximum items to return; `None` returns every match.
+    pub limit: Option<usize>,
+    pub after: Option<ListCursor>,
+    /// Matches to skip (after `after`, if set) before the page starts.
+    pub offset: usize,
+}
+
+impl ListQuery {
+    pub fn matches(&self, txn: &Transaction) -> bool {
+        (self.status.is_empty() || self.status.contains(&txn.status))
+            && self.currency.is_none_or(|c| txn.currency == c)
+            && self.q.as_deref().is_none_or(|q| {
+                txn.description.to_lowercase().contains(&q.to_lowercase())
+            })
+            && self.tag.as_ref().is_none_or(|tag| txn.tags.contains(tag))
+    }
+}
+
+/// Half-open `[from, to)` window on `created_at`. A missing bound is unbounded.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DateRange {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+}
+
+impl DateRange {
+    pub fn contains(&self, at: DateTime<Utc>) -> bool {
+        self.from.is_none_or(|from| at >= from) && self.to.is_none_or(|to| at < to)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ListResult {
+    /// One page of matches, oldest first.
+    pub items: Vec<Transaction>,
+    /// Matches across all pages.
+    pub total: usize,
+    /// Set when more matches follow this page.
+    pub next_cursor: Option<ListCursor>,
+    /// Present when the query requested at least one facet.
+    pub facets: Option<Facets>,
+    /// Set by the service for `page`/`per_page` requests.
+    pub page: Option<PageInfo>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PageInfo {
+    pub page: usize,
+    pub per_page: usize,
+    pub total_pages: usize,
+}
+
 pub trait Storage: Send + Sync + 'static {
+    /// Brings the backing schema up to date. Called once at startup, before serving; must be
+    /// idempotent, recording applied versions so a rerun applies nothing. SQL backends would run
+    /// their embedded migrations here. Stores without a schema keep the default no-op.
+    fn migrate(&self) -> impl Future<Output = Result<(), AppError>> + Send {
+        async { Ok(()) }
+    }
+
+    /// Checks the backing store is reachable, for readiness probes. Stores with nothing to
+    /// reach keep the default, which always succeeds.
+    fn health_check(&self) -> impl Future<Output = Result<(), AppError>> + Send {
+        async { Ok(()) }
+    }
+
     fn insert(&self, txn: Transaction) -> impl Future<Output = Result<(), AppError>> + Send;
 
+    /// Inserts `txn` unless its tenant's scope already holds `max_per_scope` live transactions,
+    /// checking under the same lock as the write so concurrent creates cannot overshoot.
+    /// Returns whether it was inserted.
+    fn insert_capped(
+        &self,
+        txn: Transaction,
+        max_per_scope: usize,
+    ) -> impl Future<Output = Result<bool, AppError>> + Send;
+
     fn get(&self, id: Uuid) -> impl Future<Output = Result<Option<Transaction>, AppError>> + Send;
 
+    /// The live transactions among `ids`, in the order given. Ids with no live transaction are
+    /// left out.
+    fn get_many(
+        &self,
+        ids: &[Uuid],
+    ) -> impl Future<Output = Result<Vec<Transaction>, AppError>> + Send;
+
+    /// Returns the most recently created transaction with this key in `tenant`'s scope; older
+    /// ones may exist once an idempotency TTL has lapsed.
     fn find_by_idempotency_key(
         &self,
+        tenant: Option<&str>,
         key: &str,
     ) -> impl Future<Output = Result<Option<Transaction>, AppError>> + Send;
 
-    fn list(
+    /// Number of live transactions in `tenant`'s idempotency scope; `None` is the unscoped one.
+    /// Backends keep this as a running count rather than scanning.
+    fn count_by_scope(
         &self,
-        status: Option<TransactionStatus>,
-        currency: Option<Currency>,
-    ) -> impl Future<Output = Result<Vec<Transaction>, AppError>> + Send;
+        tenant: Option<&str>,
+    ) -> impl Future<Output = Result<usize, AppError>> + Send;
+
+    /// Lists a page of live transactions matching `query` in `(created_at, id)` order. `total`
+    /// and any requested facets cover every match, not just the page.
+    fn list(&self, query: &ListQuery) -> impl Future<Output = Result<ListResult, AppError>> + Send;
+
+    /// The `n` most recently created live transactions, newest first.
+    fn recent(&self, n: usize) -> impl Future<Output = Result<Vec<Transaction>, AppError>> + Send;
+
+    /// Sums the amounts of live transactions in `status` created within `range`, per currency,
+    /// in minor units.
+    fn sum_by_status(
+        &self,
+        status: TransactionStatus,
+        range: DateRange,
+    ) -> impl Future<Output = Result<BTreeMap<Currency, i64>, AppError>> + Send;
 
+    /// Applies a status transition, recording `reason` as the cancellation or failure reason
+    /// when moving to `Cancelled` or `Failed`. When `expected_version` is set and does not match
+    /// the stored version, fails with `AppError::VersionConflict` carrying the current state.
     fn update_status(
         &self,
         id: Uuid,
         status: TransactionStatus,
+        expected_version: Option<u64>,
+        reason: Option<String>,
     ) -> impl Future<Output = Result<Transaction, AppError>> + Send;
+
+    fn update_description(
+        &self,
+        id: Uuid,
+        description: String,
+    ) -> impl Future<Output = Result<Transaction, AppError>> + Send;
+
+    /// Replaces the amount, and the fee withheld from it, in minor units, of a pending
+    /// transaction. Fails with `NotPending` otherwise.
+    fn update_amount(
+        &self,
+        id: Uuid,
+        amount_minor: i64,
+        fee_minor: Option<i64>,
+    ) -> impl Future<Output = Result<Transaction, AppError>> + Send;
+
+    /// Cancels a transaction, recording `reason`. Fails with `InvalidStateTransition` unless the
+    /// transaction may move to `Cancelled`.
+    fn void(
+        &self,
+        id: Uuid,
+        reason: Option<String>,
+    ) -> impl Future<Output = Result<Transaction, AppError>> + Send;
+
+    /// Marks a live transaction as deleted. Already-deleted transactions are `NotFound`.
+    fn soft_delete(&self, id: Uuid) -> impl Future<Output = Result<Transaction, AppError>> + Send;
+
+    /// Clears `deleted_at` on a soft-deleted transaction, making it visible again. Unknown ids
+    /// are `NotFound`; live transactions fail with `AppError::NotDeleted`.
+    fn restore(&self, id: Uuid) -> impl Future<Output = Result<Transaction, AppError>> + Send;
+
+    /// Appends a note to its live transaction. Fails with `NotFound` if there is none.
+    fn add_note(&self, note: Note) -> impl Future<Output = Result<Note, AppError>> + Send;
+
+    /// Notes on a live transaction, oldest first.
+    fn list_notes(&self, id: Uuid) -> impl Future<Output = Result<Vec<Note>, AppError>> + Send;
+
+    /// Stores the rate for its `(base, quote)` pair, replacing any earlier one.
+    fn upsert_rate(
+        &self,
+        rate: ExchangeRate,
+    ) -> impl Future<Output = Result<ExchangeRate, AppError>> + Send;
+
+    /// The stored rate for exactly this pair; inverses are not derived.
+    fn get_rate(
+        &self,
+        base: Currency,
+        quote: Currency,
+    ) -> impl Future<Output = Result<Option<ExchangeRate>, AppError>> + Send;
 }
@@ -0,0 +1,312 @@
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::time::Duration;
+
+use redis::aio::ConnectionManager;
+use redis::AsyncCommands;
+use uuid::Uuid;
+
+use crate::domain::models::{Currency, ExchangeRate, Note, Transaction, TransactionStatus};
+use crate::error::AppError;
+use crate::storage::{DateRange, ListQuery, ListResult, Storage};
+
+/// Shared `idempotency_key -> transaction id` mapping. Entries expire on their own, so a miss
+/// never means the key is unused.
+pub trait IdempotencyCache: Send + Sync + 'static {
+    fn get(&self, key: &str) -> impl Future<Output = Result<Option<Uuid>, AppError>> + Send;
+
+    fn set(&self, key: &str, id: Uuid) -> impl Future<Output = Result<(), AppError>> + Send;
+}
+
+/// `IdempotencyCache` backed by Redis `SET ... EX`.
+#[derive(Clone)]
+pub struct RedisCache {
+    conn: ConnectionManager,
+    ttl: Duration,
+}
+
+impl RedisCache {
+    const KEY_PREFIX: &'static str = "idempotency:";
+
+    pub async fn connect(url: &str, ttl: Duration) -> Result<Self, AppError> {
+        let client = redis::Client::open(url)
+            .map_err(|e| AppError::Internal(format!("Invalid Redis URL: {}", e)))?;
+        let conn = client
+            .get_connection_manager()
+            .await
+            .map_err(|e| AppError::Internal(format!("Redis connection failed: {}", e)))?;
+        Ok(Self { conn, ttl })
+    }
+}
+
+impl IdempotencyCache for RedisCache {
+    async fn get(&self, key: &str) -> Result<Option<Uuid>, AppError> {
+        let mut conn = self.conn.clone();
+        let raw: Option<String> = conn
+            .get(format!("{}{}", Self::KEY_PREFIX, key))
+            .await
+            .map_err(|e| AppError::Internal(format!("Redis GET failed: {}", e)))?;
+        Ok(raw.and_then(|id| id.parse().ok()))
+    }
+
+    async fn set(&self, key: &str, id: Uuid) -> Result<(), AppError> {
+        let mut conn = self.conn.clone();
+        conn.set_ex::<_, _, ()>(
+            format!("{}{}", Self::KEY_PREFIX, key),
+            id.to_string(),
+            self.ttl.as_secs().max(1),
+        )
+        .await
+        .map_err(|e| AppError::Internal(format!("Redis SET failed: {}", e)))
+    }
+}
+
+/// Cache key for `key` in `tenant`'s scope. Unscoped keys are stored as-is; the tenant's length
+/// prefix keeps tenants containing `:` from colliding.
+fn scoped_key(tenant: Option<&str>, key: &str) -> String {
+    match tenant {
+        Some(tenant) => format!("{}:{}:{}", tenant.len(), tenant, key),
+        None => key.to_string(),
+    }
+}
+
+/// Storage decorator that answers idempotency lookups from a shared cache before the primary
+/// store, so instances behind a load balancer agree on replays. Cache failures are logged and
+/// fall back to the primary store; they never fail the request.
+#[derive(Clone)]
+pub struct RedisIdempotency<S: Storage, C: IdempotencyCache = RedisCache> {
+    inner: S,
+    cache: C,
+}
+
+impl<S: Storage, C: IdempotencyCache> RedisIdempotency<S, C> {
+    pub fn new(inner: S, cache: C) -> Self {
+        Self { inner, cache }
+    }
+
+    async fn remember(&self, txn: &Transaction) {
+        let Some(key) = &txn.idempotency_key else {
+            return;
+        };
+        let key = scoped_key(txn.tenant_id.as_deref(), key);
+        if let Err(e) = self.cache.set(&key, txn.id).await {
+            tracing::warn!(error = %e, "failed to cache idempotency key");
+        }
+    }
+}
+
+impl<S: Storage, C: IdempotencyCache> Storage for RedisIdempotency<S, C> {
+    async fn migrate(&self) -> Result<(), AppError> {
+        self.inner.migrate().await
+    }
+
+    async fn health_check(&self) -> Result<(), AppError> {
+        self.inner.health_check().await
+    }
+
+    async fn insert(&self, txn: Transaction) -> Result<(), AppError> {
+        self.inner.insert(txn.clone()).await?;
+        self.remember(&txn).await;
+        Ok(())
+    }
+
+    async fn insert_capped(
+        &self,
+        txn: Transaction,
+        max_per_scope: usize,
+    ) -> Result<bool, AppError> {
+        let inserted = self.inner.insert_capped(txn.clone(), max_per_scope).await?;
+        if inserted {
+            self.remember(&txn).await;
+        }
+        Ok(inserted)
+    }
+
+    async fn get(&self, id: Uuid) -> Result<Option<Transaction>, AppError> {
+        self.inner.get(id).await
+    }
+
+    async fn get_many(&self, ids: &[Uuid]) -> Result<Vec<Transaction>, AppError> {
+        self.inner.get_many(ids).await
+    }
+
+    async fn find_by_idempotency_key(
+        &self,
+        tenant: Option<&str>,
+        key: &str,
+    ) -> Result<Option<Transaction>, AppError> {
+        match self.cache.get(&scoped_key(tenant, key)).await {
+            Ok(Some(id)) => {
+                // A deleted or evicted target falls through to the full lookup.
+                if let Some(txn) = self.inner.get(id).await? {
+                    if txn.idempotency_key.as_deref() == Some(key)
+                        && txn.tenant_id.as_deref() == tenant
+                    {
+                        return Ok(Some(txn));
+                    }
+                }
+            }
+            Ok(None) => {}
+            Err(e) => tracing::warn!(error = %e, "idempotency cache lookup failed"),
+        }
+
+        let found = self.inner.find_by_idempotency_key(tenant, key).await?;
+        if let Some(txn) = &found {
+            self.remember(txn).await;
+        }
+        Ok(found)
+    }
+
+    async fn count_by_scope(&self, tenant: Option<&str>) -> Result<usize, AppError> {
+        self.inner.count_by_scope(tenant).await
+    }
+
+    async fn list(&self, query: &ListQuery) -> Result<ListResult, AppError> {
+        self.inner.list(query).await
+    }
+
+    async fn recent(&self, n: usize) -> Result<Vec<Transaction>, AppError> {
+        self.inner.recent(n).await
+    }
+
+    async fn sum_by_status(
+        &self,
+        status: TransactionStatus,
+        range: DateRange,
+    ) -> Result<BTreeMap<Currency, i64>, AppError> {
+        self.inner.sum_by_status(status, range).await
+    }
+
+    async fn update_status(
+        &self,
+        id: Uuid,
+        status: TransactionStatus,
+        expected_version: Option<u64>,
+        reason: Option<String>,
+    ) -> Result<Transaction, AppError> {
+        self.inner.update_status(id, status, expected_version, reason).await
+    }
+
+    async fn update_description(
+        &self,
+        id: Uuid,
+        description: String,
+    ) -> Result<Transaction, AppError> {
+        self.inner.update_description(id, description).await
+    }
+
+    async fn update_amount(
+        &self,
+        id: Uuid,
+        amount_minor: i64,
+        fee_minor: Option<i64>,
+    ) -> Result<Transaction, AppError> {
+        self.inner.update_amount(id, amount_minor, fee_minor).await
+    }
+
+    async fn void(&self, id: Uuid, reason: Option<String>) -> Result<Transaction, AppError> {
+        self.inner.void(id, reason).await
+    }
+
+    async fn soft_delete(&self, id: Uuid) -> Result<Transaction, AppError> {
+        self.inner.soft_delete(id).await
+    }
+
+    async fn restore(&self, id: Uuid) -> Result<Transaction, AppError> {
+        self.inner.restore(id).await
+    }
+
+    async fn add_note(&self, note: Note) -> Result<Note, AppError> {
+        self.inner.add_note(note).await
+    }
+
+    async fn list_notes(&self, id: Uuid) -> Result<Vec<Note>, AppError> {
+        self.inner.list_notes(id).await
+    }
+
+    async fn upsert_rate(&self, rate: ExchangeRate) -> Result<ExchangeRate, AppError> {
+        self.inner.upsert_rate(rate).await
+    }
+
+    async fn get_rate(
+        &self,
+        base: Currency,
+        quote: Currency,
+    ) -> Result<Option<ExchangeRate>, AppError> {
+        self.inner.get_rate(base, quote).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::models::{CreateTransactionRequest, Currency};
+    use crate::domain::service::TransactionService;
+    use crate::storage::memory::InMemoryStorage;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct StubCache {
+        entries: Arc<Mutex<HashMap<String, Uuid>>>,
+        lookups: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl IdempotencyCache for StubCache {
+        async fn get(&self, key: &str) -> Result<Option<Uuid>, AppError> {
+            self.lookups.lock().unwrap().push(key.to_string());
+            Ok(self.entries.lock().unwrap().get(key).copied())
+        }
+
+        async fn set(&self, key: &str, id: Uuid) -> Result<(), AppError> {
+            self.entries.lock().unwrap().insert(key.to_string(), id);
+            Ok(())
+        }
+    }
+
+    fn create_req(key: &str) -> CreateTransactionRequest {
+        CreateTransactionRequest {
+            idempotency_key: Some(key.into()),
+            tenant_id: None,
+            amount: 10.0,
+            currency: Some(Currency::Usd),
+            description: "cached".into(),
+            expires_at: None,
+            execute_at: None,
+            tags: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn replay_is_served_from_shared_cache() {
+        let cache = StubCache::default();
+        // Two service instances, as behind a load balancer, sharing the store and the cache.
+        let primary = InMemoryStorage::new();
+        let first = TransactionService::new(RedisIdempotency::new(primary.clone(), cache.clone()));
+        let second = TransactionService::new(RedisIdempotency::new(primary, cache.clone()));
+
+        let (created, _) = first.create(create_req("shared")).await.unwrap();
+        assert_eq!(cache.entries.lock().unwrap()["shared"], created.id);
+
+        let (replayed, was_created) = second.create(create_req("shared")).await.unwrap();
+        assert!(!was_created);
+        assert_eq!(replayed.id, created.id);
+        assert_eq!(cache.lookups.lock().unwrap().as_slice(), ["shared", "shared"]);
+    }
+
+    #[tokio::test]
+    async fn cache_miss_falls_back_and_populates() {
+        let cache = StubCache::default();
+        let primary = InMemoryStorage::new();
+        let (txn, _) = TransactionService::new(primary.clone())
+            .create(create_req("cold"))
+            .await
+            .unwrap();
+
+        let storage = RedisIdempotency::new(primary, cache.clone());
+        let found = storage.find_by_idempotency_key(None, "cold").await.unwrap();
+
+        assert_eq!(found.map(|t| t.id), Some(txn.id));
+        assert_eq!(cache.entries.lock().unwrap()["cold"], txn.id);
+    }
+}
@@ -0,0 +1,359 @@
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::time::Duration;
+
+use uuid::Uuid;
+
+use crate::domain::models::{Currency, ExchangeRate, Note, Transaction, TransactionStatus};
+use crate::error::AppError;
+use crate::storage::{DateRange, ListQuery, ListResult, Storage};
+
+#[derive(Debug, Clone)]
+pub struct RetryConfig {
+    /// Attempts after the first one; zero disables retrying.
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled for each one after.
+    pub initial_backoff: Duration,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(50),
+        }
+    }
+}
+
+/// Decorator retrying `insert`, `get` and `update_status` on `AppError::Internal` with
+/// exponential backoff. Every other error is returned immediately, as are all other operations.
+#[derive(Debug, Clone)]
+pub struct RetryingStorage<S: Storage> {
+    inner: S,
+    config: RetryConfig,
+}
+
+impl<S: Storage> RetryingStorage<S> {
+    pub fn new(inner: S, config: RetryConfig) -> Self {
+        Self { inner, config }
+    }
+
+    async fn retry<T, F, Fut>(&self, op: &str, mut attempt: F) -> Result<T, AppError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, AppError>>,
+    {
+        let mut backoff = self.config.initial_backoff;
+        let mut retries = 0;
+        loop {
+            match attempt().await {
+                Err(AppError::Internal(msg)) if retries < self.config.max_retries => {
+                    retries += 1;
+                    tracing::warn!(op, retries, error = %msg, "retrying storage operation");
+                    tokio::time::sleep(backoff).await;
+                    backoff *= 2;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+impl<S: Storage> Storage for RetryingStorage<S> {
+    async fn migrate(&self) -> Result<(), AppError> {
+        self.inner.migrate().await
+    }
+
+    async fn health_check(&self) -> Result<(), AppError> {
+        self.inner.health_check().await
+    }
+
+    async fn insert(&self, txn: Transaction) -> Result<(), AppError> {
+        self.retry("insert", || self.inner.insert(txn.clone())).await
+    }
+
+    async fn insert_capped(
+        &self,
+        txn: Transaction,
+        max_per_scope: usize,
+    ) -> Result<bool, AppError> {
+        self.retry("insert_capped", || {
+            self.inner.insert_capped(txn.clone(), max_per_scope)
+        })
+        .await
+    }
+
+    async fn get(&self, id: Uuid) -> Result<Option<Transaction>, AppError> {
+        self.retry("get", || self.inner.get(id)).await
+    }
+
+    async fn get_many(&self, ids: &[Uuid]) -> Result<Vec<Transaction>, AppError> {
+        self.inner.get_many(ids).await
+    }
+
+    async fn find_by_idempotency_key(
+        &self,
+        tenant: Option<&str>,
+        key: &str,
+    ) -> Result<Option<Transaction>, AppError> {
+        self.inner.find_by_idempotency_key(tenant, key).await
+    }
+
+    async fn count_by_scope(&self, tenant: Option<&str>) -> Result<usize, AppError> {
+        self.inner.count_by_scope(tenant).await
+    }
+
+    async fn list(&self, query: &ListQuery) -> Result<ListResult, AppError> {
+        self.inner.list(query).await
+    }
+
+    async fn recent(&self, n: usize) -> Result<Vec<Transaction>, AppError> {
+        self.inner.recent(n).await
+    }
+
+    async fn sum_by_status(
+        &self,
+        status: TransactionStatus,
+        range: DateRange,
+    ) -> Result<BTreeMap<Currency, i64>, AppError> {
+        self.inner.sum_by_status(status, range).await
+    }
+
+    async fn update_status(
+        &self,
+        id: Uuid,
+        status: TransactionStatus,
+        expected_version: Option<u64>,
+        reason: Option<String>,
+    ) -> Result<Transaction, AppError> {
+        self.retry("update_status", || {
+            self.inner.update_status(id, status, expected_version, reason.clone())
+        })
+        .await
+    }
+
+    async fn update_description(
+        &self,
+        id: Uuid,
+        description: String,
+    ) -> Result<Transaction, AppError> {
+        self.inner.update_description(id, description).await
+    }
+
+    async fn update_amount(
+        &self,
+        id: Uuid,
+        amount_minor: i64,
+        fee_minor: Option<i64>,
+    ) -> Result<Transaction, AppError> {
+        self.inner.update_amount(id, amount_minor, fee_minor).await
+    }
+
+    async fn void(&self, id: Uuid, reason: Option<String>) -> Result<Transaction, AppError> {
+        self.inner.void(id, reason).await
+    }
+
+    async fn soft_delete(&self, id: Uuid) -> Result<Transaction, AppError> {
+        self.inner.soft_delete(id).await
+    }
+
+    async fn restore(&self, id: Uuid) -> Result<Transaction, AppError> {
+        self.inner.restore(id).await
+    }
+
+    async fn add_note(&self, note: Note) -> Result<Note, AppError> {
+        self.inner.add_note(note).await
+    }
+
+    async fn list_notes(&self, id: Uuid) -> Result<Vec<Note>, AppError> {
+        self.inner.list_notes(id).await
+    }
+
+    async fn upsert_rate(&self, rate: ExchangeRate) -> Result<ExchangeRate, AppError> {
+        self.inner.upsert_rate(rate).await
+    }
+
+    async fn get_rate(
+        &self,
+        base: Currency,
+        quote: Currency,
+    ) -> Result<Option<ExchangeRate>, AppError> {
+        self.inner.get_rate(base, quote).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::memory::InMemoryStorage;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+
+    /// Fails `get` with `Internal` until `failures` is exhausted, counting every call.
+    #[derive(Clone, Default)]
+    struct FlakyStorage {
+        inner: InMemoryStorage,
+        failures: Arc<AtomicU32>,
+        calls: Arc<AtomicU32>,
+    }
+
+    impl Storage for FlakyStorage {
+        async fn insert(&self, txn: Transaction) -> Result<(), AppError> {
+            self.inner.insert(txn).await
+        }
+
+        async fn insert_capped(
+            &self,
+            txn: Transaction,
+            max_per_scope: usize,
+        ) -> Result<bool, AppError> {
+            self.inner.insert_capped(txn, max_per_scope).await
+        }
+
+        async fn get(&self, id: Uuid) -> Result<Option<Transaction>, AppError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            let remaining = self.failures.load(Ordering::SeqCst);
+            if remaining > 0 {
+                self.failures.store(remaining - 1, Ordering::SeqCst);
+                return Err(AppError::Internal("connection reset".into()));
+            }
+            self.inner.get(id).await
+        }
+
+        async fn get_many(&self, ids: &[Uuid]) -> Result<Vec<Transaction>, AppError> {
+            self.inner.get_many(ids).await
+        }
+
+        async fn find_by_idempotency_key(
+            &self,
+            tenant: Option<&str>,
+            key: &str,
+        ) -> Result<Option<Transaction>, AppError> {
+            self.inner.find_by_idempotency_key(tenant, key).await
+        }
+
+        async fn count_by_scope(&self, tenant: Option<&str>) -> Result<usize, AppError> {
+            self.inner.count_by_scope(tenant).await
+        }
+
+        async fn list(&self, query: &ListQuery) -> Result<ListResult, AppError> {
+            self.inner.list(query).await
+        }
+
+        async fn recent(&self, n: usize) -> Result<Vec<Transaction>, AppError> {
+            self.inner.recent(n).await
+        }
+
+        async fn sum_by_status(
+            &self,
+            status: TransactionStatus,
+            range: DateRange,
+        ) -> Result<BTreeMap<Currency, i64>, AppError> {
+            self.inner.sum_by_status(status, range).await
+        }
+
+        async fn update_status(
+            &self,
+            id: Uuid,
+            status: TransactionStatus,
+            expected_version: Option<u64>,
+            reason: Option<String>,
+        ) -> Result<Transaction, AppError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            self.inner.update_status(id, status, expected_version, reason).await
+        }
+
+        async fn update_description(
+            &self,
+            id: Uuid,
+            description: String,
+        ) -> Result<Transaction, AppError> {
+            self.inner.update_description(id, description).await
+        }
+
+        async fn update_amount(
+            &self,
+            id: Uuid,
+            amount_minor: i64,
+            fee_minor: Option<i64>,
+        ) -> Result<Transaction, AppError> {
+            self.inner.update_amount(id, amount_minor, fee_minor).await
+        }
+
+        async fn void(&self, id: Uuid, reason: Option<String>) -> Result<Transaction, AppError> {
+            self.inner.void(id, reason).await
+        }
+
+        async fn soft_delete(&self, id: Uuid) -> Result<Transaction, AppError> {
+            self.inner.soft_delete(id).await
+        }
+
+        async fn restore(&self, id: Uuid) -> Result<Transaction, AppError> {
+            self.inner.restore(id).await
+        }
+
+        async fn add_note(&self, note: Note) -> Result<Note, AppError> {
+            self.inner.add_note(note).await
+        }
+
+        async fn list_notes(&self, id: Uuid) -> Result<Vec<Note>, AppError> {
+            self.inner.list_notes(id).await
+        }
+
+        async fn upsert_rate(&self, rate: ExchangeRate) -> Result<ExchangeRate, AppError> {
+            self.inner.upsert_rate(rate).await
+        }
+
+        async fn get_rate(
+            &self,
+            base: Currency,
+            quote: Currency,
+        ) -> Result<Option<ExchangeRate>, AppError> {
+            self.inner.get_rate(base, quote).await
+        }
+    }
+
+    fn retrying(flaky: &FlakyStorage, max_retries: u32) -> RetryingStorage<FlakyStorage> {
+        RetryingStorage::new(
+            flaky.clone(),
+            RetryConfig {
+                max_retries,
+                initial_backoff: Duration::from_millis(1),
+            },
+        )
+    }
+
+    #[tokio::test]
+    async fn retries_internal_errors_until_success() {
+        let flaky = FlakyStorage::default();
+        flaky.failures.store(2, Ordering::SeqCst);
+
+        let result = retrying(&flaky, 3).get(Uuid::new_v4()).await;
+
+        assert!(matches!(result, Ok(None)));
+        assert_eq!(flaky.calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_max_retries() {
+        let flaky = FlakyStorage::default();
+        flaky.failures.store(5, Ordering::SeqCst);
+
+        let result = retrying(&flaky, 2).get(Uuid::new_v4()).await;
+
+        assert!(matches!(result, Err(AppError::Internal(_))));
+        assert_eq!(flaky.calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn other_errors_are_not_retried() {
+        let flaky = FlakyStorage::default();
+
+        let result = retrying(&flaky, 3)
+            .update_status(Uuid::new_v4(), TransactionStatus::Completed, None, None)
+            .await;
+
+        assert!(matches!(result, Err(AppError::NotFound(_))));
+        assert_eq!(flaky.calls.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,129 @@
+//! Tracing subscriber setup. Built with the `otel` feature and given an OTLP endpoint, spans are
+//! also exported to an OpenTelemetry collector over gRPC.
+
+use tracing::Subscriber;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::EnvFilter;
+
+pub const OTLP_ENDPOINT_ENV: &str = "OTEL_EXPORTER_OTLP_ENDPOINT";
+
+/// Flushes buffered spans when dropped. Keep it alive for the life of the process.
+#[derive(Default)]
+pub struct TelemetryGuard {
+    #[cfg(feature = "otel")]
+    provider: Option<opentelemetry_sdk::trace::SdkTracerProvider>,
+}
+
+impl Drop for TelemetryGuard {
+    fn drop(&mut self) {
+        #[cfg(feature = "otel")]
+        if let Some(provider) = self.provider.take() {
+            if let Err(e) = provider.shutdown() {
+                tracing::warn!(error = %e, "failed to flush OpenTelemetry spans");
+            }
+        }
+    }
+}
+
+/// JSON logs filtered by `RUST_LOG` (default `info`), plus OTLP export to `otlp_endpoint` when
+/// the `otel` feature is enabled. Without the feature the endpoint is ignored.
+pub fn subscriber(
+    otlp_endpoint: Option<&str>,
+) -> Result<(impl Subscriber + Send + Sync, TelemetryGuard), String> {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| "info".into());
+    let fmt = tracing_subscriber::fmt::layer().json();
+
+    #[cfg(feature = "otel")]
+    let (otel, guard) = match otlp_endpoint {
+        Some(endpoint) => {
+            let (layer, provider) = otel::layer(endpoint)?;
+            let guard = TelemetryGuard {
+                provider: Some(provider),
+            };
+            (Some(layer), guard)
+        }
+        None => (None, TelemetryGuard::default()),
+    };
+    #[cfg(not(feature = "otel"))]
+    let (otel, guard) = {
+        let _ = otlp_endpoint;
+        (None::<tracing_subscriber::layer::Identity>, TelemetryGuard::default())
+    };
+
+    let subscriber = tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt)
+        .with(otel);
+    Ok((subscriber, guard))
+}
+
+/// Installs the global subscriber, reading the endpoint from `OTEL_EXPORTER_OTLP_ENDPOINT`. An
+/// exporter that cannot be built is reported and skipped rather than stopping the server.
+pub fn init() -> TelemetryGuard {
+    let endpoint = std::env::var(OTLP_ENDPOINT_ENV).ok();
+    match subscriber(endpoint.as_deref()) {
+        Ok((subscriber, guard)) => {
+            subscriber.init();
+            if endpoint.is_some() && !cfg!(feature = "otel") {
+                tracing::warn!("{} is set but the otel feature is disabled", OTLP_ENDPOINT_ENV);
+            }
+            guard
+        }
+        Err(e) => {
+            let (subscriber, guard) = subscriber(None).expect("subscriber without OTLP");
+            subscriber.init();
+            tracing::error!(error = %e, "OpenTelemetry export disabled");
+            guard
+        }
+    }
+}
+
+#[cfg(feature = "otel")]
+mod otel {
+    use opentelemetry::trace::TracerProvider as _;
+    use opentelemetry_otlp::WithExportConfig;
+    use opentelemetry_sdk::trace::{SdkTracerProvider, Tracer};
+    use opentelemetry_sdk::Resource;
+    use tracing::Subscriber;
+    use tracing_opentelemetry::OpenTelemetryLayer;
+    use tracing_subscriber::registry::LookupSpan;
+
+    pub(super) fn layer<S>(
+        endpoint: &str,
+    ) -> Result<(OpenTelemetryLayer<S, Tracer>, SdkTracerProvider), String>
+    where
+        S: Subscriber + for<'span> LookupSpan<'span>,
+    {
+        let exporter = opentelemetry_otlp::SpanExporter::builder()
+            .with_tonic()
+            .with_endpoint(endpoint)
+            .build()
+            .map_err(|e| format!("OTLP exporter: {}", e))?;
+        let provider = SdkTracerProvider::builder()
+            .with_batch_exporter(exporter)
+            .with_resource(
+                Resource::builder()
+                    .with_service_name(env!("CARGO_PKG_NAME"))
+                    .build(),
+            )
+            .build();
+        let tracer = provider.tracer(env!("CARGO_PKG_NAME"));
+        Ok((tracing_opentelemetry::layer().with_tracer(tracer), provider))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn subscriber_initializes_with_an_endpoint() {
+        // Nothing listens here; export failures must not surface as panics.
+        let (subscriber, guard) = subscriber(Some("http://127.0.0.1:4317")).unwrap();
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info_span!("smoke").in_scope(|| tracing::info!("exported"));
+        });
+        drop(guard);
+    }
+}
@@ -1,7 +1,8 @@
 use axum::body::Body;
 use axum::http::{self, Request, StatusCode};
 use http_body_util::BodyExt;
-use async_backend_skeleton::api::build_router;
+use async_backend_skeleton::api::jwt::{Claims, JwtConfig};
+use async_backend_skeleton::api::{build_router, build_router_with_config, ApiConfig};
 use async_backend_skeleton::domain::service::TransactionService;
 use async_backend_skeleton::storage::memory::InMemoryStorage;
 use serde_json::{json, Value};
@@ -33,6 +34,8 @@ async fn health_check() {
     assert_eq!(resp.status(), StatusCode::OK);
     let json = body_json(resp.into_body()).await;
     assert_eq!(json["status"], "ok");
+    assert_eq!(json["version"], env!("CARGO_PKG_VERSION"));
+    assert!(json["uptime_seconds"].is_u64());
 }
 
 #[tokio::test]
@@ -40,26 +43,16 @@ async fn create_and_get_transaction() {
     let app = app();
 
     // Create
-    let create_resp = app
-        .clone()
-        .oneshot(
-            Request::builder()
-                .method(http::Method::POST)
-                .uri("/api/v1/transactions")
-                .header("content-type", "application/json")
-                .body(Body::from(
-                    json!({
-                        "idempotency_key": "txn-001",
-                        "amount": 150.75,
-                        "currency": "USD",
-                        "description": "Invoice payment"
-                    })
-                    .to_string(),
-                ))
-                .unwrap(),
-        )
-        .await
-        .unwrap();
+    let create_resp = post_create(
+        &app,
+        json!({
+            "idempotency_key": "txn-001",
+            "amount": 150.75,
+            "currency": "USD",
+            "description": "Invoice payment"
+        }),
+    )
+    .await;
 
     assert_eq!(create_resp.status(), StatusCode::CREATED);
     let create_body = body_json(create_resp.into_body()).await;
@@ -83,6 +76,31 @@ async fn create_and_get_transaction() {
     assert_eq!(get_body["data"]["id"], txn_id);
 }
 
+#[tokio::test]
+async fn create_sets_location_header() {
+    let app = app();
+    let payload = json!({
+        "idempotency_key": "loc-1",
+        "amount": 20.0,
+        "currency": "USD",
+        "description": "Location test"
+    });
+
+    let created = post_create(&app, payload.clone()).await;
+    assert_eq!(created.status(), StatusCode::CREATED);
+    let location = created.headers()[http::header::LOCATION]
+        .to_str()
+        .unwrap()
+        .to_string();
+    let body = body_json(created.into_body()).await;
+    let txn_id = body["data"]["id"].as_str().unwrap();
+    assert_eq!(location, format!("/api/v1/transactions/{}", txn_id));
+
+    let replayed = post_create(&app, payload).await;
+    assert_eq!(replayed.status(), StatusCode::OK);
+    assert_eq!(replayed.headers()[http::header::LOCATION], location.as_str());
+}
+
 #[tokio::test]
 async fn idempotent_create_returns_200() {
     let app = app();
@@ -91,58 +109,27 @@ async fn idempotent_create_returns_200() {
         "amount": 50.0,
         "currency": "EUR",
         "description": "Duplicate test"
-    })
-    .to_string();
+    });
 
-    let first = app
-        .clone()
-        .oneshot(
-            Request::builder()
-                .method(http::Method::POST)
-                .uri("/api/v1/transactions")
-                .header("content-type", "application/json")
-                .body(Body::from(payload.clone()))
-                .unwrap(),
-        )
-        .await
-        .unwrap();
+    let first = post_create(&app, payload.clone()).await;
     assert_eq!(first.status(), StatusCode::CREATED);
 
-    let second = app
-        .oneshot(
-            Request::builder()
-                .method(http::Method::POST)
-                .uri("/api/v1/transactions")
-                .header("content-type", "application/json")
-                .body(Body::from(payload))
-                .unwrap(),
-        )
-        .await
-        .unwrap();
+    let second = post_create(&app, payload).await;
     assert_eq!(second.status(), StatusCode::OK);
 }
 
 #[tokio::test]
 async fn invalid_amount_returns_400() {
-    let resp = app()
-        .oneshot(
-            Request::builder()
-                .method(http::Method::POST)
-                .uri("/api/v1/transactions")
-                .header("content-type", "application/json")
-                .body(Body::from(
-                    json!({
-                        "idempotency_key": "bad",
-                        "amount": -10.0,
-                        "currency": "USD",
-                        "description": "Negative"
-                    })
-                    .to_string(),
-                ))
-                .unwrap(),
-        )
-        .await
-        .unwrap();
+    let resp = post_create(
+        &app(),
+        json!({
+            "idempotency_key": "bad",
+            "amount": -10.0,
+            "currency": "USD",
+            "description": "Negative"
+        }),
+    )
+    .await;
 
     assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
 }
@@ -151,26 +138,16 @@ async fn invalid_amount_returns_400() {
 async fn state_transition_pending_to_completed() {
     let app = app();
 
-    let create_resp = app
-        .clone()
-        .oneshot(
-            Request::builder()
-                .method(http::Method::POST)
-                .uri("/api/v1/transactions")
-                .header("content-type", "application/json")
-                .body(Body::from(
-                    json!({
-                        "idempotency_key": "st-1",
-                        "amount": 100.0,
-                        "currency": "GBP",
-                        "description": "State test"
-                    })
-                    .to_string(),
-                ))
-                .unwrap(),
-        )
-        .await
-        .unwrap();
+    let create_resp = post_create(
+        &app,
+        json!({
+            "idempotency_key": "st-1",
+            "amount": 100.0,
+            "currency": "GBP",
+            "description": "State test"
+        }),
+    )
+    .await;
 
     let create_body = body_json(create_resp.into_body()).await;
     let txn_id = create_body["data"]["id"].as_str().unwrap();
@@ -197,26 +174,16 @@ async fn invalid_state_transition_returns_422() {
     let app = app();
 
     // Create
-    let create_resp = app
-        .clone()
-        .oneshot(
-            Request::builder()
-                .method(http::Method::POST)
-                .uri("/api/v1/transactions")
-                .header("content-type", "application/json")
-                .body(Body::from(
-                    json!({
-                        "idempotency_key": "st-2",
-                        "amount": 100.0,
-                        "currency": "USD",
-                        "description": "Transition test"
-                    })
-                    .to_string(),
-                ))
-                .unwrap(),
-        )
-        .await
-        .unwrap();
+    let create_resp = post_create(
+        &app,
+        json!({
+            "idempotency_key": "st-2",
+            "amount": 100.0,
+            "currency": "USD",
+            "description": "Transition test"
+        }),
+    )
+    .await;
     let create_body = body_json(create_resp.into_body()).await;
     let txn_id = create_body["data"]["id"].as_str().unwrap();
 
@@ -255,25 +222,16 @@ async fn list_transactions() {
 
     // Create two transactions
     for key in &["list-1", "list-2"] {
-        app.clone()
-            .oneshot(
-                Request::builder()
-                    .method(http::Method::POST)
-                    .uri("/api/v1/transactions")
-                    .header("content-type", "application/json")
-                    .body(Body::from(
-                        json!({
-                            "idempotency_key": key,
-                            "amount": 10.0,
-                            "currency": "USD",
-                            "description": "List test"
-                        })
-                        .to_string(),
-                    ))
-                    .unwrap(),
-            )
-            .await
-            .unwrap();
+        post_create(
+            &app,
+            json!({
+                "idempotency_key": key,
+                "amount": 10.0,
+                "currency": "USD",
+                "description": "List test"
+            }),
+        )
+        .await;
     }
 
     let list_resp = app
@@ -305,3 +263,2106 @@ async fn get_nonexistent_returns_404() {
 
     assert_eq!(resp.status(), StatusCode::NOT_FOUND);
 }
+
+const JWT_SECRET: &[u8] = b"test-secret";
+
+fn auth_app() -> axum::Router {
+    let service = TransactionService::new(InMemoryStorage::new());
+    let config = ApiConfig {
+        jwt: Some(JwtConfig::hs256(JWT_SECRET)),
+        ..ApiConfig::default()
+    };
+    build_router_with_config(service, config)
+}
+
+fn token(scope: &str) -> String {
+    let claims = Claims {
+        sub: "client-1".into(),
+        exp: (chrono::Utc::now().timestamp() + 3600) as usize,
+        scope: scope.into(),
+    };
+    jsonwebtoken::encode(
+        &jsonwebtoken::Header::default(),
+        &claims,
+        &jsonwebtoken::EncodingKey::from_secret(JWT_SECRET),
+    )
+    .unwrap()
+}
+
+fn authed_create(bearer: Option<&str>) -> Request<Body> {
+    let mut builder = Request::builder()
+        .method(http::Method::POST)
+        .uri("/api/v1/transactions")
+        .header("content-type", "application/json");
+    if let Some(bearer) = bearer {
+        builder = builder.header("authorization", format!("Bearer {}", bearer));
+    }
+    builder
+        .body(Body::from(
+            json!({
+                "idempotency_key": "jwt-1",
+                "amount": 10.0,
+                "currency": "USD",
+                "description": "Auth test"
+            })
+            .to_string(),
+        ))
+        .unwrap()
+}
+
+#[tokio::test]
+async fn create_with_write_scope_succeeds() {
+    let resp = auth_app()
+        .oneshot(authed_create(Some(&token("transactions:read transactions:write"))))
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::CREATED);
+}
+
+#[tokio::test]
+async fn create_with_read_only_scope_returns_403() {
+    let resp = auth_app()
+        .oneshot(authed_create(Some(&token("transactions:read"))))
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn create_with_invalid_token_returns_401() {
+    let app = auth_app();
+    let resp = app
+        .clone()
+        .oneshot(authed_create(Some("not-a-jwt")))
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+    let resp = app.oneshot(authed_create(None)).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn health_does_not_require_token() {
+    let resp = auth_app()
+        .oneshot(Request::builder().uri("/health").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn stale_version_patch_returns_current_state() {
+    let app = app();
+
+    let create_resp = post_create(
+        &app,
+        json!({
+            "idempotency_key": "cas-1",
+            "amount": 75.0,
+            "currency": "USD",
+            "description": "CAS test"
+        }),
+    )
+    .await;
+    let create_body = body_json(create_resp.into_body()).await;
+    let txn_id = create_body["data"]["id"].as_str().unwrap().to_string();
+    assert_eq!(create_body["data"]["version"], 1);
+
+    let patch = |body: Value| {
+        Request::builder()
+            .method(http::Method::PATCH)
+            .uri(format!("/api/v1/transactions/{}/status", txn_id))
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+
+    let ok = app
+        .clone()
+        .oneshot(patch(json!({"status": "COMPLETED", "expected_version": 1})))
+        .await
+        .unwrap();
+    assert_eq!(ok.status(), StatusCode::OK);
+
+    let stale = app
+        .oneshot(patch(json!({"status": "FAILED", "expected_version": 1, "reason": "late"})))
+        .await
+        .unwrap();
+    assert_eq!(stale.status(), StatusCode::CONFLICT);
+    let body = body_json(stale.into_body()).await;
+    assert_eq!(body["error"]["current"]["id"], txn_id.as_str());
+    assert_eq!(body["error"]["current"]["status"], "COMPLETED");
+    assert_eq!(body["error"]["current"]["version"], 2);
+}
+
+#[tokio::test]
+async fn get_by_idempotency_key() {
+    let app = app();
+
+    let create_resp = post_create(
+        &app,
+        json!({
+            "idempotency_key": "by-key-1",
+            "amount": 12.5,
+            "currency": "EUR",
+            "description": "Lookup test"
+        }),
+    )
+    .await;
+    let create_body = body_json(create_resp.into_body()).await;
+
+    let found = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/api/v1/transactions/by-key/by-key-1")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(found.status(), StatusCode::OK);
+    let found_body = body_json(found.into_body()).await;
+    assert_eq!(found_body["data"]["id"], create_body["data"]["id"]);
+
+    let missing = app
+        .oneshot(
+            Request::builder()
+                .uri("/api/v1/transactions/by-key/unknown")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(missing.status(), StatusCode::NOT_FOUND);
+}
+
+fn batch_request(keys: &[&str]) -> Request<Body> {
+    let items: Vec<Value> = keys
+        .iter()
+        .map(|key| {
+            json!({
+                "idempotency_key": key,
+                "amount": if key.starts_with("bad") { -1.0 } else { 10.0 },
+                "currency": "USD",
+                "description": "Batch item"
+            })
+        })
+        .collect();
+    Request::builder()
+        .method(http::Method::POST)
+        .uri("/api/v1/transactions/batch")
+        .header("content-type", "application/json")
+        .body(Body::from(json!({ "items": items }).to_string()))
+        .unwrap()
+}
+
+#[tokio::test]
+async fn small_batch_returns_buffered_results() {
+    let resp = app()
+        .oneshot(batch_request(&["b-1", "bad-2"]))
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body = body_json(resp.into_body()).await;
+    let results = body["data"].as_array().unwrap();
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0]["status"], 201);
+    assert_eq!(results[1]["status"], 400);
+}
+
+#[tokio::test]
+async fn large_batch_streams_ndjson() {
+    let service = TransactionService::new(InMemoryStorage::new());
+    let config = ApiConfig {
+        batch_stream_threshold: 2,
+        ..ApiConfig::default()
+    };
+    let app = build_router_with_config(service, config);
+
+    let resp = app
+        .oneshot(batch_request(&["s-1", "s-2", "bad-3", "s-4"]))
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers()["content-type"], "application/x-ndjson");
+
+    let bytes = resp.into_body().collect().await.unwrap().to_bytes();
+    let lines: Vec<Value> = std::str::from_utf8(&bytes)
+        .unwrap()
+        .lines()
+        .map(|l| serde_json::from_str(l).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 4);
+    for (i, line) in lines.iter().enumerate() {
+        assert_eq!(line["index"], i);
+    }
+    assert_eq!(lines[0]["status"], 201);
+    assert_eq!(lines[2]["status"], 400);
+    assert!(lines[2]["error"]["message"].is_string());
+}
+
+#[tokio::test]
+async fn disabled_currency_rejects_creates_but_allows_reads() {
+    let app = app();
+    let create = |key: &str| {
+        json!({
+            "idempotency_key": key,
+            "amount": 30.0,
+            "currency": "GBP",
+            "description": "Toggle test"
+        })
+    };
+
+    let created = post_create(&app, create("fx-1")).await;
+    assert_eq!(created.status(), StatusCode::CREATED);
+    let txn_id = body_json(created.into_body()).await["data"]["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    let toggle = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(http::Method::PUT)
+                .uri("/api/v1/admin/currencies/GBP")
+                .header("content-type", "application/json")
+                .body(Body::from(json!({"enabled": false}).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(toggle.status(), StatusCode::OK);
+
+    let rejected = post_create(&app, create("fx-2")).await;
+    assert_eq!(rejected.status(), StatusCode::BAD_REQUEST);
+    let body = body_json(rejected.into_body()).await;
+    assert!(body["error"]["message"].as_str().unwrap().contains("GBP"));
+
+    let read = app
+        .oneshot(
+            Request::builder()
+                .uri(format!("/api/v1/transactions/{}", txn_id))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(read.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn delete_with_idempotency_key_replays_outcome() {
+    let app = app();
+
+    let create_resp = post_create(
+        &app,
+        json!({
+            "idempotency_key": "del-1",
+            "amount": 5.0,
+            "currency": "USD",
+            "description": "Delete test"
+        }),
+    )
+    .await;
+    let txn_id = body_json(create_resp.into_body()).await["data"]["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    let delete = |key: Option<&str>| {
+        let mut builder = Request::builder()
+            .method(http::Method::DELETE)
+            .uri(format!("/api/v1/transactions/{}", txn_id));
+        if let Some(key) = key {
+            builder = builder.header("delete-idempotency-key", key);
+        }
+        builder.body(Body::empty()).unwrap()
+    };
+
+    let first = app.clone().oneshot(delete(Some("dk-1"))).await.unwrap();
+    assert_eq!(first.status(), StatusCode::OK);
+    let first_body = body_json(first.into_body()).await;
+
+    let replay = app.clone().oneshot(delete(Some("dk-1"))).await.unwrap();
+    assert_eq!(replay.status(), StatusCode::OK);
+    assert_eq!(body_json(replay.into_body()).await, first_body);
+
+    let unkeyed = app.clone().oneshot(delete(None)).await.unwrap();
+    assert_eq!(unkeyed.status(), StatusCode::NOT_FOUND);
+
+    let get = app
+        .oneshot(
+            Request::builder()
+                .uri(format!("/api/v1/transactions/{}", txn_id))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(get.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn oversized_body_returns_413_json() {
+    let service = TransactionService::new(InMemoryStorage::new());
+    let config = ApiConfig {
+        max_body_bytes: 1024,
+        ..ApiConfig::default()
+    };
+    let app = build_router_with_config(service, config);
+    let payload = json!({
+        "idempotency_key": "big",
+        "amount": 1.0,
+        "currency": "USD",
+        "description": "x".repeat(4096)
+    })
+    .to_string();
+
+    let resp = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(http::Method::POST)
+                .uri("/api/v1/transactions")
+                .header("content-type", "application/json")
+                .header("content-length", payload.len())
+                .body(Body::from(payload.clone()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    let body = body_json(resp.into_body()).await;
+    assert_eq!(body["error"]["code"], 413);
+
+    // Without a declared length the limit trips while the extractor buffers the body.
+    let chunks = futures_util::stream::iter(vec![Ok::<_, std::io::Error>(payload)]);
+    let resp = app
+        .oneshot(
+            Request::builder()
+                .method(http::Method::POST)
+                .uri("/api/v1/transactions")
+                .header("content-type", "application/json")
+                .body(Body::from_stream(chunks))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    let body = body_json(resp.into_body()).await;
+    assert_eq!(body["error"]["code"], 413);
+}
+
+#[tokio::test]
+async fn patch_description_leaves_status_unchanged() {
+    let app = app();
+
+    let create_resp = post_create(
+        &app,
+        json!({
+            "idempotency_key": "desc-1",
+            "amount": 8.0,
+            "currency": "USD",
+            "description": "Invioce"
+        }),
+    )
+    .await;
+    let txn_id = body_json(create_resp.into_body()).await["data"]["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    let patch = |id: &str| {
+        Request::builder()
+            .method(http::Method::PATCH)
+            .uri(format!("/api/v1/transactions/{}", id))
+            .header("content-type", "application/json")
+            .body(Body::from(json!({"description": "Invoice"}).to_string()))
+            .unwrap()
+    };
+
+    let resp = app.clone().oneshot(patch(&txn_id)).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body = body_json(resp.into_body()).await;
+    assert_eq!(body["data"]["description"], "Invoice");
+    assert_eq!(body["data"]["status"], "PENDING");
+
+    let missing = app
+        .oneshot(patch("00000000-0000-0000-0000-000000000000"))
+        .await
+        .unwrap();
+    assert_eq!(missing.status(), StatusCode::NOT_FOUND);
+}
+
+/// POSTs `body` as JSON to the create endpoint.
+async fn post_create(app: &axum::Router, body: Value) -> axum::response::Response {
+    app.clone()
+        .oneshot(
+            Request::builder()
+                .method(http::Method::POST)
+                .uri("/api/v1/transactions")
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+}
+
+async fn create_txn(app: &axum::Router, key: &str, currency: &str, amount: f64) -> String {
+    let resp = post_create(
+        app,
+        json!({
+            "idempotency_key": key,
+            "amount": amount,
+            "currency": currency,
+            "description": "Test transaction"
+        }),
+    )
+    .await;
+    assert!(resp.status().is_success());
+    body_json(resp.into_body()).await["data"]["id"]
+        .as_str()
+        .unwrap()
+        .to_string()
+}
+
+async fn get_json(app: &axum::Router, uri: &str) -> (StatusCode, Value) {
+    let resp = app
+        .clone()
+        .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let status = resp.status();
+    (status, body_json(resp.into_body()).await)
+}
+
+#[tokio::test]
+async fn list_facets_cover_filtered_set() {
+    let app = app();
+    let completed = create_txn(&app, "fa-1", "USD", 10.0).await;
+    create_txn(&app, "fa-2", "USD", 20.0).await;
+    create_txn(&app, "fa-3", "EUR", 30.0).await;
+    app.clone()
+        .oneshot(
+            Request::builder()
+                .method(http::Method::PATCH)
+                .uri(format!("/api/v1/transactions/{}/status", completed))
+                .header("content-type", "application/json")
+                .body(Body::from(json!({"status": "COMPLETED"}).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let uri = "/api/v1/transactions?currency=USD&facets=status,currency";
+    let (status, body) = get_json(&app, uri).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"].as_array().unwrap().len(), 2);
+    assert_eq!(body["meta"]["facets"]["status"]["PENDING"], 1);
+    assert_eq!(body["meta"]["facets"]["status"]["COMPLETED"], 1);
+    assert_eq!(body["meta"]["facets"]["currency"], json!({"USD": 2}));
+
+    let (_, body) = get_json(&app, "/api/v1/transactions").await;
+    assert!(body["meta"].get("facets").is_none());
+}
+
+#[tokio::test]
+async fn state_machine_export() {
+    let app = app();
+    let (status, body) = get_json(&app, "/api/v1/admin/state-machine").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        body["data"]["transitions"]["PENDING"],
+        json!(["PROCESSING", "COMPLETED", "FAILED", "CANCELLED"])
+    );
+    assert_eq!(body["data"]["transitions"]["COMPLETED"], json!([]));
+
+    let resp = app
+        .oneshot(
+            Request::builder()
+                .uri("/api/v1/admin/state-machine?format=dot")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(resp.headers()["content-type"], "text/vnd.graphviz");
+    let bytes = resp.into_body().collect().await.unwrap().to_bytes();
+    let dot = String::from_utf8(bytes.to_vec()).unwrap();
+    assert!(dot.starts_with("digraph"));
+    assert!(dot.contains("PENDING -> CANCELLED;"));
+}
+
+#[tokio::test]
+async fn dry_run_create_is_not_listed() {
+    let app = app();
+    let resp = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(http::Method::POST)
+                .uri("/api/v1/transactions?dry_run=true")
+                .header("content-type", "application/json")
+                .body(Body::from(
+                    json!({
+                        "idempotency_key": "dry-1",
+                        "amount": 10.0,
+                        "currency": "USD",
+                        "description": "dry run"
+                    })
+                    .to_string(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert!(resp.headers().get("location").is_none());
+    let body = body_json(resp.into_body()).await;
+    assert_eq!(body["data"]["id"], uuid::Uuid::nil().to_string());
+    assert_eq!(body["data"]["status"], "PENDING");
+
+    let (_, body) = get_json(&app, "/api/v1/transactions").await;
+    assert!(body["data"].as_array().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn get_sets_etag_and_honors_if_none_match() {
+    let app = app();
+    let id = create_txn(&app, "etag-1", "USD", 10.0).await;
+    let uri = format!("/api/v1/transactions/{}", id);
+
+    let resp = app
+        .clone()
+        .oneshot(Request::builder().uri(&uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let etag = resp.headers()["etag"].clone();
+    assert_eq!(etag.to_str().unwrap(), format!("\"{}-1\"", id));
+
+    let resp = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri(&uri)
+                .header("if-none-match", etag.clone())
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::NOT_MODIFIED);
+    assert_eq!(resp.headers()["etag"], etag);
+    let bytes = resp.into_body().collect().await.unwrap().to_bytes();
+    assert!(bytes.is_empty());
+
+    let resp = app
+        .oneshot(
+            Request::builder()
+                .uri(&uri)
+                .header("if-none-match", "\"stale\"")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn etag_of_a_sparse_or_reformatted_body_does_not_match_the_full_one() {
+    let app = app();
+    let id = create_txn(&app, "etag-sparse-1", "USD", 10.0).await;
+    let uri = format!("/api/v1/transactions/{}", id);
+    let get = |uri: String, headers: Vec<(&'static str, String)>| {
+        let mut req = Request::builder().uri(uri);
+        for (name, value) in headers {
+            req = req.header(name, value);
+        }
+        app.clone().oneshot(req.body(Body::empty()).unwrap())
+    };
+    let etag_of = |resp: &axum::response::Response| {
+        resp.headers()["etag"].to_str().unwrap().to_string()
+    };
+
+    let sparse = get(format!("{}?fields=id", uri), vec![]).await.unwrap();
+    assert_eq!(sparse.headers()["vary"], "Accept, X-Amount-Format");
+    let sparse_etag = etag_of(&sparse);
+    let full = get(uri.clone(), vec![("if-none-match", sparse_etag.clone())])
+        .await
+        .unwrap();
+    assert_eq!(full.status(), StatusCode::OK);
+    let full_etag = etag_of(&full);
+    assert_ne!(full_etag, sparse_etag);
+
+    // The same selection written differently is the same representation.
+    let sparse_uri = format!("{}?fields=id,id", uri);
+    let again = get(sparse_uri, vec![("if-none-match", sparse_etag)])
+        .await
+        .unwrap();
+    assert_eq!(again.status(), StatusCode::NOT_MODIFIED);
+
+    let strings = vec![
+        ("x-amount-format", "string".to_string()),
+        ("if-none-match", full_etag.clone()),
+    ];
+    assert_eq!(get(uri.clone(), strings).await.unwrap().status(), StatusCode::OK);
+    let packed = vec![("accept", "application/msgpack".to_string())];
+    assert_ne!(etag_of(&get(uri, packed).await.unwrap()), full_etag);
+}
+
+#[tokio::test]
+async fn list_searches_description() {
+    let app = app();
+    for (key, description) in [
+        ("q-1", "Coffee beans"),
+        ("q-2", "Office CHAIRS"),
+        ("q-3", "Armchair repair"),
+    ] {
+        let resp = post_create(
+            &app,
+            json!({
+                "idempotency_key": key,
+                "amount": 10.0,
+                "currency": if key == "q-3" { "EUR" } else { "USD" },
+                "description": description
+            }),
+        )
+        .await;
+        assert_eq!(resp.status(), StatusCode::CREATED);
+    }
+
+    let (_, body) = get_json(&app, "/api/v1/transactions?q=coffee").await;
+    let items = body["data"].as_array().unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0]["description"], "Coffee beans");
+
+    let (_, body) = get_json(&app, "/api/v1/transactions?q=Chair").await;
+    assert_eq!(body["data"].as_array().unwrap().len(), 2);
+
+    let (_, body) = get_json(&app, "/api/v1/transactions?q=chair&currency=USD").await;
+    let items = body["data"].as_array().unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0]["description"], "Office CHAIRS");
+}
+
+#[tokio::test]
+async fn error_body_carries_type() {
+    let app = app();
+    let uri = format!("/api/v1/transactions/{}", uuid::Uuid::new_v4());
+    let (status, body) = get_json(&app, &uri).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["error"]["type"], "NOT_FOUND");
+    assert_eq!(body["error"]["code"], 404);
+}
+
+#[tokio::test]
+async fn void_pending_then_reject_second_void() {
+    let app = app();
+    let id = create_txn(&app, "void-1", "USD", 10.0).await;
+    let uri = format!("/api/v1/transactions/{}/void", id);
+
+    let resp = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(http::Method::POST)
+                .uri(&uri)
+                .header("content-type", "application/json")
+                .body(Body::from(json!({"reason": "customer request"}).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body = body_json(resp.into_body()).await;
+    assert_eq!(body["data"]["status"], "CANCELLED");
+    assert_eq!(body["data"]["cancellation_reason"], "customer request");
+
+    let resp = app
+        .oneshot(
+            Request::builder()
+                .method(http::Method::POST)
+                .uri(&uri)
+                .header("content-type", "application/json")
+                .body(Body::from(json!({"reason": "again"}).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[tokio::test]
+async fn void_without_reason_returns_400() {
+    let app = app();
+    let id = create_txn(&app, "void-no-reason", "USD", 10.0).await;
+    let resp = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(http::Method::POST)
+                .uri(format!("/api/v1/transactions/{}/void", id))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let body = body_json(resp.into_body()).await;
+    assert_eq!(body["error"]["type"], "VALIDATION_FAILED");
+
+    let (_, body) = get_json(&app, &format!("/api/v1/transactions/{}", id)).await;
+    assert_eq!(body["data"]["status"], "PENDING");
+}
+
+#[tokio::test]
+async fn stream_emits_created_event() {
+    let app = app();
+    let resp = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/api/v1/transactions/stream")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers()["content-type"], "text/event-stream");
+    let mut body = resp.into_body();
+
+    let id = create_txn(&app, "sse-1", "USD", 10.0).await;
+
+    let frame = tokio::time::timeout(std::time::Duration::from_secs(5), body.frame())
+        .await
+        .expect("event within timeout")
+        .unwrap()
+        .unwrap();
+    let text = String::from_utf8(frame.into_data().unwrap().to_vec()).unwrap();
+    assert!(text.starts_with("event: created\n"), "{}", text);
+    assert!(text.lines().any(|l| l == "id: 1"), "{}", text);
+    let data: Value = serde_json::from_str(
+        text.lines()
+            .find_map(|l| l.strip_prefix("data: "))
+            .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(data["id"], id.as_str());
+}
+
+#[tokio::test]
+async fn create_with_msgpack() {
+    let app = app();
+    let req = json!({
+        "idempotency_key": "msgpack-1",
+        "amount": 42.5,
+        "currency": "EUR",
+        "description": "packed"
+    });
+    let resp = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(http::Method::POST)
+                .uri("/api/v1/transactions")
+                .header("content-type", "application/msgpack")
+                .header("accept", "application/msgpack")
+                .body(Body::from(rmp_serde::to_vec_named(&req).unwrap()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    assert_eq!(resp.headers()["content-type"], "application/msgpack");
+    let bytes = resp.into_body().collect().await.unwrap().to_bytes();
+    let body: Value = rmp_serde::from_slice(&bytes).unwrap();
+    assert_eq!(body["data"]["amount"], 42.5);
+    assert_eq!(body["data"]["currency"], "EUR");
+
+    // Errors honor the negotiated format too; JSON stays the default.
+    let resp = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri(format!("/api/v1/transactions/{}", uuid::Uuid::new_v4()))
+                .header("accept", "application/msgpack")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    let bytes = resp.into_body().collect().await.unwrap().to_bytes();
+    let body: Value = rmp_serde::from_slice(&bytes).unwrap();
+    assert_eq!(body["error"]["type"], "NOT_FOUND");
+
+    let (status, body) = get_json(&app, "/api/v1/transactions/by-key/msgpack-1").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["description"], "packed");
+}
+
+#[tokio::test]
+async fn malformed_json_uses_error_shape() {
+    let app = app();
+    let post = |body: &'static str| {
+        Request::builder()
+            .method(http::Method::POST)
+            .uri("/api/v1/transactions")
+            .header("content-type", "application/json")
+            .body(Body::from(body))
+            .unwrap()
+    };
+
+    let resp = app.clone().oneshot(post(r#"{"amount": "abc"}"#)).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let body = body_json(resp.into_body()).await;
+    assert_eq!(body["error"]["type"], "VALIDATION_FAILED");
+    let message = body["error"]["message"].as_str().unwrap();
+    assert!(message.contains("amount"), "{}", message);
+    assert!(message.contains("line 1 column"), "{}", message);
+
+    let resp = app
+        .clone()
+        .oneshot(post(
+            r#"{"idempotency_key": "k", "amount": 1.0, "currency": "USD",
+                "description": "d", "memo": "x"}"#,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let body = body_json(resp.into_body()).await;
+    assert!(body["error"]["message"].as_str().unwrap().contains("unknown field `memo`"));
+
+    let resp = app.oneshot(post("{not json")).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(body_json(resp.into_body()).await["error"]["code"], 400);
+}
+
+#[tokio::test]
+async fn list_pages_with_cursor_and_meta() {
+    let app = app();
+    let mut ids = Vec::new();
+    for i in 0..5 {
+        ids.push(create_txn(&app, &format!("page-{}", i), "USD", 10.0).await);
+    }
+
+    let (_, body) = get_json(&app, "/api/v1/transactions?limit=2").await;
+    assert_eq!(body["meta"]["total"], 5);
+    assert_eq!(body["meta"]["limit"], 2);
+    let mut seen: Vec<String> = Vec::new();
+    let mut page = body;
+    loop {
+        seen.extend(
+            page["data"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|t| t["id"].as_str().unwrap().to_string()),
+        );
+        let Some(cursor) = page["meta"]["next_cursor"].as_str() else {
+            break;
+        };
+        let uri = format!("/api/v1/transactions?limit=2&cursor={}", cursor);
+        page = get_json(&app, &uri).await.1;
+    }
+    let mut expected = ids.clone();
+    expected.sort();
+    seen.sort();
+    assert_eq!(seen, expected);
+
+    let (_, body) = get_json(&app, &format!("/api/v1/transactions/{}", ids[0])).await;
+    assert!(body.get("meta").is_none());
+
+    let (status, _) = get_json(&app, "/api/v1/transactions?cursor=bogus").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn status_batch_mixes_outcomes() {
+    let app = app();
+    let pending = create_txn(&app, "sb-1", "USD", 10.0).await;
+    let completed = create_txn(&app, "sb-2", "USD", 10.0).await;
+    let patch = |id: &str| {
+        Request::builder()
+            .method(http::Method::PATCH)
+            .uri(format!("/api/v1/transactions/{}/status", id))
+            .header("content-type", "application/json")
+            .body(Body::from(json!({"status": "COMPLETED"}).to_string()))
+            .unwrap()
+    };
+    app.clone().oneshot(patch(&completed)).await.unwrap();
+
+    let resp = app
+        .oneshot(
+            Request::builder()
+                .method(http::Method::POST)
+                .uri("/api/v1/transactions/status-batch")
+                .header("content-type", "application/json")
+                .body(Body::from(
+                    json!({"ids": [pending, completed], "status": "COMPLETED"}).to_string(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body = body_json(resp.into_body()).await;
+    assert_eq!(body["data"][0]["result"], "updated");
+    assert_eq!(body["data"][0]["transaction"]["status"], "COMPLETED");
+    assert_eq!(body["data"][1]["id"], completed.as_str());
+    assert_eq!(body["data"][1]["result"], "invalid_transition");
+    assert_eq!(body["data"][1]["from"], "COMPLETED");
+}
+
+#[tokio::test]
+async fn large_list_is_gzip_compressed() {
+    let app = app();
+    for i in 0..20 {
+        create_txn(&app, &format!("gzip-{}", i), "USD", 10.0).await;
+    }
+    let get = |uri: &str| {
+        Request::builder()
+            .uri(uri)
+            .header("accept-encoding", "gzip")
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    let resp = app.clone().oneshot(get("/api/v1/transactions")).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers()["content-encoding"], "gzip");
+
+    let resp = app.clone().oneshot(get("/health")).await.unwrap();
+    assert!(resp.headers().get("content-encoding").is_none());
+
+    let uncompressed = build_router_with_config(
+        TransactionService::new(InMemoryStorage::new()),
+        ApiConfig {
+            compression: false,
+            ..ApiConfig::default()
+        },
+    );
+    for i in 0..20 {
+        create_txn(&uncompressed, &format!("plain-{}", i), "USD", 10.0).await;
+    }
+    let resp = uncompressed.oneshot(get("/api/v1/transactions")).await.unwrap();
+    assert!(resp.headers().get("content-encoding").is_none());
+}
+
+#[tokio::test]
+async fn list_filters_by_tag() {
+    let app = app();
+    for (key, tags) in [("tag-1", json!(["Payroll", "eu"])), ("tag-2", json!(["eu"]))] {
+        let resp = post_create(
+            &app,
+            json!({
+                "idempotency_key": key,
+                "amount": 10.0,
+                "currency": "EUR",
+                "description": "tagged",
+                "tags": tags
+            }),
+        )
+        .await;
+        assert_eq!(resp.status(), StatusCode::CREATED);
+    }
+
+    let (_, body) = get_json(&app, "/api/v1/transactions?tag=payroll").await;
+    let items = body["data"].as_array().unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0]["tags"], json!(["payroll", "eu"]));
+
+    let (_, body) = get_json(&app, "/api/v1/transactions?tag=eu").await;
+    assert_eq!(body["meta"]["total"], 2);
+}
+
+#[tokio::test]
+async fn create_without_idempotency_key_always_creates() {
+    let app = app();
+    let mut ids = Vec::new();
+    for _ in 0..2 {
+        let resp = post_create(
+            &app,
+            json!({"amount": 5.0, "currency": "USD", "description": "keyless"}),
+        )
+        .await;
+        assert_eq!(resp.status(), StatusCode::CREATED);
+        let body = body_json(resp.into_body()).await;
+        assert!(body["data"]["idempotency_key"].is_null());
+        ids.push(body["data"]["id"].clone());
+    }
+    assert_ne!(ids[0], ids[1]);
+}
+
+#[tokio::test]
+async fn balance_sums_completed_transactions() {
+    let app = app();
+    let completed = create_txn(&app, "bal-1", "USD", 12.5).await;
+    create_txn(&app, "bal-2", "USD", 99.0).await;
+    let resp = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(http::Method::PATCH)
+                .uri(format!("/api/v1/transactions/{}/status", completed))
+                .header("content-type", "application/json")
+                .body(Body::from(json!({"status": "COMPLETED"}).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let (status, body) = get_json(&app, "/api/v1/transactions/balance").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"], json!({"by_currency": {"USD": 12.5}}));
+}
+
+#[tokio::test]
+async fn idempotency_key_is_scoped_by_tenant_header() {
+    let app = app();
+    let create = |tenant: &str| {
+        Request::builder()
+            .method(http::Method::POST)
+            .uri("/api/v1/transactions")
+            .header("content-type", "application/json")
+            .header("x-tenant-id", tenant)
+            .body(Body::from(
+                json!({
+                    "idempotency_key": "tenant-key",
+                    "amount": 1.0,
+                    "currency": "USD",
+                    "description": "scoped"
+                })
+                .to_string(),
+            ))
+            .unwrap()
+    };
+
+    let first = app.clone().oneshot(create("acme")).await.unwrap();
+    assert_eq!(first.status(), StatusCode::CREATED);
+    let first = body_json(first.into_body()).await;
+    let second = app.clone().oneshot(create("globex")).await.unwrap();
+    assert_eq!(second.status(), StatusCode::CREATED);
+    let second = body_json(second.into_body()).await;
+    assert_ne!(first["data"]["id"], second["data"]["id"]);
+    assert_eq!(second["data"]["tenant_id"], "globex");
+
+    let replay = app.clone().oneshot(create("acme")).await.unwrap();
+    assert_eq!(replay.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn unknown_currency_lists_supported_codes() {
+    let app = app();
+    let resp = post_create(
+        &app,
+        json!({
+            "idempotency_key": "xyz",
+            "amount": 1.0,
+            "currency": "XYZ",
+            "description": "unknown currency"
+        }),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let body = body_json(resp.into_body()).await;
+    assert_eq!(body["error"]["type"], "VALIDATION_FAILED");
+    let message = body["error"]["message"].as_str().unwrap();
+    assert!(message.contains("\"XYZ\""), "{}", message);
+    assert!(message.contains("USD, EUR, GBP, JPY, CAD, AUD, CHF"), "{}", message);
+
+    let (status, body) = get_json(&app, "/api/v1/transactions?currency=XYZ").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"]["type"], "VALIDATION_FAILED");
+    assert!(body["error"]["message"].as_str().unwrap().contains("expected one of USD"));
+}
+
+#[tokio::test]
+async fn recent_lists_newest_first() {
+    let app = app();
+    let mut ids = Vec::new();
+    for key in ["recent-a", "recent-b", "recent-c"] {
+        ids.push(create_txn(&app, key, "USD", 3.0).await);
+    }
+
+    let (status, body) = get_json(&app, "/api/v1/transactions/recent?n=2").await;
+    assert_eq!(status, StatusCode::OK);
+    let listed: Vec<_> = body["data"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|t| t["id"].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(listed, [ids[2].clone(), ids[1].clone()]);
+}
+
+#[tokio::test]
+async fn replayed_create_sets_idempotency_replayed_header() {
+    let app = app();
+    let payload = json!({
+        "idempotency_key": "replay-hdr-1",
+        "amount": 15.0,
+        "currency": "USD",
+        "description": "Replay header"
+    });
+
+    let first = post_create(&app, payload.clone()).await;
+    assert_eq!(first.status(), StatusCode::CREATED);
+    assert!(first.headers().get("idempotency-replayed").is_none());
+
+    let second = post_create(&app, payload).await;
+    assert_eq!(second.status(), StatusCode::OK);
+    assert_eq!(second.headers()["idempotency-replayed"], "true");
+}
+
+#[tokio::test]
+async fn list_filters_by_any_of_several_statuses() {
+    let app = app();
+    let mut ids = Vec::new();
+    for key in ["multi-1", "multi-2", "multi-3"] {
+        let resp = post_create(
+            &app,
+            json!({
+                "idempotency_key": key,
+                "amount": 10.0,
+                "currency": "USD",
+                "description": "multi status"
+            }),
+        )
+        .await;
+        let body = body_json(resp.into_body()).await;
+        ids.push(body["data"]["id"].as_str().unwrap().to_string());
+    }
+    for (id, status) in [(&ids[1], "FAILED"), (&ids[2], "COMPLETED")] {
+        let body = json!({ "status": status, "reason": "test" });
+        let resp = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::PATCH)
+                    .uri(format!("/api/v1/transactions/{}/status", id))
+                    .header("content-type", "application/json")
+                    .body(Body::from(body.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    let (_, body) = get_json(&app, "/api/v1/transactions?status=PENDING,FAILED").await;
+    let found: Vec<&str> = body["data"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|t| t["id"].as_str().unwrap())
+        .collect();
+    assert_eq!(found, vec![ids[0].as_str(), ids[1].as_str()]);
+
+    let (_, body) = get_json(&app, "/api/v1/transactions?status=COMPLETED").await;
+    assert_eq!(body["meta"]["total"], 1);
+}
+
+#[tokio::test]
+async fn csv_import_reports_invalid_rows_without_aborting() {
+    let app = app();
+    let csv = "idempotency_key,amount,currency,description,tags\n\
+               imp-1,12.50,USD,Office chairs,furniture;q1\n\
+               imp-2,-3,USD,Refund?,\n\
+               imp-3,oops\n";
+    let resp = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(http::Method::POST)
+                .uri("/api/v1/transactions/import")
+                .header("content-type", "text/csv")
+                .body(Body::from(csv))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body = body_json(resp.into_body()).await;
+    let summary = &body["data"];
+    assert_eq!(summary["created"], 1);
+    assert_eq!(summary["skipped"], 0);
+    assert_eq!(summary["errored"], 2);
+    assert_eq!(summary["rows"][0]["row"], 2);
+    assert_eq!(summary["rows"][0]["outcome"], "created");
+    assert_eq!(summary["rows"][1]["row"], 3);
+    assert_eq!(summary["rows"][1]["outcome"], "error");
+    assert_eq!(summary["rows"][1]["error"]["type"], "VALIDATION_FAILED");
+    assert_eq!(summary["rows"][2]["row"], 4);
+    assert_eq!(summary["rows"][2]["outcome"], "error");
+
+    let id = summary["rows"][0]["id"].as_str().unwrap();
+    let (_, fetched) = get_json(&app, &format!("/api/v1/transactions/{}", id)).await;
+    assert_eq!(fetched["data"]["tags"], json!(["furniture", "q1"]));
+}
+
+#[tokio::test]
+async fn readiness_reports_in_flight_requests() {
+    let (status, body) = get_json(&app(), "/health/ready").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["status"], "ready");
+    assert_eq!(body["storage"], "ok");
+    assert_eq!(body["in_flight"], 0);
+    assert!(body["max_in_flight"].is_null());
+}
+
+#[tokio::test]
+async fn amount_adjustment_is_limited_to_pending() {
+    let app = app();
+    let send = |method: http::Method, uri: String, body: Value| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+    let created = app
+        .clone()
+        .oneshot(send(
+            http::Method::POST,
+            "/api/v1/transactions".into(),
+            json!({"idempotency_key": "adj-1", "amount": 10.0, "currency": "USD",
+                   "description": "adj"}),
+        ))
+        .await
+        .unwrap();
+    let created = body_json(created.into_body()).await;
+    let id = created["data"]["id"].as_str().unwrap().to_string();
+    let amount_uri = format!("/api/v1/transactions/{}/amount", id);
+
+    let resp = app
+        .clone()
+        .oneshot(send(http::Method::PATCH, amount_uri.clone(), json!({"amount": 12.5})))
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body = body_json(resp.into_body()).await;
+    assert_eq!(body["data"]["amount"], 12.5);
+
+    let resp = app
+        .clone()
+        .oneshot(send(
+            http::Method::PATCH,
+            format!("/api/v1/transactions/{}/status", id),
+            json!({"status": "COMPLETED"}),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let resp = app
+        .oneshot(send(http::Method::PATCH, amount_uri, json!({"amount": 20.0})))
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body = body_json(resp.into_body()).await;
+    assert_eq!(body["error"]["type"], "NOT_PENDING");
+}
+
+#[tokio::test]
+async fn offset_pagination_returns_numbered_pages() {
+    let app = app();
+    let mut ids = Vec::new();
+    for i in 0..5 {
+        let resp = post_create(
+            &app,
+            json!({
+                "idempotency_key": format!("page-{}", i),
+                "amount": 1.0 + i as f64,
+                "currency": "USD",
+                "description": "paged"
+            }),
+        )
+        .await;
+        let body = body_json(resp.into_body()).await;
+        ids.push(body["data"]["id"].clone());
+    }
+
+    let page_ids = |body: &Value| -> Vec<Value> {
+        body["data"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|t| t["id"].clone())
+            .collect()
+    };
+
+    let (status, body) = get_json(&app, "/api/v1/transactions?page=1&per_page=2").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(page_ids(&body), &ids[0..2]);
+    assert_eq!(body["meta"]["total"], 5);
+    assert_eq!(body["meta"]["page"], 1);
+    assert_eq!(body["meta"]["per_page"], 2);
+    assert_eq!(body["meta"]["total_pages"], 3);
+    assert!(body["meta"]["next_cursor"].is_null());
+
+    let (_, body) = get_json(&app, "/api/v1/transactions?page=2&per_page=2").await;
+    assert_eq!(page_ids(&body), &ids[2..4]);
+    assert_eq!(body["meta"]["page"], 2);
+
+    let (_, body) = get_json(&app, "/api/v1/transactions?page=3&per_page=2").await;
+    assert_eq!(page_ids(&body), &ids[4..]);
+
+    let (status, body) = get_json(&app, "/api/v1/transactions?page=2&cursor=abc").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"]["type"], "VALIDATION_FAILED");
+}
+
+#[tokio::test]
+async fn failing_without_reason_returns_400() {
+    let app = app();
+    let created = post_create(
+        &app,
+        json!({"idempotency_key": "fail-1", "amount": 9.0, "currency": "USD",
+               "description": "to fail"}),
+    )
+    .await;
+    let created = body_json(created.into_body()).await;
+    let id = created["data"]["id"].as_str().unwrap().to_string();
+    let patch = |body: Value| {
+        Request::builder()
+            .method(http::Method::PATCH)
+            .uri(format!("/api/v1/transactions/{}/status", id))
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+
+    let rejected = app
+        .clone()
+        .oneshot(patch(json!({"status": "FAILED"})))
+        .await
+        .unwrap();
+    assert_eq!(rejected.status(), StatusCode::BAD_REQUEST);
+
+    let accepted = app
+        .oneshot(patch(json!({"status": "FAILED", "reason": "insufficient funds"})))
+        .await
+        .unwrap();
+    assert_eq!(accepted.status(), StatusCode::OK);
+    let body = body_json(accepted.into_body()).await;
+    assert_eq!(body["data"]["status"], "FAILED");
+    assert_eq!(body["data"]["failure_reason"], "insufficient funds");
+}
+
+#[tokio::test]
+async fn reused_key_with_changed_amount_returns_409() {
+    let app = app();
+    let post = |amount: f64| {
+        json!({
+            "idempotency_key": "mismatch-1",
+            "amount": amount,
+            "currency": "USD",
+            "description": "Mismatch"
+        })
+    };
+
+    let first = post_create(&app, post(10.0)).await;
+    assert_eq!(first.status(), StatusCode::CREATED);
+
+    let changed = post_create(&app, post(11.0)).await;
+    assert_eq!(changed.status(), StatusCode::CONFLICT);
+    let body = body_json(changed.into_body()).await;
+    assert_eq!(body["error"]["type"], "IDEMPOTENCY_CONFLICT");
+
+    let same = post_create(&app, post(10.0)).await;
+    assert_eq!(same.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn retry_replays_after_the_transaction_was_edited() {
+    let app = app();
+    let original = json!({
+        "idempotency_key": "edited-1",
+        "amount": 10.0,
+        "currency": "USD",
+        "description": "Original"
+    });
+    let created = post_create(&app, original.clone()).await;
+    assert_eq!(created.status(), StatusCode::CREATED);
+    let id = body_json(created.into_body()).await["data"]["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let patch = |uri: String, body: Value| {
+        Request::builder()
+            .method(http::Method::PATCH)
+            .uri(uri)
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+
+    let edits = [
+        (format!("/api/v1/transactions/{}", id), json!({"description": "Edited"})),
+        (format!("/api/v1/transactions/{}/amount", id), json!({"amount": 12.5})),
+    ];
+    for (uri, body) in edits {
+        let resp = app.clone().oneshot(patch(uri, body)).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let retry = post_create(&app, original.clone()).await;
+        assert_eq!(retry.status(), StatusCode::OK);
+        assert_eq!(body_json(retry.into_body()).await["data"]["id"], id.as_str());
+    }
+}
+
+#[tokio::test]
+async fn notes_are_listed_in_the_order_they_were_added() {
+    let app = app();
+    let post = |uri: String, body: Value| {
+        Request::builder()
+            .method("POST")
+            .uri(uri)
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+    let created = app
+        .clone()
+        .oneshot(post(
+            "/api/v1/transactions".into(),
+            json!({"idempotency_key": "notes-1", "amount": 10.0, "currency": "USD",
+                   "description": "noted"}),
+        ))
+        .await
+        .unwrap();
+    let created = body_json(created.into_body()).await;
+    let id = created["data"]["id"].as_str().unwrap().to_string();
+    let notes_uri = format!("/api/v1/transactions/{}/notes", id);
+
+    for text in ["Customer called", "Refund promised"] {
+        let resp = app
+            .clone()
+            .oneshot(post(notes_uri.clone(), json!({"author": "agent-7", "text": text})))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::CREATED);
+    }
+    let resp = app
+        .clone()
+        .oneshot(post(notes_uri.clone(), json!({"author": "agent-7", "text": "  "})))
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    let (status, body) = get_json(&app, &notes_uri).await;
+    assert_eq!(status, StatusCode::OK);
+    let notes = body["data"].as_array().unwrap();
+    assert_eq!(notes.len(), 2);
+    assert_eq!(notes[0]["text"], "Customer called");
+    assert_eq!(notes[1]["text"], "Refund promised");
+    assert_eq!(notes[0]["transaction_id"], id.as_str());
+
+    let (_, txn) = get_json(&app, &format!("/api/v1/transactions/{}", id)).await;
+    assert_eq!(txn["data"]["version"], 1);
+
+    let missing = "/api/v1/transactions/00000000-0000-0000-0000-000000000000/notes";
+    let (status, _) = get_json(&app, missing).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn fields_param_returns_only_the_selected_fields() {
+    let app = app();
+    let id = create_txn(&app, "sparse-1", "USD", 12.5).await;
+
+    let (status, body) = get_json(&app, "/api/v1/transactions?fields=id,amount,status").await;
+    assert_eq!(status, StatusCode::OK);
+    let item = body["data"][0].as_object().unwrap();
+    let keys: Vec<&str> = item.keys().map(String::as_str).collect();
+    assert_eq!(keys, ["amount", "id", "status"]);
+    assert_eq!(item["amount"], 12.5);
+    assert!(body["meta"]["total"].is_number());
+
+    let uri = format!("/api/v1/transactions/{}?fields=description", id);
+    let (status, body) = get_json(&app, &uri).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"].as_object().unwrap().len(), 1);
+    assert!(body["data"].get("currency").is_none());
+
+    let (status, body) = get_json(&app, "/api/v1/transactions?fields=id,secret").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["error"]["message"].as_str().unwrap().contains("secret"));
+}
+
+#[tokio::test]
+async fn exchange_rate_can_be_set_and_fetched() {
+    let app = app();
+    let (status, body) = get_json(&app, "/api/v1/rates/USD/EUR").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["error"]["type"], "RATE_NOT_FOUND");
+
+    let resp = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("PUT")
+                .uri("/api/v1/rates/USD/EUR")
+                .header("content-type", "application/json")
+                .body(Body::from(
+                    json!({"rate": 0.92, "as_of": "2024-05-01T00:00:00Z"}).to_string(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let (status, body) = get_json(&app, "/api/v1/rates/USD/EUR").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["base"], "USD");
+    assert_eq!(body["data"]["quote"], "EUR");
+    assert_eq!(body["data"]["rate"], 0.92);
+    assert_eq!(body["data"]["as_of"], "2024-05-01T00:00:00Z");
+
+    let (status, _) = get_json(&app, "/api/v1/rates/EUR/USD").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn batch_get_returns_found_and_missing_ids() {
+    let app = app();
+    let first = create_txn(&app, "bget-1", "USD", 10.0).await;
+    let second = create_txn(&app, "bget-2", "EUR", 20.0).await;
+    let missing = "00000000-0000-0000-0000-000000000001";
+    let post = |ids: Value| {
+        Request::builder()
+            .method("POST")
+            .uri("/api/v1/transactions/batch-get")
+            .header("content-type", "application/json")
+            .body(Body::from(json!({ "ids": ids }).to_string()))
+            .unwrap()
+    };
+
+    let resp = app
+        .clone()
+        .oneshot(post(json!([second, missing, first, second])))
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body = body_json(resp.into_body()).await;
+    let found: Vec<&str> = body["data"]["found"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|t| t["id"].as_str().unwrap())
+        .collect();
+    assert_eq!(found, [second.as_str(), first.as_str()]);
+    assert_eq!(body["data"]["missing"], json!([missing]));
+
+    let too_many: Vec<String> = (0..1001).map(|_| missing.to_string()).collect();
+    let resp = app.oneshot(post(json!(too_many))).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn list_without_pagination_returns_default_page() {
+    let app = app();
+    for i in 0..150 {
+        create_txn(&app, &format!("default-page-{}", i), "USD", 1.0).await;
+    }
+
+    let (status, body) = get_json(&app, "/api/v1/transactions").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"].as_array().unwrap().len(), 100);
+    assert_eq!(body["meta"]["total"], 150);
+    assert_eq!(body["meta"]["has_more"], true);
+    let cursor = body["meta"]["next_cursor"].as_str().unwrap();
+
+    let (_, rest) = get_json(&app, &format!("/api/v1/transactions?cursor={}", cursor)).await;
+    assert_eq!(rest["data"].as_array().unwrap().len(), 50);
+    assert_eq!(rest["meta"]["has_more"], false);
+
+    let (_, all) = get_json(&app, "/api/v1/transactions?limit=150").await;
+    assert_eq!(all["data"].as_array().unwrap().len(), 150);
+    assert_eq!(all["meta"]["has_more"], false);
+}
+
+#[tokio::test]
+async fn unknown_route_returns_json_404() {
+    let app = app();
+    let (status, body) = get_json(&app, "/api/v1/nope").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["error"]["type"], "NOT_FOUND");
+    assert_eq!(body["error"]["message"], "No route for GET /api/v1/nope");
+}
+
+#[tokio::test]
+async fn wrong_method_on_known_route_returns_json_405() {
+    let resp = app()
+        .oneshot(
+            Request::builder()
+                .method("DELETE")
+                .uri("/api/v1/transactions")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::METHOD_NOT_ALLOWED);
+    assert_eq!(resp.headers()["allow"], "POST,GET,HEAD");
+    let body = body_json(resp.into_body()).await;
+    assert_eq!(body["error"]["type"], "METHOD_NOT_ALLOWED");
+    assert_eq!(body["error"]["code"], 405);
+}
+
+#[tokio::test]
+async fn base_path_prefixes_every_route() {
+    let config = ApiConfig {
+        base_path: Some("/payments/".into()),
+        ..ApiConfig::default()
+    };
+    let app = build_router_with_config(TransactionService::new(InMemoryStorage::new()), config);
+
+    let (status, body) = get_json(&app, "/payments/health").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["status"], "ok");
+    let (status, _) = get_json(&app, "/payments/api/v1/transactions").await;
+    assert_eq!(status, StatusCode::OK);
+
+    let created = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(http::Method::POST)
+                .uri("/payments/api/v1/transactions")
+                .header("content-type", "application/json")
+                .body(Body::from(
+                    json!({"amount": 5.0, "currency": "USD", "description": "prefixed"})
+                        .to_string(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(created.status(), StatusCode::CREATED);
+    let location = created.headers()[http::header::LOCATION]
+        .to_str()
+        .unwrap()
+        .to_string();
+    let id = body_json(created.into_body()).await["data"]["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    assert_eq!(location, format!("/payments/api/v1/transactions/{}", id));
+    let (status, _) = get_json(&app, &location).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, body) = get_json(&app, "/health").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["error"]["type"], "NOT_FOUND");
+}
+
+#[tokio::test]
+async fn lowercase_currency_is_accepted_on_create_and_filter() {
+    let app = app();
+    let id = create_txn(&app, "lower-1", "usd", 12.0).await;
+
+    let (status, body) = get_json(&app, &format!("/api/v1/transactions/{}", id)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["currency"], "USD");
+
+    let (status, body) = get_json(&app, "/api/v1/transactions?currency=Usd").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"].as_array().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn create_without_json_content_type_is_415() {
+    let body = json!({"idempotency_key": "ct-1", "amount": 5.0, "currency": "USD",
+                      "description": "no content type"});
+    for content_type in [None, Some("text/plain")] {
+        let mut req = Request::builder()
+            .method(http::Method::POST)
+            .uri("/api/v1/transactions");
+        if let Some(content_type) = content_type {
+            req = req.header("content-type", content_type);
+        }
+        let resp = app()
+            .oneshot(req.body(Body::from(body.to_string())).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        let body = body_json(resp.into_body()).await;
+        assert_eq!(body["error"]["type"], "UNSUPPORTED_MEDIA_TYPE");
+    }
+}
+
+#[tokio::test]
+async fn deleted_transaction_can_be_restored() {
+    let app = app();
+    let id = create_txn(&app, "restore-1", "USD", 15.0).await;
+    let send = |method: http::Method, uri: String| {
+        app.clone()
+            .oneshot(Request::builder().method(method).uri(uri).body(Body::empty()).unwrap())
+    };
+
+    let resp = send(http::Method::DELETE, format!("/api/v1/transactions/{}", id)).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let (_, body) = get_json(&app, "/api/v1/transactions").await;
+    assert_eq!(body["data"].as_array().unwrap().len(), 0);
+
+    let restore = format!("/api/v1/transactions/{}/restore", id);
+    let resp = send(http::Method::POST, restore.clone()).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let (_, body) = get_json(&app, "/api/v1/transactions").await;
+    assert_eq!(body["data"][0]["id"], id.as_str());
+
+    let resp = send(http::Method::POST, restore).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body = body_json(resp.into_body()).await;
+    assert_eq!(body["error"]["type"], "NOT_DELETED");
+
+    let unknown = "/api/v1/transactions/00000000-0000-0000-0000-000000000001/restore";
+    let resp = send(http::Method::POST, unknown.into()).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn list_meta_counts_statuses_across_all_pages() {
+    let app = app();
+    let completed = create_txn(&app, "counts-1", "USD", 1.0).await;
+    create_txn(&app, "counts-2", "USD", 2.0).await;
+    create_txn(&app, "counts-3", "EUR", 3.0).await;
+    let resp = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(http::Method::PATCH)
+                .uri(format!("/api/v1/transactions/{}/status", completed))
+                .header("content-type", "application/json")
+                .body(Body::from(json!({"status": "COMPLETED"}).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let (_, body) = get_json(&app, "/api/v1/transactions?limit=1").await;
+    assert_eq!(body["data"].as_array().unwrap().len(), 1);
+    assert_eq!(body["meta"]["status_counts"], json!({"PENDING": 2, "COMPLETED": 1}));
+    assert!(body["meta"].get("facets").is_none());
+
+    let (_, body) = get_json(&app, "/api/v1/transactions?currency=EUR").await;
+    assert_eq!(body["meta"]["status_counts"], json!({"PENDING": 1}));
+}
+
+#[tokio::test]
+async fn amount_format_header_quotes_amounts() {
+    let app = app();
+    let id = create_txn(&app, "fmt-1", "USD", 150.75).await;
+    let get = |format: &'static str| {
+        app.clone().oneshot(
+            Request::builder()
+                .uri(format!("/api/v1/transactions/{}", id))
+                .header("x-amount-format", format)
+                .body(Body::empty())
+                .unwrap(),
+        )
+    };
+
+    let body = body_json(get("string").await.unwrap().into_body()).await;
+    assert_eq!(body["data"]["amount"], "150.75");
+    let body = body_json(get("number").await.unwrap().into_body()).await;
+    assert_eq!(body["data"]["amount"], 150.75);
+}
+
+#[tokio::test]
+async fn amount_format_header_applies_to_streamed_batch_lines() {
+    let mut req = batch_request(&["fmt-batch-1", "fmt-batch-2"]);
+    req.headers_mut()
+        .insert("accept", "application/x-ndjson".parse().unwrap());
+    req.headers_mut()
+        .insert("x-amount-format", "string".parse().unwrap());
+    let resp = app().oneshot(req).await.unwrap();
+    assert_eq!(resp.headers()["content-type"], "application/x-ndjson");
+
+    let bytes = resp.into_body().collect().await.unwrap().to_bytes();
+    let lines: Vec<Value> = std::str::from_utf8(&bytes)
+        .unwrap()
+        .lines()
+        .map(|l| serde_json::from_str(l).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 2);
+    for line in &lines {
+        assert_eq!(line["data"]["amount"], "10.00");
+    }
+}
+
+#[tokio::test]
+async fn amount_format_header_applies_to_stream_events() {
+    let app = app();
+    let resp = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/api/v1/transactions/stream")
+                .header("x-amount-format", "string")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let mut body = resp.into_body();
+
+    create_txn(&app, "fmt-sse-1", "USD", 150.75).await;
+
+    let frame = tokio::time::timeout(std::time::Duration::from_secs(5), body.frame())
+        .await
+        .expect("event within timeout")
+        .unwrap()
+        .unwrap();
+    let text = String::from_utf8(frame.into_data().unwrap().to_vec()).unwrap();
+    let data: Value = serde_json::from_str(
+        text.lines()
+            .find_map(|l| l.strip_prefix("data: "))
+            .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(data["amount"], "150.75");
+}
+
+#[tokio::test]
+async fn whole_second_timestamps_apply_only_to_their_router() {
+    let config = ApiConfig {
+        whole_second_timestamps: true,
+        ..ApiConfig::default()
+    };
+    let whole = build_router_with_config(TransactionService::new(InMemoryStorage::new()), config);
+    let precise = app();
+    let is_whole = |value: &Value| !value.as_str().unwrap().contains('.');
+
+    let id = create_txn(&whole, "whole-1", "USD", 10.0).await;
+    let (_, body) = get_json(&whole, &format!("/api/v1/transactions/{}", id)).await;
+    assert!(is_whole(&body["data"]["created_at"]), "{}", body["data"]["created_at"]);
+    let id = create_txn(&precise, "precise-1", "USD", 10.0).await;
+    let (_, body) = get_json(&precise, &format!("/api/v1/transactions/{}", id)).await;
+    assert!(body["data"]["created_at"].as_str().unwrap().contains('.'));
+}
+
+#[tokio::test]
+async fn whole_second_timestamps_apply_to_streamed_bodies() {
+    let config = ApiConfig {
+        whole_second_timestamps: true,
+        ..ApiConfig::default()
+    };
+    let app = build_router_with_config(TransactionService::new(InMemoryStorage::new()), config);
+    let is_whole = |value: &Value| !value.as_str().unwrap().contains('.');
+
+    let events = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/api/v1/transactions/stream")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let mut events = events.into_body();
+
+    let mut req = batch_request(&["whole-batch-1"]);
+    req.headers_mut()
+        .insert("accept", "application/x-ndjson".parse().unwrap());
+    let resp = app.oneshot(req).await.unwrap();
+    let bytes = resp.into_body().collect().await.unwrap().to_bytes();
+    let line: Value = serde_json::from_slice(&bytes).unwrap();
+    assert!(is_whole(&line["data"]["created_at"]), "{}", line["data"]["created_at"]);
+
+    let frame = tokio::time::timeout(std::time::Duration::from_secs(5), events.frame())
+        .await
+        .expect("event within timeout")
+        .unwrap()
+        .unwrap();
+    let text = String::from_utf8(frame.into_data().unwrap().to_vec()).unwrap();
+    let data: Value = serde_json::from_str(
+        text.lines()
+            .find_map(|l| l.strip_prefix("data: "))
+            .unwrap(),
+    )
+    .unwrap();
+    assert!(is_whole(&data["created_at"]), "{}", data["created_at"]);
+}
+
+#[tokio::test]
+async fn cached_replay_returns_byte_identical_body() {
+    let service = TransactionService::new(InMemoryStorage::new());
+    let config = ApiConfig {
+        idempotency_response_ttl: Some(std::time::Duration::from_secs(60)),
+        ..ApiConfig::default()
+    };
+    let app = build_router_with_config(service, config);
+    let create = || {
+        post_create(
+            &app,
+            json!({
+                "idempotency_key": "cached-1",
+                "amount": 12.5,
+                "currency": "USD",
+                "description": "cached replay"
+            }),
+        )
+    };
+
+    let first = create().await;
+    assert_eq!(first.status(), StatusCode::CREATED);
+    let original = first.into_body().collect().await.unwrap().to_bytes();
+    let replay = create().await;
+    assert_eq!(replay.status(), StatusCode::OK);
+    assert_eq!(replay.headers()["idempotency-replayed"], "true");
+    assert_eq!(replay.into_body().collect().await.unwrap().to_bytes(), original);
+
+    // Changing the transaction drops the cached body.
+    let id = serde_json::from_slice::<Value>(&original).unwrap()["data"]["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let patch = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(http::Method::PATCH)
+                .uri(format!("/api/v1/transactions/{}/status", id))
+                .header("content-type", "application/json")
+                .body(Body::from(json!({"status": "COMPLETED"}).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(patch.status(), StatusCode::OK);
+    let replay = body_json(create().await.into_body()).await;
+    assert_eq!(replay["data"]["status"], "COMPLETED");
+}
+
+#[tokio::test]
+async fn cached_replay_ends_with_the_idempotency_key() {
+    let service = TransactionService::new(InMemoryStorage::new())
+        .with_idempotency_ttl(chrono::TimeDelta::milliseconds(50));
+    let config = ApiConfig {
+        idempotency_response_ttl: Some(std::time::Duration::from_secs(60)),
+        ..ApiConfig::default()
+    };
+    let app = build_router_with_config(service, config);
+    let body = json!({
+        "idempotency_key": "cached-lapsed-1",
+        "amount": 12.5,
+        "currency": "USD",
+        "description": "lapsing key"
+    });
+
+    let first = post_create(&app, body.clone()).await;
+    assert_eq!(first.status(), StatusCode::CREATED);
+    let first = body_json(first.into_body()).await;
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+
+    let second = post_create(&app, body).await;
+    assert_eq!(second.status(), StatusCode::CREATED);
+    let second = body_json(second.into_body()).await;
+    assert_ne!(second["data"]["id"], first["data"]["id"]);
+}
+
+#[tokio::test]
+async fn list_links_point_at_neighbouring_pages() {
+    let service = TransactionService::new(InMemoryStorage::new());
+    let config = ApiConfig {
+        public_base_url: Some("https://api.example.com/".into()),
+        ..ApiConfig::default()
+    };
+    let app = build_router_with_config(service, config);
+    for key in ["link-1", "link-2", "link-3"] {
+        create_txn(&app, key, "USD", 5.0).await;
+    }
+
+    let (_, body) = get_json(&app, "/api/v1/transactions?currency=USD&limit=2").await;
+    let links = &body["meta"]["links"];
+    assert_eq!(
+        links["self"],
+        "https://api.example.com/api/v1/transactions?currency=USD&limit=2"
+    );
+    let next = format!(
+        "https://api.example.com/api/v1/transactions?currency=USD&limit=2&cursor={}",
+        body["meta"]["next_cursor"].as_str().unwrap()
+    );
+    assert_eq!(links["next"], next.as_str());
+    assert!(links.get("prev").is_none());
+
+    let (_, body) = get_json(&app, "/api/v1/transactions?page=2&per_page=2").await;
+    let links = &body["meta"]["links"];
+    assert_eq!(links["prev"], "https://api.example.com/api/v1/transactions?per_page=2&page=1");
+    assert!(links.get("next").is_none());
+}
+
+#[tokio::test]
+async fn truncate_description_only_shortens_list_items() {
+    let app = app();
+    let resp = post_create(
+        &app,
+        json!({
+            "amount": 3.0,
+            "currency": "USD",
+            "description": "Quarterly invoice for consulting services"
+        }),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let id = body_json(resp.into_body()).await["data"]["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    let (status, list) = get_json(&app, "/api/v1/transactions?truncate_description=9").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(list["data"][0]["description"], "Quarterly…");
+
+    let (_, single) = get_json(&app, &format!("/api/v1/transactions/{}", id)).await;
+    assert_eq!(single["data"]["description"], "Quarterly invoice for consulting services");
+}
+
+#[tokio::test]
+async fn create_schema_requires_read_scope() {
+    let app = auth_app();
+    let get = |scope: &str| {
+        app.clone().oneshot(
+            Request::builder()
+                .uri("/api/v1/schema/create-transaction")
+                .header("authorization", format!("Bearer {}", token(scope)))
+                .body(Body::empty())
+                .unwrap(),
+        )
+    };
+
+    assert_eq!(get("transactions:write").await.unwrap().status(), StatusCode::FORBIDDEN);
+    assert_eq!(get("transactions:read").await.unwrap().status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn create_schema_lists_allowed_currencies() {
+    let (status, schema) = get_json(&app(), "/api/v1/schema/create-transaction").await;
+    assert_eq!(status, StatusCode::OK);
+    let currency = &schema["properties"]["currency"];
+    assert_eq!(
+        currency["description"],
+        "One of USD, EUR, GBP, JPY, CAD, AUD, CHF, in any letter case"
+    );
+    let pattern = regex::Regex::new(currency["pattern"].as_str().unwrap()).unwrap();
+    for code in ["USD", "usd", "Chf"] {
+        assert!(pattern.is_match(code), "{} should match", code);
+    }
+    assert!(!pattern.is_match("XYZ"));
+    assert_eq!(schema["properties"]["amount"]["exclusiveMinimum"], 0);
+    assert_eq!(schema["additionalProperties"], false);
+}